@@ -5,6 +5,7 @@
 #![warn(clippy::all, rust_2018_idioms, missing_docs)]
 use anyhow::Result;
 use clap::Parser;
+use std::time::Duration;
 
 use freezeout_bot::{
     Strategy,
@@ -29,13 +30,13 @@ impl Strategy for AlwaysCallOrCheck {
             // Raise preflop with a pair.
             if c1.rank() == c2.rank()
                 && state.board().is_empty()
-                && req.can_raise()
+                && let Some(raise) = req.option(PlayerAction::Raise)
                 && matches!(player.action, PlayerAction::None)
                 && matches!(player.action, PlayerAction::BigBlind)
                 && matches!(player.action, PlayerAction::SmallBlind)
                 && p > 0.2
             {
-                return (PlayerAction::Raise, req.min_raise);
+                return (PlayerAction::Raise, raise.min);
             }
         }
 
@@ -57,9 +58,15 @@ struct Cli {
     /// Number of clients to run.
     #[clap(long, short, value_parser = clap::value_parser!(u8).range(1..=5))]
     clients: u8,
-    /// The server WebSocker url (eg. ws://127.0.0.1:9871).
+    /// The server address or WebSocket url (eg. 127.0.0.1:9871 or ws://127.0.0.1:9871).
     #[clap(long, short, default_value = "ws://127.0.0.1:9871")]
     url: String,
+    /// Connect to a TLS server using a wss:// url.
+    #[clap(long)]
+    tls: bool,
+    /// Minimum interval in milliseconds between clients connections.
+    #[clap(long, default_value_t = 0)]
+    connect_interval: u64,
     /// Help long flag.
     #[clap(long, action = clap::ArgAction::HelpLong)]
     help: Option<bool>,
@@ -72,6 +79,8 @@ async fn main() -> Result<()> {
     let config = freezeout_bot::Config {
         clients: cli.clients,
         url: cli.url,
+        tls: cli.tls,
+        connect_interval: Duration::from_millis(cli.connect_interval),
     };
 
     freezeout_bot::run(config, || AlwaysCallOrCheck).await
@@ -8,7 +8,7 @@ use rand::prelude::*;
 use tokio::{
     signal,
     sync::{broadcast, mpsc},
-    time::{self, Duration},
+    time::{self, Duration, Instant},
 };
 
 use freezeout_core::{
@@ -30,8 +30,13 @@ pub trait Strategy: Send + 'static {
 pub struct Config {
     /// Number of clients to run.
     pub clients: u8,
-    /// The server WebSocket url.
+    /// The server address or WebSocket url, a wss:// url connects over TLS.
     pub url: String,
+    /// Connect over TLS, a `host:port` or `ws://` url is turned into a `wss://` url.
+    pub tls: bool,
+    /// Minimum interval between client connections, spreads the connections load
+    /// on the server when running many clients.
+    pub connect_interval: Duration,
 }
 
 static NICKNAMES: &[&str] = &["Alice", "Bob", "Carol", "Dave", "Frank", "Mike"];
@@ -51,11 +56,15 @@ where
     let (shutdown_broadcast_tx, _) = broadcast::channel(1);
     let (shutdown_complete_tx, mut shutdown_complete_rx) = mpsc::channel(1);
 
+    let url = connection::server_url(&config.url, config.tls);
+    let mut pacer = ConnectPacer::new(config.connect_interval);
     for idx in 0..config.clients {
+        pacer.wait().await;
+
         let mut client = Client::new(
             factory(),
             NICKNAMES[idx as usize % NICKNAMES.len()].to_string(),
-            &config.url,
+            &url,
             shutdown_broadcast_tx.subscribe(),
             shutdown_complete_tx.clone(),
         )
@@ -81,6 +90,30 @@ where
     Ok(())
 }
 
+/// Spaces connections by a minimum interval.
+struct ConnectPacer {
+    interval: Duration,
+    next: Option<Instant>,
+}
+
+impl ConnectPacer {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next: None,
+        }
+    }
+
+    /// Waits until the next connection can be opened.
+    async fn wait(&mut self) {
+        if let Some(next) = self.next {
+            time::sleep_until(next).await;
+        }
+
+        self.next = Some(Instant::now() + self.interval);
+    }
+}
+
 /// Poker client.
 struct Client<S: Strategy> {
     strategy: S,
@@ -147,6 +180,10 @@ impl<S: Strategy> Client<S> {
             } else {
                 state.handle_message(msg);
 
+                if let Some(msg) = state.take_resync_request() {
+                    self.send(msg).await?;
+                }
+
                 if let Some(req) = state.action_request() {
                     let delay = rand::rng().random_range(500..1500);
                     time::sleep(Duration::from_millis(delay)).await;
@@ -167,3 +204,25 @@ impl<S: Strategy> Client<S> {
         self.conn.send(&msg).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn connect_pacer() {
+        let interval = Duration::from_millis(20);
+        let mut pacer = ConnectPacer::new(interval);
+
+        // The first connection doesn't wait.
+        let start = Instant::now();
+        pacer.wait().await;
+        assert!(start.elapsed() < interval);
+
+        // Following connections are spaced by at least the interval.
+        for n in 1..=5 {
+            pacer.wait().await;
+            assert!(start.elapsed() >= interval * n);
+        }
+    }
+}
@@ -7,4 +7,6 @@
 mod client;
 pub use client::{Config, Strategy, run};
 
+pub mod odds;
+
 pub use freezeout_core as core;
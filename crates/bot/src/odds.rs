@@ -0,0 +1,113 @@
+// Copyright (C) 2025 Vince Vasta
+// SPDX-License-Identifier: Apache-2.0
+
+//! Pot odds helpers for bot strategies.
+use freezeout_core::{game_state::GameState, poker::Chips};
+
+/// Returns the fraction of the final pot the player has to put in to call.
+///
+/// The final pot includes the call, so calling is profitable in the long run if
+/// the player equity is greater than the returned value.
+pub fn pot_odds(call: Chips, pot: Chips) -> f64 {
+    let total = call.amount() as f64 + pot.amount() as f64;
+    if total == 0.0 {
+        0.0
+    } else {
+        call.amount() as f64 / total
+    }
+}
+
+/// Checks if the local player should call an all in given its estimated equity.
+///
+/// The `equity` is the probability of winning the hand between 0 and 1, with
+/// all chips in the middle there is no fold equity left so the decision only
+/// depends on the pot odds. Returns false if a call has not been requested.
+pub fn should_call_all_in(state: &GameState, equity: f64) -> bool {
+    state
+        .call_amount()
+        .is_some_and(|call| equity > pot_odds(call, state.total_pot()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use freezeout_core::{
+        crypto::SigningKey,
+        message::{ActionOption, BlindLevel, Message, PlayerAction, PlayerUpdate, SignedMessage},
+        poker::{PlayerCards, TableId},
+    };
+
+    #[test]
+    fn call_all_in_pot_odds() {
+        let sk = SigningKey::default();
+        let player_id = sk.verifying_key().peer_id();
+        let villain_id = SigningKey::default().verifying_key().peer_id();
+        let mut state = GameState::new(player_id.clone(), "alice".to_string());
+
+        let msgs = [
+            Message::TableJoined {
+                table_id: TableId::new_id(),
+                chips: Chips::new(200_000),
+                seats: 2,
+            },
+            Message::PlayerJoined {
+                player_id: villain_id.clone(),
+                nickname: "bob".to_string(),
+                chips: Chips::new(50_000),
+            },
+        ];
+        for msg in msgs {
+            state.handle_message(SignedMessage::new(&sk, msg));
+        }
+
+        // With no action request there is nothing to call.
+        assert!(!should_call_all_in(&state, 1.0));
+
+        // Villain goes all in for 50k into a 100k pot.
+        let update = |id, chips, bet| PlayerUpdate {
+            player_id: id,
+            chips: Chips::new(chips),
+            bet: Chips::new(bet),
+            action: PlayerAction::None,
+            action_timer: None,
+            time_bank: None,
+            cards: PlayerCards::None,
+            has_button: false,
+            is_active: true,
+            equity: None,
+        };
+        let msgs = [
+            Message::GameUpdate {
+                seq: 1,
+                players: vec![
+                    update(player_id.clone(), 200_000, 0),
+                    update(villain_id, 0, 50_000),
+                ],
+                board: Vec::default(),
+                extra_boards: Vec::default(),
+                pot: Chips::new(100_000),
+                pots: vec![Chips::new(100_000)],
+                blinds: BlindLevel::default(),
+            },
+            Message::ActionRequest {
+                player_id,
+                big_blind: Chips::new(20_000),
+                actions: vec![
+                    ActionOption::fixed(PlayerAction::Fold, Chips::ZERO),
+                    ActionOption::fixed(PlayerAction::Call, Chips::new(50_000)),
+                ],
+            },
+        ];
+        for msg in msgs {
+            state.handle_message(SignedMessage::new(&sk, msg));
+        }
+
+        // Calling 50k to win a 200k pot needs more than 25% equity.
+        assert_eq!(state.call_amount(), Some(Chips::new(50_000)));
+        assert_eq!(state.total_pot(), Chips::new(150_000));
+        assert_eq!(pot_odds(Chips::new(50_000), state.total_pot()), 0.25);
+        assert!(should_call_all_in(&state, 0.30));
+        assert!(!should_call_all_in(&state, 0.25));
+        assert!(!should_call_all_in(&state, 0.20));
+    }
+}
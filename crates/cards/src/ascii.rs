@@ -0,0 +1,81 @@
+// Copyright (C) 2025 Vince Vasta
+// SPDX-License-Identifier: Apache-2.0
+
+//! ASCII rendering of cards for consumers that don't use egui.
+use crate::{Card, Suit};
+
+impl Card {
+    /// Returns a boxed ASCII-art representation of this card.
+    ///
+    /// The card is 5 lines of 7 characters separated by a newline, the rank is
+    /// shown in the top-left and bottom-right corners and the suit letter in
+    /// the center:
+    ///
+    /// ```text
+    /// +-----+
+    /// |K    |
+    /// |  d  |
+    /// |    K|
+    /// +-----+
+    /// ```
+    pub fn to_ascii(&self) -> String {
+        let rank = self.rank();
+        let suit = match self.suit() {
+            Suit::Clubs => 'c',
+            Suit::Diamonds => 'd',
+            Suit::Hearts => 'h',
+            Suit::Spades => 's',
+        };
+
+        format!("+-----+\n|{rank}    |\n|  {suit}  |\n|    {rank}|\n+-----+")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Card, Rank, Suit};
+
+    #[test]
+    fn card_to_ascii() {
+        let c = Card::new(Rank::King, Suit::Diamonds);
+        assert_eq!(c.to_ascii(), "+-----+\n|K    |\n|  d  |\n|    K|\n+-----+");
+
+        let c = Card::new(Rank::Ten, Suit::Clubs);
+        assert_eq!(c.to_ascii(), "+-----+\n|T    |\n|  c  |\n|    T|\n+-----+");
+    }
+
+    #[test]
+    fn ascii_glyphs_and_layout() {
+        for suit in Suit::suits() {
+            for rank in Rank::ranks() {
+                let card = Card::new(rank, suit);
+                let ascii = card.to_ascii();
+                let lines = ascii.lines().collect::<Vec<_>>();
+
+                // Check the box layout.
+                assert_eq!(lines.len(), 5);
+                assert!(lines.iter().all(|l| l.len() == 7 && l.is_ascii()));
+                assert_eq!(lines[0], "+-----+");
+                assert_eq!(lines[4], "+-----+");
+                assert!(
+                    lines[1..4]
+                        .iter()
+                        .all(|l| l.starts_with('|') && l.ends_with('|'))
+                );
+
+                // Check rank in the corners and suit in the center.
+                let rank_char = rank.to_string().chars().next().unwrap();
+                let suit_char = suit
+                    .to_string()
+                    .to_ascii_lowercase()
+                    .chars()
+                    .next()
+                    .unwrap();
+                assert_eq!(lines[1].chars().nth(1), Some(rank_char));
+                assert_eq!(lines[3].chars().nth(5), Some(rank_char));
+                assert_eq!(lines[2].chars().nth(3), Some(suit_char));
+                assert_eq!(lines[2].chars().filter(|c| *c != ' ').count(), 3);
+            }
+        }
+    }
+}
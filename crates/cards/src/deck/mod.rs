@@ -2,9 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! Poker cards definitions.
+use blake2::{Blake2s256, Digest};
 use rand::prelude::*;
+use rand_chacha::ChaCha20Rng;
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::{error, fmt, str::FromStr};
 
 #[cfg(feature = "parallel")]
 pub mod parallel;
@@ -53,6 +55,15 @@ impl Card {
         }
     }
 
+    /// Returns the card color.
+    pub fn color(&self) -> CardColor {
+        if self.suit().is_red() {
+            CardColor::Red
+        } else {
+            CardColor::Black
+        }
+    }
+
     /// Returns the card rank.
     pub fn rank(&self) -> Rank {
         let rank_bits = self.rank_bits();
@@ -105,8 +116,50 @@ impl fmt::Debug for Card {
     }
 }
 
+impl FromStr for Card {
+    type Err = ParseCardError;
+
+    /// Parses a card from a rank and a suit, e.g. "Ah" or "TD".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let (Some(rank), Some(suit), None) = (chars.next(), chars.next(), chars.next()) else {
+            return Err(ParseCardError::InvalidCard(s.to_string()));
+        };
+
+        let rank = Rank::from_char(rank).ok_or(ParseCardError::InvalidRank(rank))?;
+        let suit = Suit::from_char(suit).ok_or(ParseCardError::InvalidSuit(suit))?;
+        Ok(Card::new(rank, suit))
+    }
+}
+
+/// An error from parsing cards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseCardError {
+    /// The string doesn't have the expected number of characters.
+    InvalidCard(String),
+    /// Invalid rank character.
+    InvalidRank(char),
+    /// Invalid suit character.
+    InvalidSuit(char),
+    /// A card appears more than once in a hand.
+    DuplicateCard(Card),
+}
+
+impl fmt::Display for ParseCardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidCard(s) => write!(f, "cannot parse '{s}'"),
+            Self::InvalidRank(c) => write!(f, "invalid rank '{c}'"),
+            Self::InvalidSuit(c) => write!(f, "invalid suit '{c}'"),
+            Self::DuplicateCard(c) => write!(f, "duplicate card {c}"),
+        }
+    }
+}
+
+impl error::Error for ParseCardError {}
+
 /// Card rank.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Rank {
     /// Deuce
     Deuce = 0,
@@ -145,6 +198,38 @@ impl Rank {
         ]
         .into_iter()
     }
+
+    /// Returns the rank for a character in "23456789TJQKA", ignoring case.
+    #[inline]
+    pub fn from_char(c: char) -> Option<Rank> {
+        let rank = match c.to_ascii_uppercase() {
+            '2' => Rank::Deuce,
+            '3' => Rank::Trey,
+            '4' => Rank::Four,
+            '5' => Rank::Five,
+            '6' => Rank::Six,
+            '7' => Rank::Seven,
+            '8' => Rank::Eight,
+            '9' => Rank::Nine,
+            'T' => Rank::Ten,
+            'J' => Rank::Jack,
+            'Q' => Rank::Queen,
+            'K' => Rank::King,
+            'A' => Rank::Ace,
+            _ => return None,
+        };
+
+        Some(rank)
+    }
+}
+
+impl FromStr for Rank {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let c = single_char(s)?;
+        Rank::from_char(c).ok_or(ParseCardError::InvalidRank(c))
+    }
 }
 
 impl fmt::Display for Rank {
@@ -169,8 +254,17 @@ impl fmt::Display for Rank {
     }
 }
 
+/// Card color.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CardColor {
+    /// Hearts and diamonds.
+    Red,
+    /// Clubs and spades.
+    Black,
+}
+
 /// Card suit.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Suit {
     /// Clubs suit.
     Clubs = 8,
@@ -200,6 +294,104 @@ impl Suit {
     pub fn suits() -> impl DoubleEndedIterator<Item = Suit> {
         [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades].into_iter()
     }
+
+    /// Checks if this is a red suit, hearts or diamonds.
+    #[inline]
+    pub fn is_red(&self) -> bool {
+        matches!(self, Suit::Hearts | Suit::Diamonds)
+    }
+
+    /// Returns the suit for a character in "CDHS", ignoring case.
+    #[inline]
+    pub fn from_char(c: char) -> Option<Suit> {
+        match c.to_ascii_uppercase() {
+            'C' => Some(Suit::Clubs),
+            'D' => Some(Suit::Diamonds),
+            'H' => Some(Suit::Hearts),
+            'S' => Some(Suit::Spades),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Suit {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let c = single_char(s)?;
+        Suit::from_char(c).ok_or(ParseCardError::InvalidSuit(c))
+    }
+}
+
+/// Returns the character of a single character string.
+fn single_char(s: &str) -> Result<char, ParseCardError> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(ParseCardError::InvalidCard(s.to_string())),
+    }
+}
+
+/// One of the 169 canonical classes of two cards starting hands.
+///
+/// A class is identified by the two ranks and by whether the cards are suited,
+/// a pair is never suited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StartingHandClass {
+    /// The higher rank.
+    pub high: Rank,
+    /// The lower rank, equal to the higher rank for pairs.
+    pub low: Rank,
+    /// The cards have the same suit.
+    pub suited: bool,
+}
+
+impl StartingHandClass {
+    /// Checks if this is a pocket pair.
+    pub fn is_pair(&self) -> bool {
+        self.high == self.low
+    }
+}
+
+impl fmt::Display for StartingHandClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_pair() {
+            write!(f, "{}{}", self.high, self.low)
+        } else {
+            let suffix = if self.suited { 's' } else { 'o' };
+            write!(f, "{}{}{suffix}", self.high, self.low)
+        }
+    }
+}
+
+/// Returns the canonical class of a two cards starting hand, e.g. "AKs" or "TT".
+pub fn starting_hand_class(hand: (Card, Card)) -> StartingHandClass {
+    let (c1, c2) = hand;
+    StartingHandClass {
+        high: c1.rank().max(c2.rank()),
+        low: c1.rank().min(c2.rank()),
+        suited: c1.suit() == c2.suit(),
+    }
+}
+
+/// Returns the number of cards for each rank, indexed from Deuce to Ace.
+pub fn rank_counts(cards: &[Card]) -> [u8; 13] {
+    let mut counts = [0; 13];
+    for c in cards {
+        counts[c.rank_bits() as usize] += 1;
+    }
+    counts
+}
+
+/// Returns the number of cards for each suit, indexed as clubs, diamonds,
+/// hearts, and spades.
+pub fn suit_counts(cards: &[Card]) -> [u8; 4] {
+    let mut counts = [0; 4];
+    for c in cards {
+        // Suit bits are one-hot with clubs in the highest bit.
+        counts[3 - c.suit_bits().trailing_zeros() as usize] += 1;
+    }
+    counts
 }
 
 /// A cards Deck
@@ -212,6 +404,40 @@ impl Deck {
     /// The number of cards in the deck.
     pub const SIZE: usize = 52;
 
+    /// Returns an iterator over the 1326 distinct two cards starting hands.
+    pub fn starting_hands() -> impl Iterator<Item = (Card, Card)> {
+        let cards = Self::default().cards;
+        (0..cards.len()).flat_map(move |i| {
+            let cards = cards.clone();
+            ((i + 1)..cards.len()).map(move |j| (cards[i], cards[j]))
+        })
+    }
+
+    /// Creates a deck that deals the given cards in order.
+    pub fn from_cards(cards: impl IntoIterator<Item = Card>) -> Self {
+        let mut cards = cards.into_iter().collect::<Vec<_>>();
+        // Cards are dealt from the end.
+        cards.reverse();
+        Self { cards }
+    }
+
+    /// Parses a hand of distinct cards, e.g. "AhKd" or "As Ks Qs Js Ts".
+    pub fn parse_hand(s: &str) -> Result<Vec<Card>, ParseCardError> {
+        let chars = s.chars().filter(|c| !c.is_whitespace()).collect::<Vec<_>>();
+
+        let mut cards = Vec::with_capacity(chars.len() / 2);
+        for chunk in chars.chunks(2) {
+            let card = chunk.iter().collect::<String>().parse::<Card>()?;
+            if cards.contains(&card) {
+                return Err(ParseCardError::DuplicateCard(card));
+            }
+
+            cards.push(card);
+        }
+
+        Ok(cards)
+    }
+
     /// Creates a new shuffled deck.
     pub fn shuffled<R: Rng>(rng: &mut R) -> Self {
         let mut deck = Self::default();
@@ -219,9 +445,61 @@ impl Deck {
         deck
     }
 
+    /// Creates a deck shuffled from a committed server seed and the players
+    /// entropy.
+    ///
+    /// The inputs are hashed together to seed the shuffle so that neither the
+    /// server nor the players alone control the deal, the same inputs always give
+    /// the same deck so that the shuffle can be verified once the seed is revealed.
+    /// The shuffle uses the ChaCha20 generator with a Fisher-Yates shuffle that
+    /// doesn't depend on the rand crate algorithms, so that decks can be verified
+    /// by other implementations and across versions.
+    pub fn shuffle_committed(server_seed: &[u8; 32], client_entropy: &[&[u8]]) -> Self {
+        let mut hasher = Blake2s256::new();
+        hasher.update(b"freezeout-shuffle");
+        hasher.update(server_seed);
+        for entropy in client_entropy {
+            // Prefix with the length so that entropy boundaries are unambiguous.
+            hasher.update((entropy.len() as u64).to_le_bytes());
+            hasher.update(entropy);
+        }
+
+        let mut rng = ChaCha20Rng::from_seed(hasher.finalize().into());
+        let mut deck = Self::default();
+        for i in (1..deck.cards.len()).rev() {
+            let j = uniform_index(&mut rng, i as u32 + 1);
+            deck.cards.swap(i, j as usize);
+        }
+
+        deck
+    }
+
     /// Deals a card from the deck.
+    ///
+    /// Panics if the deck is empty.
     pub fn deal(&mut self) -> Card {
-        self.cards.pop().unwrap()
+        self.try_deal().expect("Cannot deal from an empty deck")
+    }
+
+    /// Deals a card from the deck or returns `None` if the deck is empty.
+    pub fn try_deal(&mut self) -> Option<Card> {
+        self.cards.pop()
+    }
+
+    /// Deals `n` cards from the deck in the same order as `n` calls to
+    /// [Deck::deal].
+    ///
+    /// Panics if the deck has fewer than `n` cards.
+    pub fn deal_n(&mut self, n: usize) -> Vec<Card> {
+        assert!(
+            n <= self.cards.len(),
+            "Cannot deal {n} cards from a deck with {} cards",
+            self.cards.len()
+        );
+
+        let mut cards = self.cards.split_off(self.cards.len() - n);
+        cards.reverse();
+        cards
     }
 
     /// Checks if the deck is empty.
@@ -234,25 +512,63 @@ impl Deck {
         self.cards.len()
     }
 
+    /// Checks if a card is in the deck.
+    pub fn contains(&self, card: Card) -> bool {
+        self.cards.contains(&card)
+    }
+
+    /// The cards remaining in the deck.
+    pub fn remaining(&self) -> &[Card] {
+        &self.cards
+    }
+
+    /// Returns an iterator over the cards remaining in the deck.
+    pub fn iter(&self) -> impl Iterator<Item = Card> + '_ {
+        self.cards.iter().copied()
+    }
+
     /// Removes a card from the deck.
     pub fn remove(&mut self, card: Card) {
         self.cards.retain(|c| c != &card);
     }
 
+    /// Removes the given cards from the deck in a single pass.
+    ///
+    /// Cards not in the deck are ignored, returns the number of cards removed.
+    pub fn remove_all(&mut self, cards: &[Card]) -> usize {
+        let count = self.cards.len();
+        self.cards.retain(|c| !cards.contains(c));
+        count - self.cards.len()
+    }
+
     /// Calls the given closure n times with a sample of k cards.
     ///
     /// Panics if k is not in the [1..Self::count()] range.
-    pub fn sample<F>(&self, n: usize, k: usize, mut f: F)
+    pub fn sample<F>(&self, n: usize, k: usize, f: F)
+    where
+        F: FnMut(&[Card]),
+    {
+        self.sample_with_rng(n, k, &mut SmallRng::from_os_rng(), f);
+    }
+
+    /// Calls the given closure n times with a sample of k cards drawn using the
+    /// given random number generator.
+    ///
+    /// Generators created from identical seeds yield identical sequences of
+    /// samples from the same deck, so that a run can be reproduced.
+    ///
+    /// Panics if k is not in the [1..Self::count()] range.
+    pub fn sample_with_rng<R, F>(&self, n: usize, k: usize, rng: &mut R, mut f: F)
     where
+        R: Rng + ?Sized,
         F: FnMut(&[Card]),
     {
         assert!(k > 0 && k < self.cards.len());
 
         let mut h = vec![Card::new(Rank::Ace, Suit::Hearts); k];
-        let mut rng = SmallRng::from_os_rng();
 
         for _ in 0..n {
-            for (pos, c) in self.cards.choose_multiple(&mut rng, k).enumerate() {
+            for (pos, c) in self.cards.choose_multiple(rng, k).enumerate() {
                 h[pos] = *c;
             }
 
@@ -260,6 +576,21 @@ impl Deck {
         }
     }
 
+    /// Returns the number of k-cards hands [Self::for_each] iterates through.
+    ///
+    /// Panics if k is not in the range 2 <= k <= 7.
+    pub fn combinations(&self, k: usize) -> u64 {
+        assert!((2..=7).contains(&k), "2 <= k <= 7");
+
+        let n = self.cards.len() as u64;
+        if k as u64 > n {
+            return 0;
+        }
+
+        // Each partial product is C(n - k + i, i) so the division is exact.
+        (1..=k as u64).fold(1, |c, i| c * (n - k as u64 + i) / i)
+    }
+
     /// Calls the `f` closure for each k-cards hand.
     ///
     /// Panics if k is not in the range 2 <= k <= 7.
@@ -267,14 +598,14 @@ impl Deck {
     where
         F: FnMut(&[Card]),
     {
-        assert!(2 <= k && k <= 7, "2 <= k <= 7");
+        assert!((2..=7).contains(&k), "2 <= k <= 7");
 
         if k > self.cards.len() {
             return;
         }
 
         let n = self.cards.len();
-        let mut h = vec![Card::new(Rank::Ace, Suit::Hearts); 7];
+        let mut h = [Card::new(Rank::Ace, Suit::Hearts); 7];
 
         for c1 in 0..n {
             h[0] = self.cards[c1];
@@ -350,6 +681,20 @@ impl IntoIterator for Deck {
     }
 }
 
+/// Returns a uniformly distributed index less than `n`.
+///
+/// Uses rejection sampling on the generator output so that the result only
+/// depends on the generator stream.
+fn uniform_index<R: RngCore>(rng: &mut R, n: u32) -> u32 {
+    let limit = u32::MAX - u32::MAX % n;
+    loop {
+        let x = rng.next_u32();
+        if x < limit {
+            return x % n;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -401,6 +746,57 @@ mod tests {
         assert_eq!(c.to_string(), "AH");
     }
 
+    #[test]
+    fn starting_hands() {
+        let hands = Deck::starting_hands().collect::<Vec<_>>();
+        assert_eq!(hands.len(), 1_326);
+
+        // All hands are distinct irrespective of the cards order.
+        let unique = hands
+            .iter()
+            .map(|(c1, c2)| (c1.id().min(c2.id()), c1.id().max(c2.id())))
+            .collect::<HashSet<_>>();
+        assert_eq!(unique.len(), 1_326);
+        assert!(hands.iter().all(|(c1, c2)| c1 != c2));
+
+        let mut classes = ahash::HashMap::default();
+        for hand in hands {
+            *classes.entry(starting_hand_class(hand)).or_insert(0) += 1;
+        }
+        assert_eq!(classes.len(), 169);
+
+        // Each pair has 6 combos, suited hands 4 and offsuit hands 12.
+        for (class, count) in &classes {
+            let expected = if class.is_pair() {
+                6
+            } else if class.suited {
+                4
+            } else {
+                12
+            };
+            assert_eq!(*count, expected, "{class}");
+        }
+
+        let class = |c1, c2| starting_hand_class((c1, c2)).to_string();
+        let aks = class(
+            Card::new(Rank::King, Suit::Spades),
+            Card::new(Rank::Ace, Suit::Spades),
+        );
+        assert_eq!(aks, "AKs");
+
+        let t9o = class(
+            Card::new(Rank::Ten, Suit::Hearts),
+            Card::new(Rank::Nine, Suit::Clubs),
+        );
+        assert_eq!(t9o, "T9o");
+
+        let tt = class(
+            Card::new(Rank::Ten, Suit::Hearts),
+            Card::new(Rank::Ten, Suit::Clubs),
+        );
+        assert_eq!(tt, "TT");
+    }
+
     #[test]
     fn deck_for_each() {
         let deck = Deck::default();
@@ -426,6 +822,9 @@ mod tests {
             hands.insert(cards.to_owned());
         });
         assert_eq!(hands.len(), 22_100);
+        assert_eq!(deck.combinations(3), 22_100);
+        assert_eq!(deck.combinations(5), 2_598_960);
+        assert_eq!(deck.combinations(7), 133_784_560);
     }
 
     #[test]
@@ -452,6 +851,13 @@ mod tests {
             count += 1;
         });
         assert_eq!(count, 99_884_400);
+        assert_eq!(deck.combinations(7), count);
+
+        // No hands when there are fewer than k cards.
+        let cards = deck.remaining().iter().skip(2).copied().collect::<Vec<_>>();
+        deck.remove_all(&cards);
+        assert_eq!(deck.combinations(2), 1);
+        assert_eq!(deck.combinations(3), 0);
     }
 
     #[test]
@@ -470,4 +876,244 @@ mod tests {
         });
         assert_eq!(counter, 10);
     }
+
+    #[test]
+    fn sample_with_rng() {
+        let samples = |seed| {
+            let mut hands = Vec::new();
+            let mut rng = StdRng::seed_from_u64(seed);
+            Deck::default().sample_with_rng(20, 5, &mut rng, |hand| hands.push(hand.to_vec()));
+            hands
+        };
+
+        // The same seed yields the same hands.
+        let hands = samples(7);
+        assert_eq!(hands.len(), 20);
+        assert_eq!(hands, samples(7));
+        assert_ne!(hands, samples(8));
+    }
+
+    #[test]
+    fn rank_and_suit_counts() {
+        // A pair of kings with four hearts.
+        let cards = [
+            Card::new(Rank::King, Suit::Hearts),
+            Card::new(Rank::King, Suit::Spades),
+            Card::new(Rank::Nine, Suit::Hearts),
+            Card::new(Rank::Deuce, Suit::Hearts),
+            Card::new(Rank::Ace, Suit::Hearts),
+            Card::new(Rank::Five, Suit::Clubs),
+        ];
+
+        let ranks = rank_counts(&cards);
+        assert_eq!(ranks[Rank::King as usize], 2);
+        assert_eq!(ranks[Rank::Nine as usize], 1);
+        assert_eq!(ranks[Rank::Deuce as usize], 1);
+        assert_eq!(ranks[Rank::Ace as usize], 1);
+        assert_eq!(ranks[Rank::Five as usize], 1);
+        assert_eq!(
+            ranks.iter().map(|&c| c as usize).sum::<usize>(),
+            cards.len()
+        );
+
+        assert_eq!(suit_counts(&cards), [1, 0, 4, 1]);
+        assert_eq!(rank_counts(&[]), [0; 13]);
+        assert_eq!(suit_counts(&[]), [0; 4]);
+    }
+
+    #[test]
+    fn from_cards() {
+        let cards = [
+            Card::new(Rank::Ace, Suit::Spades),
+            Card::new(Rank::King, Suit::Hearts),
+            Card::new(Rank::Deuce, Suit::Clubs),
+        ];
+
+        let mut deck = Deck::from_cards(cards);
+        assert_eq!(deck.count(), 3);
+        for card in cards {
+            assert_eq!(deck.deal(), card);
+        }
+        assert!(deck.is_empty());
+    }
+
+    #[test]
+    fn remaining_cards() {
+        let ah = Card::new(Rank::Ace, Suit::Hearts);
+        let kd = Card::new(Rank::King, Suit::Diamonds);
+
+        let mut deck = Deck::default();
+        assert!(deck.contains(ah) && deck.contains(kd));
+
+        deck.remove(ah);
+        deck.remove(kd);
+        assert_eq!(deck.count(), 50);
+        assert!(!deck.contains(ah));
+        assert!(!deck.contains(kd));
+        assert_eq!(deck.remaining().len(), 50);
+        assert!(!deck.remaining().iter().any(|c| *c == ah || *c == kd));
+
+        // Iterating doesn't consume the deck and skips removed cards.
+        assert_eq!(deck.iter().count(), 50);
+        assert!(!deck.iter().any(|c| c == ah || c == kd));
+        assert!(deck.iter().eq(deck.remaining().iter().copied()));
+        assert_eq!(deck.count(), 50);
+
+        // Removing many cards skips missing and repeated cards.
+        let qc = Card::new(Rank::Queen, Suit::Clubs);
+        let js = Card::new(Rank::Jack, Suit::Spades);
+        assert_eq!(deck.remove_all(&[ah, qc, js, qc]), 2);
+        assert_eq!(deck.count(), 48);
+        assert!(!deck.contains(qc) && !deck.contains(js));
+        assert_eq!(deck.remove_all(&[qc, js]), 0);
+        assert_eq!(deck.remove_all(&[]), 0);
+    }
+
+    #[test]
+    fn parse_cards() {
+        // Parsing round-trips with display.
+        for card in Deck::default() {
+            assert_eq!(card.to_string().parse::<Card>(), Ok(card));
+        }
+
+        let ah = Card::new(Rank::Ace, Suit::Hearts);
+        assert_eq!("Ah".parse::<Card>(), Ok(ah));
+        assert_eq!("ah".parse::<Card>(), Ok(ah));
+        assert_eq!("AH".parse::<Card>(), Ok(ah));
+
+        assert_eq!("1h".parse::<Card>(), Err(ParseCardError::InvalidRank('1')));
+        assert_eq!("Ax".parse::<Card>(), Err(ParseCardError::InvalidSuit('x')));
+        assert!(matches!(
+            "A".parse::<Card>(),
+            Err(ParseCardError::InvalidCard(_))
+        ));
+        assert!(matches!(
+            "10h".parse::<Card>(),
+            Err(ParseCardError::InvalidCard(_))
+        ));
+
+        let kd = Card::new(Rank::King, Suit::Diamonds);
+        assert_eq!(Deck::parse_hand("AhKd"), Ok(vec![ah, kd]));
+
+        let royal = Deck::parse_hand("As Ks Qs Js Ts").unwrap();
+        assert_eq!(royal.len(), 5);
+        assert!(royal.iter().all(|c| c.suit() == Suit::Spades));
+
+        assert_eq!(
+            Deck::parse_hand("Ah Kd ah"),
+            Err(ParseCardError::DuplicateCard(ah))
+        );
+        assert!(Deck::parse_hand("Ah K").is_err());
+        assert_eq!(Deck::parse_hand(""), Ok(Vec::default()));
+    }
+
+    #[test]
+    fn parse_ranks_and_suits() {
+        for rank in Rank::ranks() {
+            assert_eq!(rank.to_string().parse::<Rank>(), Ok(rank));
+        }
+
+        for suit in Suit::suits() {
+            assert_eq!(suit.to_string().parse::<Suit>(), Ok(suit));
+        }
+
+        assert_eq!("T".parse::<Rank>(), Ok(Rank::Ten));
+        assert_eq!("t".parse::<Rank>(), Ok(Rank::Ten));
+        assert_eq!("h".parse::<Suit>(), Ok(Suit::Hearts));
+        assert_eq!("H".parse::<Suit>(), Ok(Suit::Hearts));
+
+        assert_eq!("1".parse::<Rank>(), Err(ParseCardError::InvalidRank('1')));
+        assert_eq!("x".parse::<Suit>(), Err(ParseCardError::InvalidSuit('x')));
+        assert!(matches!(
+            "10".parse::<Rank>(),
+            Err(ParseCardError::InvalidCard(_))
+        ));
+        assert!(matches!(
+            "".parse::<Suit>(),
+            Err(ParseCardError::InvalidCard(_))
+        ));
+    }
+
+    #[test]
+    fn card_colors() {
+        let red = Suit::suits().filter(|s| s.is_red()).collect::<Vec<_>>();
+        assert_eq!(red, [Suit::Diamonds, Suit::Hearts]);
+
+        assert_eq!(Card::new(Rank::Ace, Suit::Hearts).color(), CardColor::Red);
+        assert_eq!(Card::new(Rank::Ten, Suit::Diamonds).color(), CardColor::Red);
+        assert_eq!(Card::new(Rank::Ace, Suit::Spades).color(), CardColor::Black);
+        assert_eq!(
+            Card::new(Rank::Deuce, Suit::Clubs).color(),
+            CardColor::Black
+        );
+    }
+
+    #[test]
+    fn shuffle_committed() {
+        let server_seed = [7u8; 32];
+        let (e1, e2) = (b"alice".as_slice(), b"bob".as_slice());
+
+        // The same inputs reproduce the same deck.
+        let deck = Deck::shuffle_committed(&server_seed, &[e1, e2]);
+        assert_eq!(
+            deck.remaining(),
+            Deck::shuffle_committed(&server_seed, &[e1, e2]).remaining()
+        );
+        assert_eq!(deck.count(), Deck::SIZE);
+
+        // Changing any input changes the deck.
+        let other = Deck::shuffle_committed(&server_seed, &[e1, b"carol"]);
+        assert_ne!(deck.remaining(), other.remaining());
+
+        let other = Deck::shuffle_committed(&server_seed, &[e1]);
+        assert_ne!(deck.remaining(), other.remaining());
+
+        let other = Deck::shuffle_committed(&[8u8; 32], &[e1, e2]);
+        assert_ne!(deck.remaining(), other.remaining());
+
+        // Entropy boundaries matter.
+        let other = Deck::shuffle_committed(&server_seed, &[b"alicebob"]);
+        assert_ne!(deck.remaining(), other.remaining());
+    }
+
+    #[test]
+    fn deal_n() {
+        let mut deck = Deck::shuffled(&mut StdRng::seed_from_u64(7));
+        let mut other = Deck::shuffled(&mut StdRng::seed_from_u64(7));
+
+        // Dealing many cards is the same as dealing them one at a time.
+        let flop = deck.deal_n(3);
+        assert_eq!(flop, (0..3).map(|_| other.deal()).collect::<Vec<_>>());
+        assert_eq!(deck.count(), Deck::SIZE - 3);
+        assert!(deck.deal_n(0).is_empty());
+
+        let rest = deck.deal_n(Deck::SIZE - 3);
+        assert_eq!(rest.len(), Deck::SIZE - 3);
+        assert!(deck.is_empty());
+        assert_eq!(deck.try_deal(), None);
+
+        let mut deck = Deck::from_cards([Card::new(Rank::Ace, Suit::Spades)]);
+        assert_eq!(deck.try_deal(), Some(Card::new(Rank::Ace, Suit::Spades)));
+        assert_eq!(deck.try_deal(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot deal 3 cards from a deck with 2 cards")]
+    fn deal_n_exhausted() {
+        let mut deck = Deck::from_cards(Deck::default().iter().take(2));
+        deck.deal_n(3);
+    }
+
+    #[test]
+    fn shuffle_committed_known_answer() {
+        // The committed shuffle must not change across versions or it would not
+        // be possible to verify past hands.
+        let deck = Deck::shuffle_committed(&[7u8; 32], &[b"alice", b"bob"]);
+        let expected = Deck::parse_hand(
+            "4C8HTCJH5D2H2DQDKH8S8C7H4D2S6S5C3D7DQC9D7S5STSQS3C7C4H3H\
+             9SADTHKS9HQH5HACJD2C6D6C4STDAS9CKCAH3S8DJSJCKD6H",
+        )
+        .unwrap();
+        assert_eq!(deck.remaining(), expected);
+    }
 }
@@ -148,11 +148,25 @@ impl Deck {
     /// Calls the given closure from `num_tasks` parallel tasks generating
     /// `samples_per_task` samples of size k.
     pub fn par_sample<F>(&self, num_tasks: usize, samples_per_task: usize, k: usize, f: F)
+    where
+        F: Fn(usize, &[Card]) + Send + Sync,
+    {
+        let mut rng = StdRng::from_os_rng();
+        let seeds = (0..num_tasks).map(|_| rng.random()).collect::<Vec<u64>>();
+        self.par_sample_with_seeds(&seeds, samples_per_task, k, f);
+    }
+
+    /// Calls the given closure from a parallel task for each seed generating
+    /// `samples_per_task` samples of size k.
+    ///
+    /// Each task draws its samples from a generator initialized with its seed,
+    /// identical seeds yield identical sequences of samples for each task id.
+    pub fn par_sample_with_seeds<F>(&self, seeds: &[u64], samples_per_task: usize, k: usize, f: F)
     where
         F: Fn(usize, &[Card]) + Send + Sync,
     {
         assert!(k > 0 && k < self.cards.len());
-        assert!(num_tasks > 0);
+        assert!(!seeds.is_empty());
         assert!(samples_per_task > 0);
 
         if k > self.cards.len() {
@@ -160,11 +174,11 @@ impl Deck {
         }
 
         thread::scope(|s| {
-            for task_id in 0..num_tasks {
+            for (task_id, seed) in seeds.iter().enumerate() {
                 let f = &f;
                 s.spawn(move || {
                     let mut h = vec![Card::new(Rank::Ace, Suit::Diamonds); k];
-                    let mut rng = SmallRng::from_os_rng();
+                    let mut rng = StdRng::seed_from_u64(*seed);
 
                     for _ in 0..samples_per_task {
                         for (pos, c) in self.cards.choose_multiple(&mut rng, k).enumerate() {
@@ -273,4 +287,24 @@ mod tests {
         assert_eq!(counter.load(Ordering::Relaxed), NUM_TASKS as u64 * 10);
         assert_eq!(tasks.load(Ordering::Relaxed), 0b1111);
     }
+
+    #[test]
+    fn par_sample_with_seeds() {
+        use std::sync::Mutex;
+
+        let samples = |seeds: &[u64]| {
+            let hands = Mutex::new(vec![Vec::new(); seeds.len()]);
+            Deck::default().par_sample_with_seeds(seeds, 10, 5, |task_id, hand| {
+                hands.lock().unwrap()[task_id].push(hand.to_vec());
+            });
+            hands.into_inner().unwrap()
+        };
+
+        // Each task yields the same hands for the same seed.
+        let hands = samples(&[1, 2, 3]);
+        assert!(hands.iter().all(|h| h.len() == 10));
+        assert_eq!(hands, samples(&[1, 2, 3]));
+        assert_eq!(hands[1], samples(&[2])[0]);
+        assert_ne!(hands[0], hands[1]);
+    }
 }
@@ -4,9 +4,9 @@
 //! Cards images loading and painting.
 use ahash::AHashMap;
 use eframe::egui;
-use std::sync::LazyLock;
+use std::{borrow::Cow, sync::LazyLock};
 
-use crate::deck::{Card, Deck, Rank, Suit};
+use crate::deck::{Card, CardColor, Deck, Rank, Suit};
 
 /// The clubs.
 const BYTES_2C: &[u8] = include_bytes!("assets/2c.png");
@@ -133,45 +133,336 @@ static CARD_IMAGES: LazyLock<AHashMap<Card, &'static [u8]>> = LazyLock::new(|| {
     cards
 });
 
+impl From<CardColor> for egui::Color32 {
+    fn from(color: CardColor) -> Self {
+        match color {
+            CardColor::Red => egui::Color32::from_rgb(200, 30, 40),
+            CardColor::Black => egui::Color32::from_rgb(20, 20, 20),
+        }
+    }
+}
+
+/// The resolution of the cards images.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ImageScale {
+    /// The standard resolution images.
+    #[default]
+    X1,
+    /// Images with twice the standard resolution for high-DPI displays.
+    X2,
+}
+
+impl ImageScale {
+    /// Selects the images resolution for a display with the given pixels per point.
+    pub fn from_pixels_per_point(pixels_per_point: f32) -> Self {
+        if pixels_per_point >= 1.5 {
+            Self::X2
+        } else {
+            Self::X1
+        }
+    }
+}
+
+/// The PNG images of the cards faces.
+pub type CardImages = AHashMap<Card, Cow<'static, [u8]>>;
+
+/// The PNG images of a skin at twice the standard resolution.
+#[derive(Debug, Clone)]
+pub struct HiresImages {
+    /// The cards faces images.
+    pub cards: CardImages,
+    /// The cards back image.
+    pub back: Cow<'static, [u8]>,
+}
+
+/// A cards skin with the PNG images of the cards faces and back.
+#[derive(Debug, Clone)]
+pub struct Skin {
+    /// The skin identifier, textures are cached by this id.
+    pub id: String,
+    /// The cards faces images.
+    pub cards: CardImages,
+    /// The cards back image.
+    pub back: Cow<'static, [u8]>,
+    /// Optional high resolution images used on high-DPI displays.
+    pub hires: Option<HiresImages>,
+}
+
+impl Skin {
+    /// The id of the default skin.
+    pub const DEFAULT_ID: &str = "default";
+
+    /// The id of the skin created from custom images.
+    pub const CUSTOM_ID: &str = "custom";
+
+    /// Returns the scale of the images used for the requested scale.
+    ///
+    /// Falls back to the standard images if the skin has no high resolution images.
+    pub fn image_scale(&self, scale: ImageScale) -> ImageScale {
+        match scale {
+            ImageScale::X2 if self.hires.is_some() => ImageScale::X2,
+            _ => ImageScale::X1,
+        }
+    }
+
+    /// Returns the key used to cache the skin textures for the requested scale.
+    pub fn asset_key(&self, scale: ImageScale) -> String {
+        match self.image_scale(scale) {
+            ImageScale::X1 => self.id.clone(),
+            ImageScale::X2 => format!("{}@2x", self.id),
+        }
+    }
+
+    /// Returns the cards faces and back images for the requested scale.
+    fn images(&self, scale: ImageScale) -> (&CardImages, &Cow<'static, [u8]>) {
+        match (self.image_scale(scale), &self.hires) {
+            (ImageScale::X2, Some(hires)) => (&hires.cards, &hires.back),
+            _ => (&self.cards, &self.back),
+        }
+    }
+}
+
+impl Default for Skin {
+    fn default() -> Self {
+        Self {
+            id: Self::DEFAULT_ID.to_string(),
+            cards: CARD_IMAGES
+                .iter()
+                .map(|(card, image_data)| (*card, Cow::Borrowed(*image_data)))
+                .collect(),
+            back: Cow::Borrowed(BYTES_BB),
+            hires: None,
+        }
+    }
+}
+
 /// A collection of cards textures used for drawing.
 pub struct Textures {
-    cards: AHashMap<Card, egui::TextureHandle>,
-    back: egui::TextureHandle,
+    skins: SkinCache<SkinTextures>,
+    current: String,
+    current_key: String,
+    scale: ImageScale,
 }
 
 impl Textures {
     /// Loads the cards textures.
     pub fn new(ctx: &egui::Context) -> Self {
-        let cards = CARD_IMAGES
-            .iter()
-            .map(|(card, image_data)| {
+        Self::new_with_scale(ctx, ImageScale::X1)
+    }
+
+    /// Loads the cards textures with the given images scale.
+    ///
+    /// Skins that don't have high resolution images use the standard images.
+    pub fn new_with_scale(ctx: &egui::Context, scale: ImageScale) -> Self {
+        Self::with_skin(ctx, &Skin::default(), scale)
+    }
+
+    /// Loads the default cards faces textures with a custom back image.
+    pub fn with_back(ctx: &egui::Context, back: impl Into<Cow<'static, [u8]>>) -> Self {
+        let skin = Skin {
+            id: Skin::CUSTOM_ID.to_string(),
+            back: back.into(),
+            ..Skin::default()
+        };
+
+        Self::with_skin(ctx, &skin, ImageScale::X1)
+    }
+
+    /// Loads the cards textures from custom faces images keyed by card.
+    ///
+    /// Cards missing from the images use the default cards faces.
+    pub fn from_images(ctx: &egui::Context, cards: CardImages) -> Self {
+        let skin = Skin {
+            id: Skin::CUSTOM_ID.to_string(),
+            cards,
+            ..Skin::default()
+        };
+
+        Self::with_skin(ctx, &skin, ImageScale::X1)
+    }
+
+    fn with_skin(ctx: &egui::Context, skin: &Skin, scale: ImageScale) -> Self {
+        let mut textures = Self {
+            skins: SkinCache::default(),
+            current: String::default(),
+            current_key: String::default(),
+            scale,
+        };
+
+        textures.set_skin(ctx, skin);
+        textures
+    }
+
+    /// Switches the cards textures to the given skin.
+    ///
+    /// The skin images are loaded the first time a skin is used, switching back to
+    /// a skin that has already been loaded reuses its textures. Cards missing from
+    /// the skin use the default cards faces.
+    pub fn set_skin(&mut self, ctx: &egui::Context, skin: &Skin) {
+        let key = skin.asset_key(self.scale);
+        self.skins
+            .get_or_load(&key, || SkinTextures::load(ctx, skin, self.scale));
+        self.current = skin.id.clone();
+        self.current_key = key;
+    }
+
+    /// Returns the scale of the requested images.
+    pub fn scale(&self) -> ImageScale {
+        self.scale
+    }
+
+    /// Returns the id of the current skin.
+    pub fn skin(&self) -> &str {
+        &self.current
+    }
+
+    /// Gets a texture for a card.
+    pub fn card(&self, card: Card) -> egui::TextureHandle {
+        self.textures().cards.get(&card).unwrap().clone()
+    }
+
+    /// Gets a texture for a card resampled to be painted at the given size.
+    ///
+    /// Cards scaled by the GPU look blurry, this texture matches the size in
+    /// physical pixels so that it is painted without further scaling. Textures
+    /// are cached by size.
+    pub fn card_sized(
+        &mut self,
+        ctx: &egui::Context,
+        card: Card,
+        size: egui::Vec2,
+    ) -> egui::TextureHandle {
+        let size = size * ctx.pixels_per_point();
+        let size = [
+            size.x.round().max(1.0) as usize,
+            size.y.round().max(1.0) as usize,
+        ];
+        self.skins
+            .get_mut(&self.current_key)
+            .expect("Current skin should be loaded")
+            .card_sized(ctx, &self.current_key, card, size)
+    }
+
+    /// Gets a texture for a hole card.
+    pub fn back(&self) -> egui::TextureHandle {
+        self.textures().back.clone()
+    }
+
+    fn textures(&self) -> &SkinTextures {
+        self.skins
+            .get(&self.current_key)
+            .expect("Current skin should be loaded")
+    }
+}
+
+/// The textures of a skin.
+struct SkinTextures {
+    cards: AHashMap<Card, egui::TextureHandle>,
+    back: egui::TextureHandle,
+    images: CardImages,
+    sized: AHashMap<(Card, [usize; 2]), egui::TextureHandle>,
+}
+
+impl SkinTextures {
+    fn load(ctx: &egui::Context, skin: &Skin, scale: ImageScale) -> Self {
+        let key = skin.asset_key(scale);
+        let (images, back) = skin.images(scale);
+        let cards = Deck::default()
+            .into_iter()
+            .map(|card| {
                 (
-                    *card,
+                    card,
                     ctx.load_texture(
-                        card.to_string(),
-                        image_from_memory(image_data),
+                        format!("{key}/{card}"),
+                        image_from_memory(card_image(images, card)),
                         Default::default(),
                     ),
                 )
             })
             .collect();
 
-        let back = ctx.load_texture("back", image_from_memory(BYTES_BB), Default::default());
+        let back = ctx.load_texture(
+            format!("{key}/back"),
+            image_from_memory(back),
+            Default::default(),
+        );
 
-        Self { cards, back }
+        Self {
+            cards,
+            back,
+            images: images.clone(),
+            sized: AHashMap::default(),
+        }
     }
 
-    /// Gets a texture for a card.
-    pub fn card(&self, card: Card) -> egui::TextureHandle {
-        self.cards.get(&card).unwrap().clone()
+    /// Maximum number of cached resampled textures.
+    const MAX_SIZED: usize = 4 * Deck::SIZE;
+
+    fn card_sized(
+        &mut self,
+        ctx: &egui::Context,
+        key: &str,
+        card: Card,
+        size: [usize; 2],
+    ) -> egui::TextureHandle {
+        // Drop old sizes when the window is resized many times.
+        if self.sized.len() >= Self::MAX_SIZED && !self.sized.contains_key(&(card, size)) {
+            self.sized.clear();
+        }
+
+        let image_data = card_image(&self.images, card);
+        self.sized
+            .entry((card, size))
+            .or_insert_with(|| {
+                ctx.load_texture(
+                    format!("{key}/{card}@{}x{}", size[0], size[1]),
+                    image_from_memory_sized(image_data, size),
+                    Default::default(),
+                )
+            })
+            .clone()
     }
+}
 
-    /// Gets a texture for a hole card.
-    pub fn back(&self) -> egui::TextureHandle {
-        self.back.clone()
+/// Loaded skins keyed by skin id.
+struct SkinCache<T> {
+    skins: AHashMap<String, T>,
+}
+
+impl<T> Default for SkinCache<T> {
+    fn default() -> Self {
+        Self {
+            skins: AHashMap::default(),
+        }
     }
 }
 
+impl<T> SkinCache<T> {
+    /// Returns the skin with the given id calling `load` if it is not cached.
+    fn get_or_load(&mut self, id: &str, load: impl FnOnce() -> T) -> &T {
+        self.skins.entry(id.to_string()).or_insert_with(load)
+    }
+
+    /// Returns the skin with the given id if it is cached.
+    fn get(&self, id: &str) -> Option<&T> {
+        self.skins.get(id)
+    }
+
+    /// Returns a mutable reference to the skin with the given id if it is cached.
+    fn get_mut(&mut self, id: &str) -> Option<&mut T> {
+        self.skins.get_mut(id)
+    }
+}
+
+/// Returns a card face image falling back to the default image if the card is
+/// missing from the given images.
+fn card_image(cards: &CardImages, card: Card) -> &[u8] {
+    cards
+        .get(&card)
+        .map(|image_data| image_data.as_ref())
+        .unwrap_or_else(|| CARD_IMAGES[&card])
+}
+
 fn image_from_memory(image_data: &[u8]) -> egui::ColorImage {
     let image = image::load_from_memory(image_data).unwrap();
     let size = [image.width() as _, image.height() as _];
@@ -179,3 +470,107 @@ fn image_from_memory(image_data: &[u8]) -> egui::ColorImage {
     let pixels = image_buffer.as_flat_samples();
     egui::ColorImage::from_rgba_unmultiplied(size, pixels.as_slice())
 }
+
+fn image_from_memory_sized(image_data: &[u8], size: [usize; 2]) -> egui::ColorImage {
+    let image = image::load_from_memory(image_data).unwrap();
+    let image_buffer = image::imageops::resize(
+        &image.to_rgba8(),
+        size[0] as u32,
+        size[1] as u32,
+        image::imageops::FilterType::Lanczos3,
+    );
+    let pixels = image_buffer.as_flat_samples();
+    egui::ColorImage::from_rgba_unmultiplied(size, pixels.as_slice())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skin_cache() {
+        let mut cache = SkinCache::default();
+        let mut decoded = 0;
+
+        for id in ["default", "dark", "default", "dark", "default"] {
+            let image = cache.get_or_load(id, || {
+                decoded += 1;
+                image_from_memory(BYTES_BB)
+            });
+            assert!(image.width() > 0);
+        }
+
+        // Each skin is decoded only the first time it is requested.
+        assert_eq!(decoded, 2);
+        assert!(cache.get("default").is_some());
+        assert!(cache.get("light").is_none());
+    }
+
+    #[test]
+    fn image_scale() {
+        assert_eq!(ImageScale::from_pixels_per_point(1.0), ImageScale::X1);
+        assert_eq!(ImageScale::from_pixels_per_point(1.25), ImageScale::X1);
+        assert_eq!(ImageScale::from_pixels_per_point(2.0), ImageScale::X2);
+
+        // A skin without high resolution images falls back to the standard ones.
+        let skin = Skin::default();
+        assert_eq!(skin.image_scale(ImageScale::X2), ImageScale::X1);
+        assert_eq!(skin.asset_key(ImageScale::X2), "default");
+
+        // A 2x scale selects the high resolution images.
+        let hires_back = Cow::Borrowed(BYTES_AS);
+        let skin = Skin {
+            id: "hires".to_string(),
+            hires: Some(HiresImages {
+                cards: skin.cards.clone(),
+                back: hires_back.clone(),
+            }),
+            ..skin
+        };
+
+        assert_eq!(skin.asset_key(ImageScale::X1), "hires");
+        assert_eq!(skin.asset_key(ImageScale::X2), "hires@2x");
+        assert_eq!(skin.images(ImageScale::X1).1, &Cow::Borrowed(BYTES_BB));
+        assert_eq!(skin.images(ImageScale::X2).1, &hires_back);
+    }
+
+    #[test]
+    fn custom_images() {
+        let ah = Card::new(Rank::Ace, Suit::Hearts);
+        let ks = Card::new(Rank::King, Suit::Spades);
+
+        // Custom images replace the default ones and missing cards fall back.
+        let mut cards = CardImages::default();
+        cards.insert(ah, Cow::Borrowed(BYTES_BB));
+        assert_eq!(card_image(&cards, ah), BYTES_BB);
+        assert_eq!(card_image(&cards, ks), BYTES_KS);
+
+        let ctx = egui::Context::default();
+        let textures = Textures::from_images(&ctx, cards);
+        assert_eq!(textures.skin(), Skin::CUSTOM_ID);
+        assert_eq!(textures.card(ah).size(), textures.back().size());
+        assert!(textures.card(ks).size()[0] > 0);
+
+        let textures = Textures::with_back(&ctx, BYTES_AS);
+        assert_eq!(textures.back().size(), textures.card(ks).size());
+    }
+
+    #[test]
+    fn sized_cards() {
+        let ctx = egui::Context::default();
+        let mut textures = Textures::new(&ctx);
+        let ah = Card::new(Rank::Ace, Suit::Hearts);
+
+        // The texture is resampled to the requested size and cached.
+        let tx = textures.card_sized(&ctx, ah, egui::vec2(30.0, 45.0));
+        assert_eq!(tx.size(), [30, 45]);
+        assert_eq!(
+            tx.id(),
+            textures.card_sized(&ctx, ah, egui::vec2(30.0, 45.0)).id()
+        );
+
+        let tx = textures.card_sized(&ctx, ah, egui::vec2(60.0, 90.0));
+        assert_eq!(tx.size(), [60, 90]);
+        assert_eq!(textures.textures().sized.len(), 2);
+    }
+}
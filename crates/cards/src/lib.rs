@@ -21,7 +21,7 @@
 //! Deck::default().for_each(7, |hand| {
 //!     counter += 1;
 //! });
-//! assert_eq!(counter, 133_784_560);
+//! assert_eq!(counter, Deck::default().combinations(7));
 //! ```
 //!
 //! to sample 10 random 5-cards hands:
@@ -75,9 +75,18 @@
 //! The **`egui`** feature enables the [Textures](egui::Textures) type that gives access
 //! to the card images, see the `board.rs` example for a simple egui app that uses this
 //! crate cards to compute hands probabilities.
+//!
+//! The **`ascii`** feature enables `Card::to_ascii` that renders a card as boxed
+//! ASCII-art for terminal and documentation use without requiring egui.
 #[warn(clippy::all, rust_2018_idioms, missing_docs)]
 mod deck;
-pub use deck::{Card, Deck, Rank, Suit};
+pub use deck::{
+    Card, CardColor, Deck, ParseCardError, Rank, StartingHandClass, Suit, rank_counts,
+    starting_hand_class, suit_counts,
+};
+
+#[cfg(feature = "ascii")]
+mod ascii;
 
 #[cfg(feature = "egui")]
 pub mod egui;
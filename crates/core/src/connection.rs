@@ -5,11 +5,13 @@
 use anyhow::{Result, anyhow, bail};
 use bytes::BytesMut;
 use futures_util::{SinkExt, StreamExt};
+use log::warn;
 use snow::{TransportState, params::NoiseParams};
-use std::sync::LazyLock;
+use std::{sync::LazyLock, time::Duration};
 use tokio::{
     io::{AsyncRead, AsyncWrite},
     net::TcpStream,
+    time,
 };
 use tokio_tungstenite::{
     self as websocket, MaybeTlsStream, WebSocketStream,
@@ -24,6 +26,9 @@ static NOISE_PARAMS: LazyLock<NoiseParams> =
 /// Maximum message length.
 const MAX_MSG_LEN: usize = 16384;
 
+/// Default time allowed to a peer to complete the connection handshake.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// The client connection type.
 pub type ClientConnection = EncryptedConnection<MaybeTlsStream<TcpStream>>;
 
@@ -31,6 +36,10 @@ pub type ClientConnection = EncryptedConnection<MaybeTlsStream<TcpStream>>;
 pub struct EncryptedConnection<S> {
     stream: WebSocketStream<S>,
     transport: TransportState,
+    /// Number of invalid messages skipped before failing.
+    error_budget: usize,
+    /// Number of invalid messages received on this connection.
+    bad_frames: usize,
 }
 
 impl<S> EncryptedConnection<S>
@@ -47,18 +56,39 @@ where
         Ok(())
     }
 
+    /// Sets the number of invalid messages that are skipped before
+    /// [recv](Self::recv) returns an error.
+    ///
+    /// The budget covers the connection lifetime, valid messages in between
+    /// don't restore it. A message is invalid if it cannot be deserialized or its
+    /// signature doesn't verify, by default the budget is zero and the first
+    /// invalid message is an error. Decryption errors are always returned.
+    pub fn set_error_budget(&mut self, budget: usize) {
+        self.error_budget = budget;
+    }
+
     /// Waits for a [SignedMessage].
     pub async fn recv(&mut self) -> Option<Result<SignedMessage>> {
         let mut buf = [0u8; MAX_MSG_LEN];
         loop {
             match self.stream.next().await {
                 Some(Ok(WsMessage::Binary(payload))) => {
-                    break Some(
-                        self.transport
-                            .read_message(&payload, &mut buf)
-                            .map_err(anyhow::Error::from)
-                            .and_then(|len| SignedMessage::deserialize_and_verify(&buf[..len])),
-                    );
+                    let len = match self.transport.read_message(&payload, &mut buf) {
+                        Ok(len) => len,
+                        Err(e) => break Some(Err(e.into())),
+                    };
+
+                    match SignedMessage::deserialize_and_verify(&buf[..len]) {
+                        Ok(msg) => break Some(Ok(msg)),
+                        Err(e) if self.bad_frames < self.error_budget => {
+                            self.bad_frames += 1;
+                            warn!(
+                                "Skipping invalid message {e} ({}/{})",
+                                self.bad_frames, self.error_budget
+                            );
+                        }
+                        Err(e) => break Some(Err(e)),
+                    }
                 }
                 Some(Ok(_)) => continue,
                 Some(Err(e)) => break Some(Err(anyhow!("Connection error: {e}"))),
@@ -74,7 +104,30 @@ where
 }
 
 /// Creates an [EncryptedConnection] from a server stream.
+///
+/// Returns an error if the client doesn't complete the handshake within
+/// [HANDSHAKE_TIMEOUT].
 pub async fn accept_async<S>(stream: S) -> Result<EncryptedConnection<S>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    accept_async_with_timeout(stream, HANDSHAKE_TIMEOUT).await
+}
+
+/// Creates an [EncryptedConnection] from a server stream with a handshake timeout.
+pub async fn accept_async_with_timeout<S>(
+    stream: S,
+    timeout: Duration,
+) -> Result<EncryptedConnection<S>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    time::timeout(timeout, accept_handshake(stream))
+        .await
+        .map_err(|_| anyhow!("Responder handshake timed out after {timeout:?}"))?
+}
+
+async fn accept_handshake<S>(stream: S) -> Result<EncryptedConnection<S>>
 where
     S: AsyncRead + AsyncWrite + Unpin,
 {
@@ -107,11 +160,43 @@ where
 
     let transport = noise.into_transport_mode()?;
 
-    Ok(EncryptedConnection { stream, transport })
+    Ok(EncryptedConnection {
+        stream,
+        transport,
+        error_budget: 0,
+        bad_frames: 0,
+    })
+}
+
+/// Returns the WebSocket url for a server address.
+///
+/// The address is a `host:port` or a url, if `tls` is set a `ws://` url or a
+/// `host:port` address becomes a `wss://` url that connects over TLS.
+pub fn server_url(addr: &str, tls: bool) -> String {
+    match addr.split_once("://") {
+        Some(("ws", rest)) if tls => format!("wss://{rest}"),
+        Some(_) => addr.to_string(),
+        None if tls => format!("wss://{addr}"),
+        None => format!("ws://{addr}"),
+    }
 }
 
 /// Connects to a server and returns an [EncryptedConnection] if successful.
+///
+/// Returns an error if the server doesn't complete the handshake within
+/// [HANDSHAKE_TIMEOUT].
 pub async fn connect_async(url: &str) -> Result<ClientConnection> {
+    connect_async_with_timeout(url, HANDSHAKE_TIMEOUT).await
+}
+
+/// Connects to a server with a handshake timeout.
+pub async fn connect_async_with_timeout(url: &str, timeout: Duration) -> Result<ClientConnection> {
+    time::timeout(timeout, connect_handshake(url))
+        .await
+        .map_err(|_| anyhow!("Initiator handshake timed out after {timeout:?}"))?
+}
+
+async fn connect_handshake(url: &str) -> Result<ClientConnection> {
     let config = WebSocketConfig::default().max_message_size(Some(MAX_MSG_LEN));
     let (mut stream, _) = websocket::connect_async_with_config(url, Some(config), false).await?;
 
@@ -141,7 +226,12 @@ pub async fn connect_async(url: &str) -> Result<ClientConnection> {
     };
 
     let transport = noise.into_transport_mode()?;
-    Ok(EncryptedConnection { stream, transport })
+    Ok(EncryptedConnection {
+        stream,
+        transport,
+        error_budget: 0,
+        bad_frames: 0,
+    })
 }
 
 #[cfg(test)]
@@ -150,6 +240,15 @@ mod tests {
     use crate::{crypto::SigningKey, message::Message};
     use tokio::net::TcpListener;
 
+    async fn send_invalid(con: &mut ClientConnection) {
+        let mut buf = BytesMut::zeroed(MAX_MSG_LEN);
+        let len = con.transport.write_message(b"garbage", &mut buf).unwrap();
+        con.stream
+            .send(WsMessage::binary(buf.freeze().slice(..len)))
+            .await
+            .unwrap();
+    }
+
     #[tokio::test]
     async fn encrypted_websocket_connection() {
         let addr = "127.0.0.1:12345";
@@ -186,4 +285,119 @@ mod tests {
 
         rx.await.unwrap();
     }
+
+    #[tokio::test]
+    async fn stalled_handshake_timeout() {
+        let timeout = Duration::from_millis(200);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let start = time::Instant::now();
+            let res = accept_async_with_timeout(stream, timeout).await;
+            (res.is_err(), start.elapsed())
+        });
+
+        // Open the WebSocket but never start the Noise handshake.
+        let url = format!("ws://{addr}");
+        let (_stream, _) = websocket::connect_async(&url).await.unwrap();
+
+        let (is_err, elapsed) = server.await.unwrap();
+        assert!(is_err);
+        assert!(elapsed >= timeout && elapsed < timeout * 5);
+    }
+
+    #[tokio::test]
+    async fn skip_invalid_messages() {
+        const BUDGET: usize = 3;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut con = accept_async(stream).await.unwrap();
+            con.set_error_budget(BUDGET);
+
+            // A few invalid messages are skipped.
+            let msg = con.recv().await.unwrap().unwrap();
+            assert!(matches!(msg.message(), Message::JoinTable));
+
+            // Too many invalid messages return an error.
+            con.recv().await.unwrap().is_err()
+        });
+
+        let url = format!("ws://{addr}");
+        let mut con = connect_async(&url).await.unwrap();
+
+        for _ in 0..BUDGET {
+            send_invalid(&mut con).await;
+        }
+
+        let msg = SignedMessage::new(&SigningKey::default(), Message::JoinTable);
+        con.send(&msg).await.unwrap();
+
+        for _ in 0..=BUDGET {
+            send_invalid(&mut con).await;
+        }
+
+        assert!(server.await.unwrap());
+    }
+
+    #[test]
+    fn tls_server_url() {
+        assert_eq!(server_url("127.0.0.1:9871", false), "ws://127.0.0.1:9871");
+        assert_eq!(server_url("127.0.0.1:9871", true), "wss://127.0.0.1:9871");
+
+        // The tls flag upgrades ws urls.
+        assert_eq!(
+            server_url("ws://127.0.0.1:9871", false),
+            "ws://127.0.0.1:9871"
+        );
+        assert_eq!(
+            server_url("ws://127.0.0.1:9871", true),
+            "wss://127.0.0.1:9871"
+        );
+        assert_eq!(
+            server_url("wss://127.0.0.1:9871", false),
+            "wss://127.0.0.1:9871"
+        );
+    }
+
+    #[tokio::test]
+    async fn interleaved_invalid_messages() {
+        const BUDGET: usize = 3;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut con = accept_async(stream).await.unwrap();
+            con.set_error_budget(BUDGET);
+
+            // Valid messages between invalid ones don't restore the budget.
+            for _ in 0..BUDGET {
+                let msg = con.recv().await.unwrap().unwrap();
+                assert!(matches!(msg.message(), Message::JoinTable));
+            }
+
+            con.recv().await.unwrap().is_err()
+        });
+
+        let url = format!("ws://{addr}");
+        let mut con = connect_async(&url).await.unwrap();
+
+        let msg = SignedMessage::new(&SigningKey::default(), Message::JoinTable);
+        for _ in 0..BUDGET {
+            send_invalid(&mut con).await;
+            con.send(&msg).await.unwrap();
+        }
+
+        send_invalid(&mut con).await;
+        con.send(&msg).await.unwrap();
+
+        assert!(server.await.unwrap());
+    }
 }
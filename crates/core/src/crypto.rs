@@ -3,21 +3,55 @@
 
 //! Cryptographic types for signing messages.
 use anyhow::{Result, bail};
-use bip39::Mnemonic;
+use bip39::{ErrorKind, Mnemonic};
 use blake2::{Blake2s, Digest, digest, digest::typenum::ToInt};
 use ed25519_dalek::{Signer, Verifier};
 use rand::{CryptoRng, RngCore, SeedableRng, rngs::StdRng};
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::{fmt, str::FromStr};
+use thiserror::Error;
 use zeroize::Zeroizing;
 
+/// The entropy length of new keys, a 12 words phrase.
 const ENTROPY_LEN: usize = 16;
-type Entropy = [u8; ENTROPY_LEN];
+
+/// The number of words of the supported mnemonic phrases.
+const MNEMONIC_WORDS: [usize; 2] = [12, 24];
 
 /// A key for signing messages.
 pub struct SigningKey {
     key: ed25519_dalek::SigningKey,
-    entropy: Zeroizing<Entropy>,
+    entropy: Zeroizing<Vec<u8>>,
+}
+
+/// An error from parsing a mnemonic phrase.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MnemonicError {
+    /// The phrase doesn't have 12 or 24 words.
+    #[error("invalid number of words {0}")]
+    WordCount(usize),
+    /// The word at the given position is not in the BIP39 english words list.
+    #[error("invalid word at position {0}")]
+    InvalidWord(usize),
+    /// The phrase checksum doesn't match, a word may have been mistyped.
+    #[error("invalid checksum")]
+    InvalidChecksum,
+    /// The phrase entropy has an invalid size in bits.
+    #[error("invalid entropy size {0} bits")]
+    InvalidEntropy(usize),
+}
+
+impl From<ErrorKind> for MnemonicError {
+    fn from(e: ErrorKind) -> Self {
+        match e {
+            ErrorKind::InvalidChecksum => Self::InvalidChecksum,
+            ErrorKind::InvalidWord(idx) => Self::InvalidWord(idx),
+            ErrorKind::InvalidWordLength(count) => Self::WordCount(count),
+            ErrorKind::InvalidKeysize(bits) | ErrorKind::InvalidEntropyLength(bits, _) => {
+                Self::InvalidEntropy(bits)
+            }
+        }
+    }
 }
 
 /// The hasher used for signatures.
@@ -36,14 +70,31 @@ impl SigningKey {
     where
         S: AsRef<str>,
     {
-        let mnemonic = Mnemonic::from_phrase(phrase.as_ref(), Default::default())?;
-        if mnemonic.entropy().len() != ENTROPY_LEN {
-            bail!("Invalid passphrase length");
+        Ok(Self::from_mnemonic(phrase.as_ref())?)
+    }
+
+    /// Create a signing key from a 12 or 24 words BIP39 english mnemonic.
+    ///
+    /// The key is derived from the mnemonic entropy, so that the same words
+    /// always give the same key.
+    pub fn from_mnemonic(words: &str) -> Result<Self, MnemonicError> {
+        let count = words.split_whitespace().count();
+        if !MNEMONIC_WORDS.contains(&count) {
+            return Err(MnemonicError::WordCount(count));
         }
 
-        let mut entropy = Entropy::default();
-        entropy.copy_from_slice(mnemonic.entropy());
-        Ok(Self::from_entropy(entropy))
+        let mnemonic = Mnemonic::from_phrase(words, Default::default())?;
+
+        Ok(Self::from_entropy(mnemonic.entropy()))
+    }
+
+    /// Get the BIP39 english mnemonic of this key.
+    pub fn to_mnemonic(&self) -> String {
+        // This should never fail as the entropy comes from a valid mnemonic.
+        Mnemonic::from_entropy(&self.entropy, Default::default())
+            .unwrap()
+            .phrase()
+            .to_string()
     }
 
     /// Sign a message.
@@ -58,11 +109,7 @@ impl SigningKey {
 
     /// Get the secret key phrase.
     pub fn phrase(&self) -> String {
-        // This should never fail as we control the entropy size.
-        Mnemonic::from_entropy(self.entropy.as_ref(), Default::default())
-            .unwrap()
-            .phrase()
-            .to_string()
+        self.to_mnemonic()
     }
 
     /// Get the signature verifying key.
@@ -71,16 +118,16 @@ impl SigningKey {
     }
 
     fn from_crypto_rng<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
-        let mut entropy = Entropy::default();
-        rng.fill_bytes(&mut entropy);
-        Self::from_entropy(entropy)
+        let mut entropy = Zeroizing::new([0; ENTROPY_LEN]);
+        rng.fill_bytes(entropy.as_mut());
+        Self::from_entropy(entropy.as_ref())
     }
 
-    fn from_entropy(entropy: Entropy) -> Self {
-        // Hash 128 bits entropy to 256 bits SigningKey.
+    fn from_entropy(entropy: &[u8]) -> Self {
+        // Hash 128 or 256 bits entropy to 256 bits SigningKey.
         let key_hash = SigHasher::digest(entropy);
         let key = ed25519_dalek::SigningKey::from_bytes(&key_hash.into());
-        let entropy = Zeroizing::new(entropy);
+        let entropy = Zeroizing::new(entropy.to_vec());
         Self { key, entropy }
     }
 }
@@ -99,6 +146,13 @@ impl fmt::Debug for SigningKey {
 #[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Signature(ed25519_dalek::Signature);
 
+impl Signature {
+    /// Returns the signature bytes.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        self.0.to_bytes()
+    }
+}
+
 impl fmt::Debug for Signature {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -109,6 +163,44 @@ impl fmt::Debug for Signature {
     }
 }
 
+/// A deterministic hash of a completed hand used for auditing.
+#[derive(Clone, Copy, Serialize, Deserialize, Hash, Eq, PartialEq)]
+pub struct HandHash([u8; digest::consts::U32::INT]);
+
+impl HandHash {
+    /// Hashes the serialized representation of a value.
+    pub fn new<T>(value: &T) -> Self
+    where
+        T: Serialize,
+    {
+        let mut hasher = SigHasher::new();
+        bincode::serialize_into(&mut hasher, value).expect("should serialize to hasher");
+        HandHash(hasher.finalize().into())
+    }
+
+    /// The hex digits for this hash.
+    pub fn digits(&self) -> String {
+        self.0
+            .iter()
+            .fold(String::with_capacity(64), |mut output, b| {
+                output.push_str(&format!("{b:02X}"));
+                output
+            })
+    }
+}
+
+impl fmt::Debug for HandHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HandHash({})", self.digits())
+    }
+}
+
+impl fmt::Display for HandHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.digits())
+    }
+}
+
 /// Key for signature verification.
 #[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct VerifyingKey(ed25519_dalek::VerifyingKey);
@@ -147,6 +239,9 @@ impl fmt::Debug for VerifyingKey {
 pub struct PeerId([u8; digest::consts::U16::INT]);
 
 impl PeerId {
+    /// Minimum number of digits in a [PeerId::short_unique] abbreviation.
+    pub const SHORT_MIN_DIGITS: usize = 4;
+
     /// The hex digits for this peer id.
     pub fn digits(&self) -> String {
         self.0
@@ -156,6 +251,65 @@ impl PeerId {
                 output
             })
     }
+
+    /// A short fingerprint to recognize a peer at a glance, e.g. "3FA2-91C0".
+    pub fn fingerprint(&self) -> String {
+        let b = &self.0;
+        format!("{:02X}{:02X}-{:02X}{:02X}", b[0], b[1], b[2], b[3])
+    }
+
+    /// The shortest prefix of this peer id digits that is not a prefix of any
+    /// other peer id in `among`, with at least [PeerId::SHORT_MIN_DIGITS] digits.
+    ///
+    /// This peer id can be included in `among`, labels built from the ids of a
+    /// group of peers are all different.
+    pub fn short_unique(&self, among: &[PeerId]) -> String {
+        let digits = self.digits();
+        let common = among
+            .iter()
+            .filter(|id| *id != self)
+            .map(|id| {
+                let other = id.digits();
+                digits
+                    .bytes()
+                    .zip(other.bytes())
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            })
+            .max()
+            .unwrap_or(0);
+
+        let len = (common + 1).clamp(Self::SHORT_MIN_DIGITS, digits.len());
+        digits[..len].to_string()
+    }
+
+    /// An RGB color derived from this peer id to show with its fingerprint.
+    ///
+    /// Channels are kept bright enough to be readable on a dark background.
+    pub fn color(&self) -> [u8; 3] {
+        let b = &self.0;
+        [0x60 + b[4] % 0xA0, 0x60 + b[5] % 0xA0, 0x60 + b[6] % 0xA0]
+    }
+}
+
+impl FromStr for PeerId {
+    type Err = anyhow::Error;
+
+    /// Parses a peer id from its hex digits.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut id = [0u8; digest::consts::U16::INT];
+        if s.len() != id.len() * 2 || !s.is_ascii() {
+            bail!("Invalid peer id length");
+        }
+
+        for (b, digits) in id.iter_mut().zip(s.as_bytes().chunks(2)) {
+            // Digits are ASCII so this cannot fail.
+            let digits = std::str::from_utf8(digits).unwrap();
+            *b = u8::from_str_radix(digits, 16)?;
+        }
+
+        Ok(PeerId(id))
+    }
 }
 
 impl fmt::Debug for PeerId {
@@ -181,6 +335,39 @@ mod tests {
         assert_eq!(sk.key, from_phrase.key);
     }
 
+    #[test]
+    fn peer_id_from_str() {
+        let peer_id = SigningKey::default().verifying_key().peer_id();
+        let parsed = peer_id.digits().parse::<PeerId>().unwrap();
+        assert_eq!(parsed, peer_id);
+
+        let parsed = peer_id.digits().to_lowercase().parse::<PeerId>().unwrap();
+        assert_eq!(parsed, peer_id);
+
+        assert!("0011".parse::<PeerId>().is_err());
+        assert!("ZZ".repeat(16).parse::<PeerId>().is_err());
+    }
+
+    #[test]
+    fn peer_id_fingerprint() {
+        let peer_id = "3FA291C0D5E6F70011223344556677AB"
+            .parse::<PeerId>()
+            .unwrap();
+        assert_eq!(peer_id.fingerprint(), "3FA2-91C0");
+        assert_eq!(peer_id.color(), [0x60 + 0x35, 0x60 + 0x46, 0x60 + 0x57]);
+
+        // The same key always gives the same fingerprint and color.
+        let peer_id = SigningKey::default().verifying_key().peer_id();
+        let parsed = peer_id.digits().parse::<PeerId>().unwrap();
+        assert_eq!(peer_id.fingerprint(), parsed.fingerprint());
+        assert_eq!(peer_id.color(), parsed.color());
+        assert!(
+            peer_id
+                .digits()
+                .starts_with(&peer_id.fingerprint().replace('-', ""))
+        );
+    }
+
     #[test]
     fn sign() {
         #[derive(Serialize)]
@@ -202,4 +389,87 @@ mod tests {
         let msg = Point { x: 10.2001, y: 4.3 };
         assert!(!vk.verify(&msg, &sig));
     }
+
+    #[test]
+    fn peer_id_short_unique() {
+        let ids = [
+            "3FA291C0D5E6F70011223344556677AB",
+            "3FA291C0D5E6F70011223344556677AC",
+            "3FA291FFD5E6F70011223344556677AB",
+            "0011223344556677889900AABBCCDDEE",
+        ]
+        .map(|s| s.parse::<PeerId>().unwrap());
+
+        let short = ids
+            .iter()
+            .map(|id| id.short_unique(&ids))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            short,
+            vec![
+                "3FA291C0D5E6F70011223344556677AB",
+                "3FA291C0D5E6F70011223344556677AC",
+                "3FA291F",
+                "0011",
+            ]
+        );
+
+        // Abbreviations are distinct prefixes of the ids.
+        for (i, s) in short.iter().enumerate() {
+            assert!(ids[i].digits().starts_with(s.as_str()));
+            assert!(short.iter().filter(|o| *o == s).count() == 1);
+        }
+
+        // Without other ids the minimum number of digits is used.
+        assert_eq!(ids[0].short_unique(&[]), "3FA2");
+        assert_eq!(ids[0].short_unique(&ids[..1]), "3FA2");
+    }
+
+    #[test]
+    fn keypair_mnemonic() {
+        let sk = SigningKey::default();
+        assert_eq!(sk.to_mnemonic().split_whitespace().count(), 12);
+        assert_eq!(sk.to_mnemonic(), sk.phrase());
+
+        let from_mnemonic = SigningKey::from_mnemonic(&sk.to_mnemonic()).unwrap();
+        assert_eq!(sk.key, from_mnemonic.key);
+
+        // 24 words phrases round trip.
+        let words = Mnemonic::from_entropy(&[7; 32], Default::default())
+            .unwrap()
+            .phrase()
+            .to_string();
+        let sk = SigningKey::from_mnemonic(&words).unwrap();
+        assert_eq!(sk.to_mnemonic(), words);
+        assert_eq!(SigningKey::from_phrase(&words).unwrap().key, sk.key);
+
+        let valid = "abandon abandon abandon abandon abandon abandon \
+                     abandon abandon abandon abandon abandon about";
+        assert!(SigningKey::from_mnemonic(valid).is_ok());
+
+        let checksum = "abandon abandon abandon abandon abandon abandon \
+                        abandon abandon abandon abandon abandon abandon";
+        assert_eq!(
+            SigningKey::from_mnemonic(checksum).err(),
+            Some(MnemonicError::InvalidChecksum)
+        );
+
+        let word = "abandon abandon abandon abandon abandon abandon \
+                    abandon abandon abandon abandon abandon freezeout";
+        assert_eq!(
+            SigningKey::from_mnemonic(word).err(),
+            Some(MnemonicError::InvalidWord(11))
+        );
+
+        assert_eq!(
+            MnemonicError::from(ErrorKind::InvalidKeysize(100)),
+            MnemonicError::InvalidEntropy(100)
+        );
+
+        assert_eq!(
+            SigningKey::from_mnemonic("abandon about").err(),
+            Some(MnemonicError::WordCount(2))
+        );
+        assert!(SigningKey::from_phrase(checksum).is_err());
+    }
 }
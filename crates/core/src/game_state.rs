@@ -4,8 +4,11 @@
 //! Client game state types.
 use crate::{
     crypto::PeerId,
-    message::{HandPayoff, Message, PlayerAction, PlayerUpdate, SignedMessage},
-    poker::{Card, Chips, PlayerCards, TableId},
+    message::{
+        ActionOption, BlindLevel, HandPayoff, Message, PlayerAction, PlayerUpdate, SignedMessage,
+        Standing,
+    },
+    poker::{Card, Chips, PlayerCards, Street, TableId},
 };
 
 /// Game player data.
@@ -15,6 +18,9 @@ pub struct Player {
     pub player_id: PeerId,
     /// Cache player id digits to avoid generation at every repaint.
     pub player_id_digits: String,
+    /// The shortest player id prefix that tells this player apart from the other
+    /// players at the table, see [PeerId::short_unique].
+    pub player_id_short: String,
     /// This player nickname.
     pub nickname: String,
     /// This player chips.
@@ -33,12 +39,21 @@ pub struct Player {
     pub has_button: bool,
     /// The player is active in the hand.
     pub is_active: bool,
+    /// Seconds left in the player time bank once the action timer expires.
+    pub time_bank: Option<u16>,
+    /// Seconds left for a disconnected player to reconnect.
+    pub reconnect_timer: Option<u16>,
+    /// The player chance of winning the hand when players are all in.
+    pub equity: Option<f32>,
+    /// The player made hand, only spectators get it during all in runouts.
+    pub made_hand: Option<String>,
 }
 
 impl Player {
     fn new(player_id: PeerId, nickname: String, chips: Chips) -> Self {
         Self {
             player_id_digits: player_id.digits(),
+            player_id_short: player_id.short_unique(&[]),
             player_id,
             nickname,
             chips,
@@ -49,6 +64,10 @@ impl Player {
             cards: PlayerCards::None,
             has_button: false,
             is_active: true,
+            time_bank: None,
+            reconnect_timer: None,
+            equity: None,
+            made_hand: None,
         }
     }
 }
@@ -56,10 +75,8 @@ impl Player {
 /// A player action request from the server.
 #[derive(Debug)]
 pub struct ActionRequest {
-    /// The actions choices requested by server.
-    pub actions: Vec<PlayerAction>,
-    /// The action minimum raise
-    pub min_raise: Chips,
+    /// The actions choices requested by server with their amounts.
+    pub actions: Vec<ActionOption>,
     /// The hand big blind.
     pub big_blind: Chips,
 }
@@ -85,8 +102,75 @@ impl ActionRequest {
         self.check_action(PlayerAction::Raise)
     }
 
+    /// Returns the option for an action if the action is in the request.
+    pub fn option(&self, action: PlayerAction) -> Option<&ActionOption> {
+        self.actions.iter().find(|o| o.action == action)
+    }
+
     fn check_action(&self, action: PlayerAction) -> bool {
-        self.actions.iter().any(|a| a == &action)
+        self.option(action).is_some()
+    }
+}
+
+/// Buffers the last action sent by the local player so that it can be sent
+/// again if the connection drops before the server applies it.
+///
+/// The buffer outlives the game state of a connection, after a reconnection the
+/// action is sent again only if the server requests an action from the player and
+/// the buffered action is still one of the legal actions.
+#[derive(Debug, Default)]
+pub struct ActionBuffer {
+    action: Option<(PlayerAction, Chips)>,
+}
+
+impl ActionBuffer {
+    /// Records an action sent to the server.
+    pub fn push(&mut self, action: PlayerAction, amount: Chips) {
+        self.action = Some((action, amount));
+    }
+
+    /// Returns the buffered action if any.
+    pub fn action(&self) -> Option<(PlayerAction, Chips)> {
+        self.action
+    }
+
+    /// Updates the buffer with a server message for the given local player.
+    ///
+    /// Returns the action response to send again if the server requests an action
+    /// the player already sent.
+    pub fn handle_message(&mut self, player_id: &PeerId, msg: &Message) -> Option<Message> {
+        match msg {
+            // The server is not waiting for this player, the action was either
+            // applied or timed out.
+            Message::GameUpdate { players, .. } => {
+                let is_waiting = players
+                    .iter()
+                    .any(|p| &p.player_id == player_id && p.action_timer.is_some());
+                if !is_waiting {
+                    self.action = None;
+                }
+            }
+            Message::ActionRequest {
+                player_id: id,
+                actions,
+                ..
+            } if id == player_id => {
+                let (action, amount) = self.action?;
+                let Some(option) = actions.iter().find(|o| o.action == action) else {
+                    self.action = None;
+                    return None;
+                };
+
+                let amount = amount.clamp(option.min, option.max);
+                return Some(Message::ActionResponse { action, amount });
+            }
+            Message::StartHand { .. } | Message::EndHand { .. } => {
+                self.action = None;
+            }
+            _ => {}
+        }
+
+        None
     }
 }
 
@@ -96,13 +180,23 @@ pub struct GameState {
     player_id: PeerId,
     nickname: String,
     server_key: String,
+    server_id: Option<PeerId>,
     table_id: TableId,
     seats: usize,
     game_started: bool,
     players: Vec<Player>,
     action_request: Option<ActionRequest>,
     board: Vec<Card>,
+    extra_boards: Vec<Vec<Card>>,
     pot: Chips,
+    pots: Vec<Chips>,
+    blinds: BlindLevel,
+    paused: bool,
+    hand_in_progress: bool,
+    last_seq: Option<u64>,
+    resync: bool,
+    standings: Vec<Standing>,
+    hand_number: u64,
 }
 
 impl GameState {
@@ -113,12 +207,22 @@ impl GameState {
             nickname,
             table_id: TableId::NO_TABLE,
             server_key: String::default(),
+            server_id: None,
             seats: 0,
             game_started: false,
             players: Vec::default(),
             action_request: None,
             board: Vec::default(),
+            extra_boards: Vec::default(),
             pot: Chips::ZERO,
+            pots: Vec::default(),
+            blinds: BlindLevel::default(),
+            paused: false,
+            hand_in_progress: false,
+            last_seq: None,
+            resync: false,
+            standings: Vec::default(),
+            hand_number: 0,
         }
     }
 
@@ -133,6 +237,8 @@ impl GameState {
                 self.table_id = *table_id;
                 self.seats = *seats as usize;
                 self.server_key = msg.sender().digits();
+                self.server_id = Some(msg.sender());
+                self.last_seq = None;
 
                 // Add this player as the first player in the players list.
                 self.players.push(Player::new(
@@ -140,6 +246,7 @@ impl GameState {
                     self.nickname.clone(),
                     *chips,
                 ));
+                self.update_short_ids();
             }
             Message::PlayerJoined {
                 player_id,
@@ -148,9 +255,28 @@ impl GameState {
             } => {
                 self.players
                     .push(Player::new(player_id.clone(), nickname.clone(), *chips));
+                self.update_short_ids();
             }
             Message::PlayerLeft(player_id) => {
                 self.players.retain(|p| &p.player_id != player_id);
+                self.update_short_ids();
+            }
+            Message::PlayerDisconnected { player_id, seconds } => {
+                if let Some(p) = self.players.iter_mut().find(|p| &p.player_id == player_id) {
+                    p.reconnect_timer = Some(*seconds);
+                }
+            }
+            Message::PlayerReconnected(player_id) => {
+                if let Some(p) = self.players.iter_mut().find(|p| &p.player_id == player_id) {
+                    p.reconnect_timer = None;
+                }
+            }
+            Message::SpectatorHands { hands } => {
+                for (player_id, hand) in hands {
+                    if let Some(p) = self.players.iter_mut().find(|p| &p.player_id == player_id) {
+                        p.made_hand = Some(hand.clone());
+                    }
+                }
             }
             Message::StartGame(seats) => {
                 // Reorder seats according to the new order.
@@ -172,18 +298,32 @@ impl GameState {
                 self.players.rotate_left(pos);
 
                 self.game_started = true;
+                self.standings.clear();
+                self.hand_number = 0;
             }
-            Message::StartHand => {
+            Message::GameOver { standings } => {
+                self.standings = standings.clone();
+            }
+            Message::StartHand { hand_number } => {
+                self.hand_number = *hand_number;
+                self.paused = false;
+                self.hand_in_progress = true;
+
                 // Prepare for a new hand.
+                self.board.clear();
+                self.extra_boards.clear();
                 for player in &mut self.players {
                     player.cards = PlayerCards::None;
                     player.action = PlayerAction::None;
                     player.payoff = None;
+                    player.made_hand = None;
                 }
             }
             Message::EndHand { payoffs, .. } => {
+                self.hand_in_progress = false;
                 self.action_request = None;
                 self.pot = Chips::ZERO;
+                self.pots.clear();
 
                 // Update winnings for each winning player.
                 for payoff in payoffs {
@@ -196,6 +336,9 @@ impl GameState {
                     }
                 }
             }
+            Message::TablePaused => {
+                self.paused = true;
+            }
             Message::DealCards(c1, c2) => {
                 // This client player should be in first position.
                 assert!(!self.players.is_empty());
@@ -204,28 +347,42 @@ impl GameState {
                 self.players[0].cards = PlayerCards::Cards(*c1, *c2);
             }
             Message::GameUpdate {
+                seq,
                 players,
                 board,
+                extra_boards,
                 pot,
+                pots,
+                blinds,
             } => {
+                // Ignore updates older than the last one, if some updates have
+                // been skipped ask the server for a full update.
+                if let Some(last) = self.last_seq {
+                    if *seq < last {
+                        return;
+                    }
+
+                    self.resync |= *seq > last + 1;
+                }
+
+                self.last_seq = Some(*seq);
                 self.update_players(players);
                 self.board = board.clone();
+                self.extra_boards = extra_boards.clone();
                 self.pot = *pot;
+                self.pots = pots.clone();
+                self.blinds = *blinds;
             }
             Message::ActionRequest {
                 player_id,
-                min_raise,
                 big_blind,
                 actions,
-            } => {
-                // Check if the action has been requested for this player.
-                if &self.player_id == player_id {
-                    self.action_request = Some(ActionRequest {
-                        actions: actions.clone(),
-                        min_raise: *min_raise,
-                        big_blind: *big_blind,
-                    });
-                }
+            } if &self.player_id == player_id => {
+                // The action has been requested for this player.
+                self.action_request = Some(ActionRequest {
+                    actions: actions.clone(),
+                    big_blind: *big_blind,
+                });
             }
             _ => {}
         }
@@ -241,11 +398,23 @@ impl GameState {
         self.action_request = None;
     }
 
+    /// Returns a resync request if some game updates have been missed.
+    ///
+    /// The request is returned once, the client should send it to the server.
+    pub fn take_resync_request(&mut self) -> Option<Message> {
+        std::mem::take(&mut self.resync).then_some(Message::ResyncRequest)
+    }
+
     /// Returns the server key.
     pub fn server_key(&self) -> &str {
         &self.server_key
     }
 
+    /// Returns the server id once the table has been joined.
+    pub fn server_id(&self) -> Option<&PeerId> {
+        self.server_id.as_ref()
+    }
+
     /// Returns a reference to the players.
     pub fn players(&self) -> &[Player] {
         &self.players
@@ -256,11 +425,117 @@ impl GameState {
         self.pot
     }
 
+    /// The chips in each pot, the main pot first followed by the side pots.
+    pub fn pots(&self) -> &[Chips] {
+        &self.pots
+    }
+
+    /// Formats the pots on one line that fits in `width` characters, e.g.
+    /// "Main 300k | Side 100k".
+    ///
+    /// If all pots don't fit the side pots are summed up, and if that doesn't
+    /// fit either only the total pot is shown.
+    pub fn pots_summary(&self, width: usize) -> String {
+        let short = |chips: Chips| {
+            let amount = chips.amount();
+            if amount >= 1_000_000 {
+                format!("{:.1}M", amount as f64 / 1e6).replace(".0M", "M")
+            } else if amount >= 1_000 {
+                format!("{:.1}k", amount as f64 / 1e3).replace(".0k", "k")
+            } else {
+                amount.to_string()
+            }
+        };
+
+        let Some((main, sides)) = self.pots.split_first() else {
+            return format!("Pot {}", short(self.pot));
+        };
+
+        let mut parts = vec![format!("Main {}", short(*main))];
+        if sides.len() == 1 {
+            parts.push(format!("Side {}", short(sides[0])));
+        } else {
+            for (idx, side) in sides.iter().enumerate() {
+                parts.push(format!("Side{} {}", idx + 1, short(*side)));
+            }
+        }
+
+        let line = parts.join(" | ");
+        if line.len() <= width {
+            return line;
+        }
+
+        let sides_total = sides.iter().fold(Chips::ZERO, |acc, c| acc + *c);
+        let line = format!("Main {} | Sides {}", short(*main), short(sides_total));
+        if sides.len() > 1 && line.len() <= width {
+            return line;
+        }
+
+        format!("Pot {}", short(self.pot))
+    }
+
+    /// The current pot plus the bets of this betting round.
+    pub fn total_pot(&self) -> Chips {
+        self.players.iter().fold(self.pot, |acc, p| acc + p.bet)
+    }
+
+    /// Returns the chips the local player has to add to call if a call has been
+    /// requested.
+    ///
+    /// The server caps the call to the local player chips, when calling an all in
+    /// for more than the player chips this is the amount for the player to go all in.
+    pub fn call_amount(&self) -> Option<Chips> {
+        let call = self.action_request.as_ref()?.option(PlayerAction::Call)?;
+        let bet = self.players.first().map(|p| p.bet).unwrap_or_default();
+        Some(if call.min > bet {
+            call.min - bet
+        } else {
+            Chips::ZERO
+        })
+    }
+
+    /// Checks if the local player goes all in by calling.
+    pub fn call_is_all_in(&self) -> bool {
+        let chips = self.players.first().map(|p| p.chips).unwrap_or_default();
+        self.call_amount().is_some_and(|call| call >= chips)
+    }
+
+    /// Checks if the local player goes all in by betting the given amount.
+    ///
+    /// The amount is the player total bet in this round as in an action response.
+    pub fn would_be_all_in(&self, amount: Chips) -> bool {
+        self.players
+            .first()
+            .is_some_and(|p| amount >= p.chips + p.bet)
+    }
+
+    /// The current blinds level.
+    pub fn blinds(&self) -> &BlindLevel {
+        &self.blinds
+    }
+
     /// The board cards.
     pub fn board(&self) -> &[Card] {
         &self.board
     }
 
+    /// The street of the current hand.
+    ///
+    /// After the hand has ended and until the next hand starts the street is
+    /// [Street::Showdown] if any board card was dealt.
+    pub fn street(&self) -> Street {
+        if !self.hand_in_progress && !self.board.is_empty() {
+            Street::Showdown
+        } else {
+            Street::from_board_len(self.board.len())
+        }
+    }
+
+    /// The boards after the first one in multiple boards games.
+    pub fn extra_boards(&self) -> &[Vec<Card>] {
+        &self.extra_boards
+    }
+
     /// The number of seats at this table.
     pub fn seats(&self) -> usize {
         self.seats
@@ -271,11 +546,57 @@ impl GameState {
         self.game_started
     }
 
+    /// The number of the current or last hand in the game, zero before the first
+    /// hand.
+    pub fn hand_number(&self) -> u64 {
+        self.hand_number
+    }
+
+    /// The final standings of the last game, empty if the game has not ended.
+    pub fn standings(&self) -> &[Standing] {
+        &self.standings
+    }
+
+    /// Checks if the table is paused waiting for the next hand.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
     /// Checks if the local player is active.
     pub fn is_active(&self) -> bool {
         !self.players.is_empty() && self.players[0].is_active
     }
 
+    /// Checks if leaving the table should be confirmed by the player.
+    ///
+    /// Leaving while the local player is still in a hand forfeits the player bets.
+    pub fn leave_needs_confirmation(&self) -> bool {
+        self.hand_in_progress && self.is_active()
+    }
+
+    /// Checks if any player has a running countdown, i.e. an action timer, a
+    /// time bank, or a reconnect timer.
+    ///
+    /// Clients can use this to keep refreshing the countdowns while they are
+    /// shown and otherwise wait for the next message.
+    pub fn has_countdown(&self) -> bool {
+        self.players.iter().any(|p| {
+            p.action_timer.is_some() || p.time_bank.is_some() || p.reconnect_timer.is_some()
+        })
+    }
+
+    /// Updates the players short ids after players joined or left.
+    fn update_short_ids(&mut self) {
+        let ids = self
+            .players
+            .iter()
+            .map(|p| p.player_id.clone())
+            .collect::<Vec<_>>();
+        for player in &mut self.players {
+            player.player_id_short = player.player_id.short_unique(&ids);
+        }
+    }
+
     fn update_players(&mut self, updates: &[PlayerUpdate]) {
         for update in updates {
             if let Some(pos) = self
@@ -288,8 +609,10 @@ impl GameState {
                 player.bet = update.bet;
                 player.action = update.action;
                 player.action_timer = update.action_timer;
+                player.time_bank = update.time_bank;
                 player.has_button = update.has_button;
                 player.is_active = update.is_active;
+                player.equity = update.equity;
 
                 // Do not override cards for the local player as they are updated
                 // when we get a DealCards message.
@@ -306,3 +629,429 @@ impl GameState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        crypto::{HandHash, SigningKey},
+        poker::Deck,
+    };
+
+    #[test]
+    fn blinds_level() {
+        let sk = SigningKey::default();
+        let mut state = GameState::new(sk.verifying_key().peer_id(), "alice".to_string());
+        assert_eq!(state.blinds(), &BlindLevel::default());
+
+        // Level 2 with 3 hands left before the next increase.
+        let blinds = BlindLevel {
+            level: 2,
+            small_blind: Chips::new(20_000),
+            big_blind: Chips::new(40_000),
+            hands_to_next: Some(3),
+        };
+
+        let msg = Message::GameUpdate {
+            seq: 1,
+            players: Vec::default(),
+            board: Vec::default(),
+            extra_boards: Vec::default(),
+            pot: Chips::ZERO,
+            pots: Vec::default(),
+            blinds,
+        };
+        state.handle_message(SignedMessage::new(&sk, msg));
+
+        assert_eq!(state.blinds(), &blinds);
+    }
+
+    #[test]
+    fn leave_needs_confirmation() {
+        let sk = SigningKey::default();
+        let player_id = sk.verifying_key().peer_id();
+        let mut state = GameState::new(player_id.clone(), "alice".to_string());
+
+        let msg = Message::TableJoined {
+            table_id: TableId::new_id(),
+            chips: Chips::new(100_000),
+            seats: 2,
+        };
+        state.handle_message(SignedMessage::new(&sk, msg));
+        assert!(!state.leave_needs_confirmation());
+
+        // Mid-hand leaving requires confirmation.
+        state.handle_message(SignedMessage::new(
+            &sk,
+            Message::StartHand { hand_number: 1 },
+        ));
+        assert!(state.leave_needs_confirmation());
+
+        // Between hands it doesn't.
+        let msg = Message::EndHand {
+            payoffs: Vec::default(),
+            board: Vec::default(),
+            extra_boards: Vec::default(),
+            cards: Vec::default(),
+            rake: Chips::ZERO,
+            hash: HandHash::new(&"hand"),
+        };
+        state.handle_message(SignedMessage::new(&sk, msg));
+        assert!(!state.leave_needs_confirmation());
+
+        // If the player has folded there is nothing left to lose.
+        state.handle_message(SignedMessage::new(
+            &sk,
+            Message::StartHand { hand_number: 1 },
+        ));
+        let msg = Message::GameUpdate {
+            seq: 1,
+            players: vec![PlayerUpdate {
+                player_id,
+                chips: Chips::new(100_000),
+                bet: Chips::ZERO,
+                action: PlayerAction::Fold,
+                action_timer: None,
+                time_bank: None,
+                cards: PlayerCards::None,
+                has_button: false,
+                is_active: false,
+                equity: None,
+            }],
+            board: Vec::default(),
+            extra_boards: Vec::default(),
+            pot: Chips::ZERO,
+            pots: Vec::default(),
+            blinds: BlindLevel::default(),
+        };
+        state.handle_message(SignedMessage::new(&sk, msg));
+        assert!(!state.leave_needs_confirmation());
+    }
+
+    #[test]
+    fn resync_on_sequence_gap() {
+        let sk = SigningKey::default();
+        let mut state = GameState::new(sk.verifying_key().peer_id(), "alice".to_string());
+
+        let update = |seq, pot| {
+            let msg = Message::GameUpdate {
+                seq,
+                players: Vec::default(),
+                board: Vec::default(),
+                extra_boards: Vec::default(),
+                pot: Chips::new(pot),
+                pots: Vec::default(),
+                blinds: BlindLevel::default(),
+            };
+            SignedMessage::new(&sk, msg)
+        };
+
+        // Consecutive updates don't need a resync.
+        state.handle_message(update(1, 10_000));
+        state.handle_message(update(2, 20_000));
+        assert!(state.take_resync_request().is_none());
+
+        // A gap in the sequence requests a resync once.
+        state.handle_message(update(4, 40_000));
+        assert_eq!(state.pot(), Chips::new(40_000));
+        assert!(matches!(
+            state.take_resync_request(),
+            Some(Message::ResyncRequest)
+        ));
+        assert!(state.take_resync_request().is_none());
+
+        // Stale updates are ignored.
+        state.handle_message(update(3, 30_000));
+        assert_eq!(state.pot(), Chips::new(40_000));
+        assert!(state.take_resync_request().is_none());
+
+        // The full update sent by the server has the last sequence number.
+        state.handle_message(update(4, 40_000));
+        assert!(state.take_resync_request().is_none());
+    }
+
+    #[test]
+    fn street() {
+        let sk = SigningKey::default();
+        let mut state = GameState::new(sk.verifying_key().peer_id(), "alice".to_string());
+        state.handle_message(SignedMessage::new(
+            &sk,
+            Message::StartHand { hand_number: 1 },
+        ));
+
+        let board = Deck::default().remaining()[..5].to_vec();
+        let streets = [
+            (0, Street::Preflop),
+            (3, Street::Flop),
+            (4, Street::Turn),
+            (5, Street::River),
+        ];
+        for (seq, (len, street)) in streets.into_iter().enumerate() {
+            let msg = Message::GameUpdate {
+                seq: seq as u64,
+                players: Vec::default(),
+                board: board[..len].to_vec(),
+                extra_boards: Vec::default(),
+                pot: Chips::ZERO,
+                pots: Vec::default(),
+                blinds: BlindLevel::default(),
+            };
+            state.handle_message(SignedMessage::new(&sk, msg));
+            assert_eq!(state.street(), street);
+        }
+
+        // The hand has ended.
+        let msg = Message::EndHand {
+            payoffs: Vec::default(),
+            board,
+            extra_boards: Vec::default(),
+            cards: Vec::default(),
+            rake: Chips::ZERO,
+            hash: HandHash::new(&"hand"),
+        };
+        state.handle_message(SignedMessage::new(&sk, msg));
+        assert_eq!(state.street(), Street::Showdown);
+
+        assert_eq!(state.hand_number(), 1);
+
+        // A new hand starts preflop.
+        state.handle_message(SignedMessage::new(
+            &sk,
+            Message::StartHand { hand_number: 2 },
+        ));
+        assert_eq!(state.street(), Street::Preflop);
+        assert_eq!(state.hand_number(), 2);
+    }
+
+    #[test]
+    fn action_buffer_resend() {
+        let sk = SigningKey::default();
+        let player_id = sk.verifying_key().peer_id();
+        let mut buffer = ActionBuffer::default();
+
+        let update = |action_timer| Message::GameUpdate {
+            seq: 1,
+            players: vec![PlayerUpdate {
+                player_id: player_id.clone(),
+                chips: Chips::new(100_000),
+                bet: Chips::ZERO,
+                action: PlayerAction::None,
+                action_timer,
+                time_bank: None,
+                cards: PlayerCards::Covered,
+                has_button: false,
+                is_active: true,
+                equity: None,
+            }],
+            board: Vec::default(),
+            extra_boards: Vec::default(),
+            pot: Chips::ZERO,
+            pots: Vec::default(),
+            blinds: BlindLevel::default(),
+        };
+
+        let request = Message::ActionRequest {
+            player_id: player_id.clone(),
+            big_blind: Chips::new(20_000),
+            actions: vec![
+                ActionOption::fixed(PlayerAction::Fold, Chips::ZERO),
+                ActionOption::fixed(PlayerAction::Call, Chips::new(20_000)),
+                ActionOption {
+                    action: PlayerAction::Raise,
+                    min: Chips::new(40_000),
+                    max: Chips::new(100_000),
+                },
+            ],
+        };
+
+        // The player raises and the connection drops before the server gets it.
+        buffer.push(PlayerAction::Raise, Chips::new(60_000));
+
+        // After reconnecting the server is still waiting for the player action.
+        assert!(
+            buffer
+                .handle_message(&player_id, &update(Some(10)))
+                .is_none()
+        );
+        let msg = buffer.handle_message(&player_id, &request).unwrap();
+        assert!(matches!(
+            msg,
+            Message::ActionResponse {
+                action: PlayerAction::Raise,
+                amount,
+            } if amount == Chips::new(60_000)
+        ));
+
+        // The server applies the action.
+        assert!(buffer.handle_message(&player_id, &update(None)).is_none());
+        assert!(buffer.action().is_none());
+
+        // An action applied before the connection dropped is not sent again.
+        buffer.push(PlayerAction::Call, Chips::ZERO);
+        buffer.handle_message(&player_id, &update(None));
+        assert!(buffer.handle_message(&player_id, &request).is_none());
+
+        // An action that is no longer legal is dropped.
+        buffer.push(PlayerAction::Check, Chips::ZERO);
+        assert!(buffer.handle_message(&player_id, &request).is_none());
+        assert!(buffer.action().is_none());
+    }
+
+    #[test]
+    fn pots_summary() {
+        let sk = SigningKey::default();
+        let player_id = sk.verifying_key().peer_id();
+        let mut state = GameState::new(player_id, "alice".to_string());
+        assert_eq!(state.pots_summary(30), "Pot 0");
+
+        let update = |state: &mut GameState, pots: &[u32]| {
+            let pots = pots.iter().copied().map(Chips::new).collect::<Vec<_>>();
+            let msg = Message::GameUpdate {
+                seq: 1,
+                players: Vec::default(),
+                board: Vec::default(),
+                extra_boards: Vec::default(),
+                pot: pots.iter().fold(Chips::ZERO, |acc, c| acc + *c),
+                pots,
+                blinds: BlindLevel::default(),
+            };
+            state.handle_message(SignedMessage::new(&sk, msg));
+            state.last_seq = None;
+        };
+
+        update(&mut state, &[300_000, 100_000]);
+        assert_eq!(state.pots(), &[Chips::new(300_000), Chips::new(100_000)]);
+        assert_eq!(state.pots_summary(30), "Main 300k | Side 100k");
+
+        update(&mut state, &[1_500_000, 250_000, 40_500]);
+        let summary = state.pots_summary(40);
+        assert_eq!(summary, "Main 1.5M | Side1 250k | Side2 40.5k");
+        assert!(summary.len() <= 40);
+
+        // Side pots are summed up if they don't fit the width.
+        let summary = state.pots_summary(30);
+        assert_eq!(summary, "Main 1.5M | Sides 290.5k");
+        assert!(summary.len() <= 30);
+
+        // Only the total is shown in very narrow lines.
+        assert_eq!(state.pots_summary(12), "Pot 1.8M");
+    }
+
+    #[test]
+    fn countdown() {
+        let sk = SigningKey::default();
+        let player_id = sk.verifying_key().peer_id();
+        let mut state = GameState::new(player_id.clone(), "alice".to_string());
+
+        let msg = Message::TableJoined {
+            table_id: TableId::new_id(),
+            chips: Chips::new(100_000),
+            seats: 2,
+        };
+        state.handle_message(SignedMessage::new(&sk, msg));
+        assert!(!state.has_countdown());
+
+        let update = |seq, action_timer, time_bank| {
+            let msg = Message::GameUpdate {
+                seq,
+                players: vec![PlayerUpdate {
+                    player_id: player_id.clone(),
+                    chips: Chips::new(100_000),
+                    bet: Chips::ZERO,
+                    action: PlayerAction::None,
+                    action_timer,
+                    time_bank,
+                    cards: PlayerCards::None,
+                    has_button: false,
+                    is_active: true,
+                    equity: None,
+                }],
+                board: Vec::default(),
+                extra_boards: Vec::default(),
+                pot: Chips::ZERO,
+                pots: Vec::default(),
+                blinds: BlindLevel::default(),
+            };
+            SignedMessage::new(&sk, msg)
+        };
+
+        // A running action timer or time bank needs refreshing.
+        state.handle_message(update(1, Some(10), None));
+        assert!(state.has_countdown());
+        state.handle_message(update(2, Some(0), Some(20)));
+        assert!(state.has_countdown());
+
+        // Once the player acts the state is idle.
+        state.handle_message(update(3, None, None));
+        assert!(!state.has_countdown());
+    }
+
+    #[test]
+    fn call_is_all_in() {
+        let sk = SigningKey::default();
+        let player_id = sk.verifying_key().peer_id();
+
+        let state_with_chips = |chips| {
+            let mut state = GameState::new(player_id.clone(), "alice".to_string());
+            let msgs = [
+                Message::TableJoined {
+                    table_id: TableId::new_id(),
+                    chips: Chips::new(chips),
+                    seats: 2,
+                },
+                Message::GameUpdate {
+                    seq: 1,
+                    players: vec![PlayerUpdate {
+                        player_id: player_id.clone(),
+                        chips: Chips::new(chips),
+                        bet: Chips::new(10_000),
+                        action: PlayerAction::SmallBlind,
+                        action_timer: None,
+                        time_bank: None,
+                        cards: PlayerCards::None,
+                        has_button: false,
+                        is_active: true,
+                        equity: None,
+                    }],
+                    board: Vec::default(),
+                    extra_boards: Vec::default(),
+                    pot: Chips::ZERO,
+                    pots: Vec::default(),
+                    blinds: BlindLevel::default(),
+                },
+                // The server caps the call to the player chips.
+                Message::ActionRequest {
+                    player_id: player_id.clone(),
+                    big_blind: Chips::new(20_000),
+                    actions: vec![
+                        ActionOption::fixed(PlayerAction::Fold, Chips::ZERO),
+                        ActionOption::fixed(
+                            PlayerAction::Call,
+                            Chips::new(60_000.min(chips + 10_000)),
+                        ),
+                    ],
+                },
+            ];
+            for msg in msgs {
+                state.handle_message(SignedMessage::new(&sk, msg));
+            }
+            state
+        };
+
+        // Calling 50k with a 100k stack.
+        let state = state_with_chips(100_000);
+        assert_eq!(state.call_amount(), Some(Chips::new(50_000)));
+        assert!(!state.call_is_all_in());
+        assert!(!state.would_be_all_in(Chips::new(60_000)));
+        assert!(state.would_be_all_in(Chips::new(110_000)));
+
+        // Calling 50k with exactly 50k left.
+        let state = state_with_chips(50_000);
+        assert!(state.call_is_all_in());
+
+        // Calling with fewer chips than the bet.
+        let state = state_with_chips(30_000);
+        assert_eq!(state.call_amount(), Some(Chips::new(30_000)));
+        assert!(state.call_is_all_in());
+    }
+}
@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use crate::{
-    crypto::{PeerId, Signature, SigningKey, VerifyingKey},
+    crypto::{HandHash, PeerId, Signature, SigningKey, VerifyingKey},
     poker::{Card, Chips, PlayerCards, TableId},
 };
 
@@ -30,6 +30,39 @@ pub enum Message {
     JoinTable,
     /// Leave a table.
     LeaveTable,
+    /// Play again with the players of the last game.
+    RequestRematch,
+    /// Watch a game in progress without taking a seat.
+    SpectateTable,
+    /// A chat message between spectators, it is never sent to seated players.
+    ///
+    /// The server replaces the player id with the id of the spectator who sent
+    /// the message before relaying it.
+    SpectatorChat {
+        /// The spectator who sent the message.
+        player_id: PeerId,
+        /// The message text.
+        text: String,
+    },
+    /// The made hand of each player in the hand during an all in runout, it is
+    /// only sent to spectators and never to seated players.
+    SpectatorHands {
+        /// The players ids with the rank of their made hand.
+        hands: Vec<(PeerId, String)>,
+    },
+    /// Pause a table at the end of the current hand, only the server admins can
+    /// pause a table and they don't need to be seated at it.
+    PauseTable {
+        /// The table to pause.
+        table_id: TableId,
+    },
+    /// Resume a paused table.
+    ResumeTable {
+        /// The table to resume.
+        table_id: TableId,
+    },
+    /// The table has been paused, a new hand starts when the table is resumed.
+    TablePaused,
     /// Table joined confirmation.
     TableJoined {
         /// The table the player joined.
@@ -45,6 +78,20 @@ pub enum Message {
     NotEnoughChips,
     /// The player has already joined a table.
     PlayerAlreadyJoined,
+    /// Another player at the table has the same nickname.
+    NicknameTaken,
+    /// The player has joined the maximum number of tables allowed.
+    TooManyTables {
+        /// The maximum number of tables a player can join.
+        max_tables: u16,
+    },
+    /// The player left a table too recently to join another one.
+    JoinCooldown {
+        /// The seconds left before the player can join a table.
+        seconds: u16,
+    },
+    /// The server closes the connection after the client has been idle for too long.
+    IdleTimeout,
     /// A player joined the table.
     PlayerJoined {
         /// The player id.
@@ -62,40 +109,84 @@ pub enum Message {
     /// Tell players the game is starting and update the seats order.
     StartGame(Vec<PeerId>),
     /// Tell players to prepare for a new hand.
-    StartHand,
+    StartHand {
+        /// The number of this hand in the game starting from 1.
+        hand_number: u64,
+    },
     /// Tell players the hand has completed and who won.
     EndHand {
         /// List of payoffs for the hand.
         payoffs: Vec<HandPayoff>,
         /// The board cards.
         board: Vec<Card>,
+        /// The cards of the boards after the first in multiple boards games.
+        extra_boards: Vec<Vec<Card>>,
         /// Players cards.
         cards: Vec<(PeerId, PlayerCards)>,
+        /// The rake taken from the pots.
+        rake: Chips,
+        /// Hash of the hand inputs and results for auditing.
+        hash: HandHash,
+    },
+    /// Ask to see the board cards that would have been dealt after a hand that
+    /// ended before the river.
+    RabbitHunt,
+    /// The full board of a hand that ended before the river, the cards dealt
+    /// after the hand ended are only shown and don't change the payoffs.
+    RabbitHuntBoard {
+        /// The board cards.
+        board: Vec<Card>,
+    },
+    /// Tell players the game has ended with the final standings.
+    GameOver {
+        /// The players in finishing order, the winner first.
+        standings: Vec<Standing>,
     },
     /// Deal cards to a player.
     DealCards(Card, Card),
     /// A player left the table.
     PlayerLeft(PeerId),
+    /// A player connection was lost and the player seat is held for a while.
+    PlayerDisconnected {
+        /// The player id.
+        player_id: PeerId,
+        /// The seconds left for the player to reconnect.
+        seconds: u16,
+    },
+    /// A disconnected player has reconnected.
+    PlayerReconnected(PeerId),
     /// A game state update.
     GameUpdate {
+        /// The table sequence number of this update.
+        ///
+        /// Clients that see a gap in the sequence send a [Message::ResyncRequest].
+        seq: u64,
         /// The players update.
         players: Vec<PlayerUpdate>,
         /// The board cards.
         board: Vec<Card>,
+        /// The cards of the boards after the first in multiple boards games.
+        extra_boards: Vec<Vec<Card>>,
         /// The pot.
         pot: Chips,
+        /// The chips in each pot, the main pot first followed by the side pots.
+        pots: Vec<Chips>,
+        /// The current blinds level.
+        blinds: BlindLevel,
     },
     /// Request action from a player.
     ActionRequest {
         /// The player that should respond with an action.
         player_id: PeerId,
-        /// The minimum raise.
-        min_raise: Chips,
         /// The current big blind.
         big_blind: Chips,
-        /// The list of legal actions.
-        actions: Vec<PlayerAction>,
+        /// The list of legal actions with their amounts.
+        actions: Vec<ActionOption>,
     },
+    /// Request a full game update after missing some updates.
+    ResyncRequest,
+    /// Muck losing hands at showdown instead of showing them.
+    SetAutoMuck(bool),
     /// Player action response.
     ActionResponse {
         /// The action from the player.
@@ -118,12 +209,54 @@ pub struct PlayerUpdate {
     pub action: PlayerAction,
     /// The player action timer.
     pub action_timer: Option<u16>,
+    /// The seconds left in the player time bank once the action timer expires.
+    pub time_bank: Option<u16>,
     /// The player cards.
     pub cards: PlayerCards,
     /// The player has the button.
     pub has_button: bool,
     /// The player is active in the hand.
     pub is_active: bool,
+    /// The player chance of winning the hand when players are all in.
+    pub equity: Option<f32>,
+}
+
+/// The blinds level for the current hand.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BlindLevel {
+    /// The blinds level starting from 1.
+    pub level: u8,
+    /// The small blind.
+    pub small_blind: Chips,
+    /// The big blind.
+    pub big_blind: Chips,
+    /// Number of hands before the blinds increase, `None` at the last level.
+    pub hands_to_next: Option<u16>,
+}
+
+/// A legal action with the range of amounts allowed for the action.
+///
+/// Amounts are the player total bet after the action, for fold and check
+/// actions they are zero.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct ActionOption {
+    /// The action.
+    pub action: PlayerAction,
+    /// The minimum amount for this action.
+    pub min: Chips,
+    /// The maximum amount for this action.
+    pub max: Chips,
+}
+
+impl ActionOption {
+    /// Creates an option for an action with a fixed amount.
+    pub fn fixed(action: PlayerAction, amount: Chips) -> Self {
+        Self {
+            action,
+            min: amount,
+            max: amount,
+        }
+    }
 }
 
 /// A Player action.
@@ -145,6 +278,10 @@ pub enum PlayerAction {
     Raise,
     /// Player folds.
     Fold,
+    /// Player pays the straddle.
+    Straddle,
+    /// Player pays the ante.
+    Ante,
 }
 
 impl PlayerAction {
@@ -158,6 +295,8 @@ impl PlayerAction {
             PlayerAction::Bet => "BET",
             PlayerAction::Raise => "RAISE",
             PlayerAction::Fold => "FOLD",
+            PlayerAction::Straddle => "STRADDLE",
+            PlayerAction::Ante => "ANTE",
             PlayerAction::None => "",
         }
     }
@@ -174,6 +313,21 @@ pub struct HandPayoff {
     pub cards: Vec<Card>,
     /// Cards rank description.
     pub rank: String,
+    /// Number of kickers at the end of the winning cards.
+    pub kickers: u8,
+}
+
+/// A player final position in a game.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Standing {
+    /// The player id.
+    pub player_id: PeerId,
+    /// The player nickname.
+    pub nickname: String,
+    /// The player chips at the end of the game.
+    pub chips: Chips,
+    /// The player finishing place starting from 1 for the winner.
+    pub place: usize,
 }
 
 /// A signed message.
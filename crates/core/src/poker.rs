@@ -136,7 +136,7 @@ impl fmt::Display for Chips {
 }
 
 /// The player cards.
-#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PlayerCards {
     /// The player has no cards.
     #[default]
@@ -147,6 +147,33 @@ pub enum PlayerCards {
     Cards(Card, Card),
 }
 
+/// The street of a hand.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Street {
+    /// Betting before the flop.
+    Preflop,
+    /// Betting after the first three board cards.
+    Flop,
+    /// Betting after the fourth board card.
+    Turn,
+    /// Betting after the fifth board card.
+    River,
+    /// The hand has ended and the results are shown.
+    Showdown,
+}
+
+impl Street {
+    /// Returns the betting street for a board with the given number of cards.
+    pub fn from_board_len(len: usize) -> Self {
+        match len {
+            0..=2 => Street::Preflop,
+            3 => Street::Flop,
+            4 => Street::Turn,
+            _ => Street::River,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -8,7 +8,7 @@ use eframe::egui::{self, pos2, vec2};
 use std::{sync::mpsc, thread};
 
 use freezeout_cards::{egui::Textures, *};
-use freezeout_eval::eval::*;
+use freezeout_eval::equity;
 
 struct Sim {
     pair: Vec<Card>,
@@ -19,66 +19,7 @@ struct Sim {
 impl Sim {
     fn run(&self) -> f64 {
         const SAMPLES: usize = 10_000;
-        const BOARD_SIZE: usize = 5;
-        const BOARD_IDX: usize = 2;
-
-        assert!(self.num_players > 0);
-        assert!(self.pair.len() == 2);
-        assert!(self.board.len() <= 5);
-
-        // Remove cards from the deck so that we don't sample them.
-        let mut deck = Deck::default();
-
-        // Removes pair cards from the deck.
-        for c in &self.pair {
-            deck.remove(*c);
-        }
-
-        // Removes board cards from the deck.
-        for c in &self.board {
-            deck.remove(*c);
-        }
-
-        let sample_size = 2 * self.num_players + BOARD_SIZE;
-        let mut wins = 0;
-        let mut games = 0;
-
-        deck.sample(SAMPLES, sample_size, |sample| {
-            // The sample contains the two cards for each player and the board cards,
-            // copy the board cards to the end of the evaluation array.
-            let mut hand = [Card::default(); BOARD_SIZE + 2];
-            let board_start = self.num_players * BOARD_IDX;
-            hand[2..].copy_from_slice(&sample[board_start..]);
-
-            for (i, c) in self.board.iter().enumerate() {
-                hand[i + BOARD_IDX] = *c;
-            }
-
-            // Evaluate hero hand.
-            hand[0] = self.pair[0];
-            hand[1] = self.pair[1];
-            let hvalue = HandValue::eval(&hand);
-
-            // Compare against other players hand.
-            let mut has_lost = false;
-            for player in 0..self.num_players {
-                hand[0] = sample[player * 2];
-                hand[1] = sample[player * 2 + 1];
-                let ovalue = HandValue::eval(&hand);
-                if ovalue > hvalue {
-                    has_lost = true;
-                    break;
-                }
-            }
-
-            if !has_lost {
-                wins += 1;
-            }
-
-            games += 1;
-        });
-
-        wins as f64 / games as f64
+        equity::simulate(&self.pair, self.num_players, &self.board, SAMPLES).equity
     }
 }
 
@@ -158,7 +99,7 @@ impl App {
 
         for (c, v) in self.deck.iter_mut().filter(|(c, _)| c.suit() == suit) {
             if *v {
-                let tx = self.textures.card(*c);
+                let tx = self.textures.card_sized(ui.ctx(), *c, App::CARD_SIZE);
                 let img = egui::Image::new(&tx)
                     .max_size(App::CARD_SIZE)
                     .corner_radius(2.0);
@@ -352,7 +293,7 @@ impl eframe::App for App {
             self.win_prob = Some(win_prob);
         }
 
-        egui::CentralPanel::default().show(&ctx, |ui| {
+        egui::CentralPanel::default().show(ctx, |ui| {
             let (rect, _) = ui.allocate_exact_size(Self::FRAME_SIZE, egui::Sense::hover());
 
             let start_x = (rect.width() - App::DECK_ROW_LX) / 2.0;
@@ -13,14 +13,16 @@ fn main() {
     let mut agg = [0usize; 9];
 
     // Evaluate all 133M hands.
-    Deck::default().for_each(7, |hand| {
-        let rank = HandValue::eval(&hand).rank();
+    let deck = Deck::default();
+    let expected = deck.combinations(7);
+    deck.for_each(7, |hand| {
+        let rank = HandValue::eval(hand).rank();
         agg[rank as usize] += 1;
     });
 
     let elapsed = now.elapsed().as_secs_f64();
     let total = agg.iter().sum::<usize>();
-    println!("Total hands      {total}");
+    println!("Total hands      {total} of {expected}");
     println!("Elapsed:         {:.3}s", elapsed);
     println!("Hands/sec:       {:.0}\n", total as f64 / elapsed);
 
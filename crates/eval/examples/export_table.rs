@@ -0,0 +1,25 @@
+// Copyright (C) 2025 Vince Vasta
+// SPDX-License-Identifier: Apache-2.0
+//
+// Writes the 7 cards lookup table binary, the default path is the table
+// embedded in the crate:
+//
+// ```bash
+// $ cargo r --example export_table -- crates/eval/src/eval/eval7.bin
+// ```
+use std::path::PathBuf;
+
+use freezeout_eval::eval::EvalTable;
+
+fn main() -> std::io::Result<()> {
+    let path = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("crates/eval/src/eval/eval7.bin"));
+
+    let bytes = EvalTable::export();
+    std::fs::write(&path, &bytes)?;
+    println!("Written {} bytes to {}", bytes.len(), path.display());
+
+    Ok(())
+}
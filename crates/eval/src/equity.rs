@@ -0,0 +1,509 @@
+// Copyright (C) 2025 Vince Vasta
+// SPDX-License-Identifier: Apache-2.0
+
+//! Equity calculator.
+//!
+//! Estimates how often a hero hand wins against a number of villains by
+//! sampling the villains hole cards and the cards to come on the board, or
+//! computes it exactly when there are only a few unknown cards:
+//!
+//! ```
+//! # use freezeout_eval::*;
+//! let hero = Deck::parse_hand("AsAh").unwrap();
+//! let equity = equity::simulate(&hero, 1, &[], 1_000);
+//! assert!(equity.equity > 0.7);
+//! ```
+use rand::prelude::*;
+#[cfg(feature = "parallel")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use freezeout_cards::{Card, Deck};
+
+use crate::eval::HandValue;
+
+/// The outcome of an equity simulation.
+///
+/// The win, tie, and loss fractions add up to one, the equity counts a tie as
+/// a fraction of a win split among the tied players.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Equity {
+    /// Fraction of the samples the hero wins.
+    pub win: f64,
+    /// Fraction of the samples the hero ties with one or more villains.
+    pub tie: f64,
+    /// Fraction of the samples the hero loses.
+    pub loss: f64,
+    /// The hero share of the pot between 0 and 1.
+    pub equity: f64,
+}
+
+/// Maximum number of unknown cards for which [simulate] enumerates all the
+/// villain hands and runouts instead of sampling them.
+const MAX_ENUM_UNKNOWN: usize = 3;
+
+/// Estimates the equity of a hero hand against random villains hands.
+///
+/// The hero and villains hole cards are combined with the given board and with
+/// `samples` random runouts of the cards to come. When there are few unknown
+/// cards, as with one villain on the turn or river, the equity is computed
+/// exactly enumerating all the villain hands and runouts.
+///
+/// Panics if the hero doesn't have two cards, if there are no villains, or if
+/// the board has more than five cards.
+pub fn simulate(hero: &[Card], villains: usize, board: &[Card], samples: usize) -> Equity {
+    check_args(hero, villains, board);
+
+    let mut deck = Deck::default();
+    deck.remove_all(hero);
+    deck.remove_all(board);
+
+    let mut tally = Tally::default();
+    let unknown = villains * 2 + 5 - board.len();
+    if unknown <= MAX_ENUM_UNKNOWN {
+        // Few unknown cards means a single villain, each villain hand from the
+        // unknown cards is matched with the remaining cards as the runout.
+        deck.for_each(unknown, |cards| {
+            for i in 0..cards.len() {
+                for j in i + 1..cards.len() {
+                    let hole = [cards[i], cards[j]];
+                    let runout = cards
+                        .iter()
+                        .enumerate()
+                        .filter(|(k, _)| *k != i && *k != j)
+                        .map(|(_, c)| *c)
+                        .collect::<Vec<_>>();
+                    tally.add_showdown(hero, [hole.as_slice()], board, &runout);
+                }
+            }
+        });
+    } else {
+        deck.sample(samples, unknown, |sample| {
+            // The sample has the villains hole cards followed by the cards to come.
+            let (holes, runout) = sample.split_at(villains * 2);
+            tally.add_showdown(hero, holes.chunks_exact(2), board, runout);
+        });
+    }
+
+    tally.equity()
+}
+
+/// Estimates the equity of a hero hand against random villains hands sampling
+/// the runouts from `tasks` parallel tasks.
+///
+/// The samples are split evenly among the tasks, so the total number of samples
+/// is rounded up to a multiple of the number of tasks. Spots with few unknown
+/// cards are enumerated exactly as in [simulate].
+///
+/// Panics if the hero doesn't have two cards, if there are no villains, if the
+/// board has more than five cards, or if there are no tasks.
+#[cfg(feature = "parallel")]
+pub fn simulate_parallel(
+    hero: &[Card],
+    villains: usize,
+    board: &[Card],
+    samples: usize,
+    tasks: usize,
+) -> Equity {
+    check_args(hero, villains, board);
+    assert!(tasks > 0, "There must be at least one task");
+
+    let unknown = villains * 2 + 5 - board.len();
+    if unknown <= MAX_ENUM_UNKNOWN || samples == 0 {
+        return simulate(hero, villains, board, samples);
+    }
+
+    let mut deck = Deck::default();
+    deck.remove_all(hero);
+    deck.remove_all(board);
+
+    let tally = AtomicTally::new(villains);
+    deck.par_sample(tasks, samples.div_ceil(tasks), unknown, |_, sample| {
+        let (holes, runout) = sample.split_at(villains * 2);
+        tally.add(showdown(hero, holes.chunks_exact(2), board, runout));
+    });
+
+    tally.tally().equity()
+}
+
+/// Estimates the equity of a hero hand against villains holding hands from the
+/// given ranges, one range for each villain.
+///
+/// For each sample a villain gets a random hand from its range that doesn't use
+/// cards already dealt, samples where a villain has no such hand are skipped.
+///
+/// Panics if the hero doesn't have two cards, if there are no ranges, or if the
+/// board has more than five cards.
+pub fn simulate_ranges(
+    hero: &[Card],
+    ranges: &[Vec<(Card, Card)>],
+    board: &[Card],
+    samples: usize,
+) -> Equity {
+    check_args(hero, ranges.len(), board);
+
+    let mut rng = SmallRng::from_os_rng();
+    let to_come = 5 - board.len();
+    let mut tally = Tally::default();
+
+    let mut dealt = Vec::with_capacity(hero.len() + board.len() + ranges.len() * 2);
+    let mut holes = Vec::with_capacity(ranges.len());
+
+    'samples: for _ in 0..samples {
+        dealt.clear();
+        dealt.extend_from_slice(hero);
+        dealt.extend_from_slice(board);
+
+        holes.clear();
+        for range in ranges {
+            let hole = range
+                .iter()
+                .filter(|(c1, c2)| !dealt.contains(c1) && !dealt.contains(c2))
+                .choose(&mut rng);
+            let Some(&(c1, c2)) = hole else {
+                continue 'samples;
+            };
+
+            dealt.extend([c1, c2]);
+            holes.push([c1, c2]);
+        }
+
+        let runout = Deck::default()
+            .iter()
+            .filter(|c| !dealt.contains(c))
+            .choose_multiple(&mut rng, to_come);
+        tally.add_showdown(hero, holes.iter().map(|h| h.as_slice()), board, &runout);
+    }
+
+    tally.equity()
+}
+
+/// Computes the exact equity of a hero hand against the villains hands.
+///
+/// All the runouts of the cards to come are enumerated, so this is practical
+/// from the flop onwards while preflop it evaluates all the 1.7 million boards.
+///
+/// Panics if the hero doesn't have two cards, if there are no villains, or if
+/// the board has more than five cards.
+pub fn enumerate(hero: &[Card], villains: &[(Card, Card)], board: &[Card]) -> Equity {
+    check_args(hero, villains.len(), board);
+
+    let mut deck = Deck::default();
+    deck.remove_all(hero);
+    deck.remove_all(board);
+    for (c1, c2) in villains {
+        deck.remove(*c1);
+        deck.remove(*c2);
+    }
+
+    let holes = villains
+        .iter()
+        .map(|(c1, c2)| [*c1, *c2])
+        .collect::<Vec<_>>();
+    let mut tally = Tally::default();
+    let mut add_runout = |runout: &[Card]| {
+        tally.add_showdown(hero, holes.iter().map(|h| h.as_slice()), board, runout);
+    };
+
+    match 5 - board.len() {
+        0 => add_runout(&[]),
+        1 => deck.iter().for_each(|c| add_runout(&[c])),
+        n => deck.for_each(n, add_runout),
+    }
+
+    tally.equity()
+}
+
+fn check_args(hero: &[Card], villains: usize, board: &[Card]) {
+    assert_eq!(hero.len(), 2, "The hero must have two cards");
+    assert!(villains > 0, "There must be at least one villain");
+    assert!(
+        board.len() <= 5,
+        "The board cannot have more than five cards"
+    );
+}
+
+/// The outcome of a showdown for the hero.
+#[derive(Debug, Clone, Copy)]
+enum Outcome {
+    /// The hero wins the pot.
+    Win,
+    /// The hero splits the pot with the given number of players including itself.
+    Split(usize),
+    /// The hero loses the pot.
+    Loss,
+}
+
+/// Returns the outcome of the hero against the villains hole cards on a board
+/// completed by the runout.
+fn showdown<'a>(
+    hero: &[Card],
+    holes: impl IntoIterator<Item = &'a [Card]>,
+    board: &[Card],
+    runout: &[Card],
+) -> Outcome {
+    let mut cards = [Card::default(); 7];
+    cards[2..2 + board.len()].copy_from_slice(board);
+    cards[2 + board.len()..].copy_from_slice(runout);
+
+    cards[..2].copy_from_slice(hero);
+    let hero_value = HandValue::eval(&cards);
+
+    let mut tied = 0;
+    for hole in holes {
+        cards[..2].copy_from_slice(hole);
+        let value = HandValue::eval(&cards);
+        if value > hero_value {
+            return Outcome::Loss;
+        } else if value == hero_value {
+            tied += 1;
+        }
+    }
+
+    if tied == 0 {
+        Outcome::Win
+    } else {
+        Outcome::Split(tied + 1)
+    }
+}
+
+/// Counts the outcomes of the hero hand against the villains.
+#[derive(Debug, Default)]
+struct Tally {
+    wins: usize,
+    ties: usize,
+    losses: usize,
+    share: f64,
+}
+
+impl Tally {
+    /// Adds the outcome of the hero against the villains hole cards on a board
+    /// completed by the runout.
+    fn add_showdown<'a>(
+        &mut self,
+        hero: &[Card],
+        holes: impl IntoIterator<Item = &'a [Card]>,
+        board: &[Card],
+        runout: &[Card],
+    ) {
+        self.add(showdown(hero, holes, board, runout));
+    }
+
+    /// Adds a showdown outcome.
+    fn add(&mut self, outcome: Outcome) {
+        match outcome {
+            Outcome::Win => {
+                self.wins += 1;
+                self.share += 1.0;
+            }
+            Outcome::Split(players) => {
+                self.ties += 1;
+                self.share += 1.0 / players as f64;
+            }
+            Outcome::Loss => self.losses += 1,
+        }
+    }
+
+    /// Returns the equity of the counted outcomes.
+    fn equity(&self) -> Equity {
+        let total = self.wins + self.ties + self.losses;
+        if total == 0 {
+            return Equity::default();
+        }
+
+        let total = total as f64;
+        Equity {
+            win: self.wins as f64 / total,
+            tie: self.ties as f64 / total,
+            loss: self.losses as f64 / total,
+            equity: self.share / total,
+        }
+    }
+}
+
+/// Counts the outcomes from parallel tasks.
+#[cfg(feature = "parallel")]
+#[derive(Debug)]
+struct AtomicTally {
+    wins: AtomicUsize,
+    losses: AtomicUsize,
+    /// Split pots counts indexed by the number of players sharing the pot.
+    splits: Vec<AtomicUsize>,
+}
+
+#[cfg(feature = "parallel")]
+impl AtomicTally {
+    /// Creates a tally for the hero and the given number of villains.
+    fn new(villains: usize) -> Self {
+        Self {
+            wins: AtomicUsize::new(0),
+            losses: AtomicUsize::new(0),
+            splits: (0..villains + 2).map(|_| AtomicUsize::new(0)).collect(),
+        }
+    }
+
+    /// Adds a showdown outcome.
+    fn add(&self, outcome: Outcome) {
+        let counter = match outcome {
+            Outcome::Win => &self.wins,
+            Outcome::Split(players) => &self.splits[players],
+            Outcome::Loss => &self.losses,
+        };
+
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Combines the tasks outcomes.
+    fn tally(&self) -> Tally {
+        let wins = self.wins.load(Ordering::Relaxed);
+        let mut tally = Tally {
+            wins,
+            ties: 0,
+            losses: self.losses.load(Ordering::Relaxed),
+            share: wins as f64,
+        };
+
+        for (players, splits) in self.splits.iter().enumerate().skip(2) {
+            let splits = splits.load(Ordering::Relaxed);
+            tally.ties += splits;
+            tally.share += splits as f64 / players as f64;
+        }
+
+        tally
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(s: &str) -> Vec<Card> {
+        Deck::parse_hand(s).unwrap()
+    }
+
+    fn hand(s: &str) -> (Card, Card) {
+        let cards = cards(s);
+        (cards[0], cards[1])
+    }
+
+    #[test]
+    fn simulate_random_villains() {
+        let aces = simulate(&cards("AsAh"), 1, &[], 5_000);
+        assert!((0.8..0.9).contains(&aces.equity), "{aces:?}");
+        assert!((aces.win + aces.tie + aces.loss - 1.0).abs() < 1e-9);
+
+        // More villains lower the hero equity.
+        let multiway = simulate(&cards("AsAh"), 4, &[], 5_000);
+        assert!(multiway.equity < aces.equity, "{multiway:?}");
+
+        // The nuts on the river never lose.
+        let nuts = simulate(&cards("AsKs"), 3, &cards("QsJsTs2h3d"), 1_000);
+        assert_eq!(nuts.win, 1.0);
+        assert_eq!(nuts.equity, 1.0);
+    }
+
+    #[test]
+    fn simulate_ties() {
+        // The board plays so every hand ties and splits the pot.
+        let board = cards("AsKsQsJsTs");
+        let heads_up = simulate(&cards("2h3h"), 1, &board, 500);
+        assert_eq!(heads_up.tie, 1.0);
+        assert_eq!(heads_up.win, 0.0);
+        assert_eq!(heads_up.equity, 0.5);
+
+        let three_way = simulate(&cards("2h3h"), 2, &board, 500);
+        assert!((three_way.equity - 1.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn simulate_with_ranges() {
+        // A single hand range on the river has a known outcome.
+        let board = cards("Kd7c2h9s4d");
+        let ranges = vec![vec![hand("KsKh")]];
+        let equity = simulate_ranges(&cards("AsAh"), &ranges, &board, 100);
+        assert_eq!(equity.loss, 1.0);
+        assert_eq!(equity.equity, 0.0);
+
+        // Aces against kings preflop.
+        let equity = simulate_ranges(&cards("AsAh"), &ranges, &[], 5_000);
+        assert!((0.77..0.87).contains(&equity.equity), "{equity:?}");
+
+        // Hands that use the hero cards are never dealt.
+        let ranges = vec![vec![hand("AsKs"), hand("QcQd")]];
+        let equity = simulate_ranges(&cards("AsAh"), &ranges, &board, 100);
+        assert_eq!(equity.win, 1.0);
+
+        // No samples if a range has only dead cards.
+        let ranges = vec![vec![hand("AsKs")]];
+        let equity = simulate_ranges(&cards("AsAh"), &ranges, &board, 100);
+        assert_eq!(equity, Equity::default());
+    }
+
+    #[test]
+    fn enumerate_runouts() {
+        // Count the river outcomes on the turn.
+        let hero = cards("AsQs");
+        let villain = hand("JhTh");
+        let board = cards("Kh9s2h5c");
+        let (mut wins, mut ties, mut losses) = (0, 0, 0);
+        let mut deck = Deck::default();
+        deck.remove_all(&hero);
+        deck.remove_all(&board);
+        deck.remove_all(&[villain.0, villain.1]);
+        for river in deck.iter() {
+            let mut hand = board.clone();
+            hand.push(river);
+            let villain_hand = [hand.as_slice(), &[villain.0, villain.1]].concat();
+            hand.extend_from_slice(&hero);
+            match HandValue::eval(&hand).cmp(&HandValue::eval(&villain_hand)) {
+                std::cmp::Ordering::Greater => wins += 1,
+                std::cmp::Ordering::Equal => ties += 1,
+                std::cmp::Ordering::Less => losses += 1,
+            }
+        }
+        assert_eq!(wins + ties + losses, 44);
+
+        let equity = enumerate(&hero, &[villain], &board);
+        assert_eq!(equity.win, wins as f64 / 44.0);
+        assert_eq!(equity.tie, ties as f64 / 44.0);
+        assert_eq!(equity.loss, losses as f64 / 44.0);
+
+        // On the river there is a single runout.
+        let equity = enumerate(&hero, &[villain], &cards("Kh9s2h5cTs"));
+        assert_eq!(equity.loss, 1.0);
+
+        // One villain on the river is enumerated exactly without samples.
+        let board = cards("Kd7c2h9s4d");
+        let mut deck = Deck::default();
+        deck.remove_all(&cards("AsAh"));
+        deck.remove_all(&board);
+        let mut total = 0.0;
+        let mut count = 0;
+        deck.for_each(2, |hole| {
+            total += enumerate(&cards("AsAh"), &[(hole[0], hole[1])], &board).equity;
+            count += 1;
+        });
+
+        let equity = simulate(&cards("AsAh"), 1, &board, 0);
+        assert!((equity.equity - total / count as f64).abs() < 1e-9);
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn simulate_in_parallel() {
+        let aces = simulate_parallel(&cards("AsAh"), 1, &[], 8_000, 4);
+        assert!((0.8..0.9).contains(&aces.equity), "{aces:?}");
+        assert!((aces.win + aces.tie + aces.loss - 1.0).abs() < 1e-9);
+
+        // Split pots are shared among the tied players.
+        let board = cards("AsKsQsJsTs");
+        let three_way = simulate_parallel(&cards("2h3h"), 2, &board, 1_000, 4);
+        assert_eq!(three_way.tie, 1.0);
+        assert!((three_way.equity - 1.0 / 3.0).abs() < 1e-9);
+
+        // The sampled equity is close to the single task one.
+        let board = cards("Kh9s2h");
+        let serial = simulate(&cards("AsQs"), 2, &board, 20_000);
+        let parallel = simulate_parallel(&cards("AsQs"), 2, &board, 20_000, 4);
+        assert!((serial.equity - parallel.equity).abs() < 0.03);
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright (C) 2025 Vince Vasta
+// SPDX-License-Identifier: Apache-2.0
+
+//! Readable hand values descriptions.
+//!
+//! A hand value displays as its rank, e.g. "FLUSH", the same text clients show
+//! for a winning hand, and with the alternate flag `{:#}` it adds the ranks
+//! that make the hand, e.g. "FLUSH, ACE HIGH".
+use std::{fmt, sync::LazyLock};
+
+use freezeout_cards::{Card, Rank, Suit};
+
+use super::{HandRank, HandValue, eval_five_cards, sort_for_display};
+
+/// The cards ranks in display order for each hand value.
+///
+/// The table is built from one hand for each of the distinct hand values.
+static VALUE_RANKS: LazyLock<Vec<[Rank; 5]>> = LazyLock::new(|| {
+    let mut table = vec![[Rank::Deuce; 5]; HandValue::MAX_STRENGTH as usize + 1];
+    let mut add = |mut hand: [Card; 5]| {
+        let value = eval_five_cards(&hand);
+        sort_for_display(value.rank(), &mut hand);
+        table[value.value() as usize] = hand.map(|c| c.rank());
+    };
+
+    let ranks = Rank::ranks().collect::<Vec<_>>();
+    let suits = Suit::suits().collect::<Vec<_>>();
+    for r1 in 0..ranks.len() {
+        for r2 in r1..ranks.len() {
+            for r3 in r2..ranks.len() {
+                for r4 in r3..ranks.len() {
+                    for r5 in r4..ranks.len() {
+                        if r1 == r5 {
+                            continue;
+                        }
+
+                        // Rotating suits gives cards with the same rank different
+                        // suits and never makes a flush.
+                        let hand = [r1, r2, r3, r4, r5];
+                        add(std::array::from_fn(|i| {
+                            Card::new(ranks[hand[i]], suits[i % suits.len()])
+                        }));
+
+                        let distinct = r1 < r2 && r2 < r3 && r3 < r4 && r4 < r5;
+                        if distinct {
+                            add(hand.map(|r| Card::new(ranks[r], suits[0])));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    table
+});
+
+impl HandValue {
+    /// Writes the ranks of the cards that make this hand.
+    fn fmt_ranks(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(ranks) = VALUE_RANKS.get(self.0 as usize).filter(|_| self.0 > 0) else {
+            return Ok(());
+        };
+
+        match self.rank() {
+            HandRank::HighCard | HandRank::Straight | HandRank::Flush | HandRank::StraightFlush => {
+                write!(f, "{} HIGH", name(ranks[0]))
+            }
+            HandRank::OnePair => write!(f, "PAIR OF {}", plural(ranks[0])),
+            HandRank::TwoPair => write!(f, "{} AND {}", plural(ranks[0]), plural(ranks[2])),
+            HandRank::ThreeOfAKind => write!(f, "THREE {}", plural(ranks[0])),
+            HandRank::FullHouse => write!(f, "{} FULL OF {}", plural(ranks[0]), plural(ranks[3])),
+            HandRank::FourOfAKind => write!(f, "FOUR {}", plural(ranks[0])),
+        }
+    }
+}
+
+impl fmt::Display for HandValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.rank())?;
+        if f.alternate() {
+            write!(f, ", ")?;
+            self.fmt_ranks(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for HandValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("HandValue")
+            .field(&self.0)
+            .field(&format_args!("{self:#}"))
+            .finish()
+    }
+}
+
+/// The name of a rank.
+fn name(rank: Rank) -> &'static str {
+    match rank {
+        Rank::Deuce => "TWO",
+        Rank::Trey => "THREE",
+        Rank::Four => "FOUR",
+        Rank::Five => "FIVE",
+        Rank::Six => "SIX",
+        Rank::Seven => "SEVEN",
+        Rank::Eight => "EIGHT",
+        Rank::Nine => "NINE",
+        Rank::Ten => "TEN",
+        Rank::Jack => "JACK",
+        Rank::Queen => "QUEEN",
+        Rank::King => "KING",
+        Rank::Ace => "ACE",
+    }
+}
+
+/// The plural name of a rank.
+fn plural(rank: Rank) -> String {
+    match rank {
+        Rank::Six => "SIXES".to_string(),
+        rank => format!("{}S", name(rank)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use freezeout_cards::Deck;
+
+    use super::*;
+
+    fn eval(hand: &str) -> HandValue {
+        HandValue::eval(&Deck::parse_hand(hand).unwrap())
+    }
+
+    #[test]
+    fn display_hand_value() {
+        // The default display matches the hand rank text.
+        let value = eval("As9s7s4s2sKdKh");
+        assert_eq!(value.to_string(), value.rank().to_string());
+        assert_eq!(value.to_string(), "FLUSH");
+
+        let describe = |hand: &str| format!("{:#}", eval(hand));
+        assert_eq!(describe("As9s7s4s2sKdKh"), "FLUSH, ACE HIGH");
+        assert_eq!(describe("Ks9d7h4c2s"), "HIGH CARD, KING HIGH");
+        assert_eq!(describe("6s6d7h4c2s"), "ONE PAIR, PAIR OF SIXES");
+        assert_eq!(describe("As9d9hAc2s"), "TWO PAIRS, ACES AND NINES");
+        assert_eq!(describe("Js9dJhJc2s"), "THREE OF A KIND, THREE JACKS");
+        assert_eq!(describe("As2d3h4c5s"), "STRAIGHT, FIVE HIGH");
+        assert_eq!(describe("9sTdJhQcKs"), "STRAIGHT, KING HIGH");
+        assert_eq!(describe("2s2d2hKcKs"), "FULL HOUSE, TWOS FULL OF KINGS");
+        assert_eq!(describe("QsQdQhQc3s"), "FOUR OF A KIND, FOUR QUEENS");
+        assert_eq!(describe("TsJsQsKsAs"), "STRAIGHT FLUSH, ACE HIGH");
+
+        // Debug shows the value with the description.
+        assert_eq!(
+            format!("{:?}", eval("As9d9hAc2s")),
+            format!(
+                "HandValue({}, TWO PAIRS, ACES AND NINES)",
+                eval("As9d9hAc2s").value()
+            )
+        );
+    }
+
+    #[test]
+    fn value_ranks_table() {
+        // Every hand value has an entry.
+        let mut seen = vec![false; HandValue::MAX_STRENGTH as usize + 1];
+        Deck::default().for_each(5, |hand| {
+            let value = HandValue::eval(hand);
+            if !seen[value.value() as usize] {
+                seen[value.value() as usize] = true;
+                let mut hand = [hand[0], hand[1], hand[2], hand[3], hand[4]];
+                sort_for_display(value.rank(), &mut hand);
+                assert_eq!(VALUE_RANKS[value.value() as usize], hand.map(|c| c.rank()));
+            }
+        });
+        assert_eq!(seen.iter().filter(|s| **s).count(), 7462);
+    }
+}
@@ -6,7 +6,7 @@ use xxhash_rust::xxh3::xxh3_64_with_seed;
 use freezeout_cards::Card;
 
 #[rustfmt::skip]
-static HASH_ADJS: [u16; 16384] = [
+pub(super) static HASH_ADJS: [u16; 16384] = [
        54,     1,    15,    17,    18,    42,     1,     0,     3,    17,
         3,    14,    49,     0,     5,     1,    62,     6,     0,     1,
         6,     2,     2,     1,     1,     1,     1,     1,    14,    64,
@@ -1649,7 +1649,7 @@ static HASH_ADJS: [u16; 16384] = [
 ];
 
 #[rustfmt::skip]
-static HAND_VALUES: [(u16, [u8; 3]); 49205] = [
+pub(super) static HAND_VALUES: [(u16, [u8; 3]); 49205] = [
     (  71, [0x07, 0x77, 0x7c]), (1617, [0x03, 0xbc, 0xcc]), ( 155, [0x00, 0x00, 0x0c]),
     (4967, [0x05, 0x57, 0x9a]), (6360, [0x04, 0x79, 0xac]), ( 216, [0x08, 0x88, 0xbb]),
     (6841, [0x02, 0x37, 0x9b]), (2473, [0x05, 0xbb, 0xcc]), ( 131, [0x02, 0x22, 0x2c]),
@@ -18054,17 +18054,21 @@ static HAND_VALUES: [(u16, [u8; 3]); 49205] = [
     (4272, [0x05, 0x88, 0x9b]), (  24, [0x0a, 0xbb, 0xbb]),
 ];
 
-fn h_0(k: u64) -> usize {
+pub(super) fn h_0(k: u64) -> usize {
     xxh3_64_with_seed(&k.to_be_bytes(), 0xb487c11dfa1112fd) as usize % 16384
 }
 
-fn h_n(k: u64, adj: u16) -> usize {
+pub(super) fn h_n(k: u64, adj: u16) -> usize {
     xxh3_64_with_seed(&k.to_be_bytes(), adj as u64) as usize % 49205
 }
 
 pub fn hand_rank(hand: &[Card]) -> (u16, [u8; 3]) {
-    let hand_id = hand.iter().map(|c| (c.id() & 0xff) as u64).product::<u64>();
+    let hand_id = hand_id(hand);
     let bucket = h_0(hand_id);
     let adj = HASH_ADJS[bucket];
     HAND_VALUES[h_n(hand_id, adj)]
 }
+
+pub(super) fn hand_id(hand: &[Card]) -> u64 {
+    hand.iter().map(|c| (c.id() & 0xff) as u64).product::<u64>()
+}
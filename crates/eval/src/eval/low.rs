@@ -0,0 +1,131 @@
+// Copyright (C) 2025 Vince Vasta
+// SPDX-License-Identifier: Apache-2.0
+
+//! Deuce to seven lowball evaluation.
+//!
+//! In deuce to seven the worst high hand wins, straights and flushes count
+//! against the hand and aces are always high, so that A-5-4-3-2 is an ace high
+//! hand and not a straight. The low values are computed from the standard five
+//! cards evaluator inverting its order.
+use freezeout_cards::{Card, Rank};
+
+use super::{HandRank, HandValue, PERM6, PERM7, eval_five_cards};
+
+/// The value of a deuce to seven low hand from 5, 6, or 7 cards.
+///
+/// A larger value is a better low hand, the best low is 7-5-4-3-2 offsuit.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LowValue(u16);
+
+impl LowValue {
+    /// The hand rank of the low hand, a straight is never ace to five.
+    pub fn rank(&self) -> HandRank {
+        HandRank::from_eval(self.0 / 2)
+    }
+
+    /// The low hand value, a better low hand has a larger value.
+    pub fn value(&self) -> u16 {
+        self.0
+    }
+
+    /// Evaluates the low value of five cards.
+    fn from_five_cards(cards: &[Card]) -> Self {
+        let mut ranks = cards.iter().map(|c| c.rank()).collect::<Vec<_>>();
+        ranks.sort();
+
+        // A-5-4-3-2 is not a straight, it is the ace high hand right below
+        // A-6-4-3-2 so its value is between A-6-4-3-2 and the next hand.
+        if ranks == [Rank::Deuce, Rank::Trey, Rank::Four, Rank::Five, Rank::Ace] {
+            let mut hand = [Card::default(); 5];
+            for (dst, src) in hand.iter_mut().zip(cards) {
+                *dst = if src.rank() == Rank::Five {
+                    Card::new(Rank::Six, src.suit())
+                } else {
+                    *src
+                };
+            }
+
+            Self(eval_five_cards(&hand).value() * 2 + 1)
+        } else {
+            Self(eval_five_cards(cards).value() * 2)
+        }
+    }
+}
+
+impl HandValue {
+    /// Evaluates the best deuce to seven low hand for 5, 6 or 7 cards.
+    pub fn eval_low_2to7(cards: &[Card]) -> LowValue {
+        let perms: &[[usize; 5]] = match cards.len() {
+            7 => &PERM7,
+            6 => &PERM6,
+            5 => &[[0, 1, 2, 3, 4]],
+            n => panic!("Hands size not supported {n}"),
+        };
+
+        perms
+            .iter()
+            .map(|p| LowValue::from_five_cards(&p.map(|i| cards[i])))
+            .max()
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use freezeout_cards::Deck;
+
+    use super::*;
+
+    fn eval(hand: &str) -> LowValue {
+        HandValue::eval_low_2to7(&Deck::parse_hand(hand).unwrap())
+    }
+
+    #[test]
+    fn low_2to7() {
+        // The worst high hand is the best low.
+        let best = eval("7s5h4d3c2s");
+        assert_eq!(best.rank(), HandRank::HighCard);
+        assert!(best > eval("8s6h4d3c2s"));
+        assert!(best > eval("7s6h4d3c2s"));
+
+        // Flushes and straights count against the hand.
+        assert!(eval("8s6h4d3c2s") > eval("7s5s4s3s2s"));
+        assert_eq!(eval("7s5s4s3s2s").rank(), HandRank::Flush);
+        assert!(eval("KsQhJdTc8s") > eval("6s5h4d3c2s"));
+        assert_eq!(eval("6s5h4d3c2s").rank(), HandRank::Straight);
+
+        // Aces are high and A-5-4-3-2 is not a straight.
+        let ace_five = eval("As5h4d3c2s");
+        assert_eq!(ace_five.rank(), HandRank::HighCard);
+        assert!(ace_five > eval("As6h4d3c2s"));
+        assert!(ace_five < eval("KsQhJdTc8s"));
+        assert_eq!(eval("As5s4s3s2s").rank(), HandRank::Flush);
+        assert!(eval("As5s4s3s2s") > eval("As6s4s3s2s"));
+        assert!(eval("As5s4s3s2s") < eval("KsQsJsTs8s"));
+
+        // Pairs are worse than any high card.
+        assert!(eval("AsKhQdJc9s") > eval("2s2h3d4c5s"));
+
+        // The best five cards of seven cards.
+        assert_eq!(eval("7s5h4d3c2sKsKh"), best);
+        assert_eq!(eval("2s2h3d3c4s4h5d").rank(), HandRank::OnePair);
+    }
+
+    #[test]
+    fn low_2to7_order() {
+        // All hands have distinct values with the same order as high hands
+        // inverted apart from A-5-4-3-2.
+        let mut values = ahash::AHashSet::default();
+        Deck::default().for_each(5, |hand| {
+            let low = HandValue::eval_low_2to7(hand);
+            values.insert(low.value());
+            if low.value().is_multiple_of(2) {
+                assert_eq!(low.value() / 2, HandValue::eval(hand).value());
+            }
+        });
+
+        // The A-5-4-3-2 straight and straight flush become ace high hands so
+        // the number of distinct hands doesn't change.
+        assert_eq!(values.len(), 7462);
+    }
+}
@@ -16,13 +16,27 @@
 //! [kevlink]: http://suffe.cool/poker/evaluator.html
 //! [kevcode]: http://suffe.cool/poker/code/
 use std::cmp::Ordering;
+use thiserror::Error;
 
-use freezeout_cards::Card;
+use freezeout_cards::{Card, Deck, Rank, suit_counts};
 
+mod display;
 mod eval7;
+mod low;
+#[cfg(feature = "short-deck")]
+mod short_deck;
+mod table;
+
+pub use low::LowValue;
+#[cfg(feature = "short-deck")]
+pub use short_deck::ShortDeckValue;
+pub use table::{EvalTable, TableError};
 
 /// An hand rank.
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+///
+/// Ranks are ordered from the weakest to the strongest so that comparing the
+/// rank of two hands is consistent with comparing their values.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum HandRank {
     /// A high card.
     HighCard,
@@ -86,11 +100,41 @@ impl std::fmt::Display for HandRank {
     }
 }
 
+/// An error from hand evaluation of invalid cards.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvalError {
+    /// The hand contains the same card more than once.
+    #[error("duplicate card {0}")]
+    DuplicateCard(Card),
+    /// The hand doesn't have 5, 6 or 7 cards.
+    #[error("hand size not supported {0}")]
+    InvalidHandSize(usize),
+}
+
+/// The best five cards of a hand split into the cards that make its rank and
+/// the kickers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BestHand {
+    /// The hand rank.
+    pub rank: HandRank,
+    /// The cards that make the hand rank in display order, e.g. the two pairs in
+    /// a two pair hand or the highest card in a high card hand.
+    pub made: Vec<Card>,
+    /// The kickers in descending rank order.
+    pub kickers: Vec<Card>,
+}
+
 /// The value of hand from 5, 6, or 7 cards.
-#[derive(Debug, Clone, Copy)]
+///
+/// A hand value displays as its rank, the alternate format `{:#}` adds the
+/// ranks that make the hand, e.g. "FLUSH, ACE HIGH".
+#[derive(Clone, Copy)]
 pub struct HandValue(u16);
 
 impl HandValue {
+    /// The strength of the strongest hand.
+    pub const MAX_STRENGTH: u32 = 7462;
+
     /// Evaluates a hand and the best cards for 5, 6 or 7 cards.
     pub fn eval_with_best_hand(cards: &[Card]) -> (HandValue, [Card; 5]) {
         if cards.len() == 7 {
@@ -107,8 +151,49 @@ impl HandValue {
         }
     }
 
+    /// Evaluates a hand and the best cards for 5, 6 or 7 cards with the best cards
+    /// in display order, see [best_hand_display].
+    pub fn eval_with_display_hand(cards: &[Card]) -> (HandValue, [Card; 5]) {
+        let (value, mut hand) = Self::eval_with_best_hand(cards);
+        sort_for_display(value.rank(), &mut hand);
+        (value, hand)
+    }
+
+    /// Evaluates a hand for 5, 6 or 7 cards and splits the best cards into the
+    /// cards that make the hand rank and the kickers.
+    ///
+    /// The made cards followed by the kickers are in display order, see
+    /// [best_hand_display].
+    pub fn eval_best_hand_detailed(cards: &[Card]) -> (HandValue, BestHand) {
+        let (value, hand) = Self::eval_with_display_hand(cards);
+        let rank = value.rank();
+        let made = match rank {
+            HandRank::HighCard => 1,
+            HandRank::OnePair => 2,
+            HandRank::ThreeOfAKind => 3,
+            HandRank::TwoPair | HandRank::FourOfAKind => 4,
+            HandRank::Straight
+            | HandRank::Flush
+            | HandRank::FullHouse
+            | HandRank::StraightFlush => 5,
+        };
+
+        let best_hand = BestHand {
+            rank,
+            made: hand[..made].to_vec(),
+            kickers: hand[made..].to_vec(),
+        };
+
+        (value, best_hand)
+    }
+
     /// Evaluates a hand for 5, 6 or 7 cards.
     pub fn eval(cards: &[Card]) -> HandValue {
+        debug_assert!(
+            find_duplicate(cards).is_none(),
+            "Duplicate cards in hand {cards:?}"
+        );
+
         if cards.len() == 7 {
             let (v, _) = eval_seven_cards(cards, false);
             v
@@ -122,6 +207,56 @@ impl HandValue {
         }
     }
 
+    /// Evaluates an Omaha hand that must use exactly two hole cards and three
+    /// board cards.
+    pub fn eval_omaha(hole: &[Card; 4], board: &[Card; 5]) -> HandValue {
+        Self::eval_omaha_with_best_hand(hole, board).0
+    }
+
+    /// Evaluates an Omaha hand and its best cards, the best hand has the two
+    /// hole cards followed by the three board cards.
+    pub fn eval_omaha_with_best_hand(
+        hole: &[Card; 4],
+        board: &[Card; 5],
+    ) -> (HandValue, [Card; 5]) {
+        let mut hand = [hole[0], hole[1], board[0], board[1], board[2]];
+        let mut best_value = HandValue::default();
+        let mut best_hand = hand;
+
+        for h in OMAHA_HOLE {
+            hand[0] = hole[h[0]];
+            hand[1] = hole[h[1]];
+            for b in OMAHA_BOARD {
+                hand[2] = board[b[0]];
+                hand[3] = board[b[1]];
+                hand[4] = board[b[2]];
+                let value = eval_five_cards(&hand);
+                if value > best_value {
+                    best_hand = hand;
+                    best_value = value;
+                }
+            }
+        }
+
+        (best_value, best_hand)
+    }
+
+    /// Evaluates a hand for 5, 6 or 7 cards checking the cards are valid.
+    ///
+    /// Returns an error if the hand contains duplicate cards or if the hand doesn't
+    /// have 5, 6 or 7 cards.
+    pub fn try_eval(cards: &[Card]) -> Result<HandValue, EvalError> {
+        if !(5..=7).contains(&cards.len()) {
+            return Err(EvalError::InvalidHandSize(cards.len()));
+        }
+
+        if let Some(card) = find_duplicate(cards) {
+            return Err(EvalError::DuplicateCard(card));
+        }
+
+        Ok(Self::eval(cards))
+    }
+
     /// The hand rank.
     pub fn rank(&self) -> HandRank {
         HandRank::from_eval(self.0)
@@ -131,6 +266,17 @@ impl HandValue {
     pub fn value(&self) -> u16 {
         self.0
     }
+
+    /// The hand strength where a higher value is a stronger hand.
+    ///
+    /// Strength goes from 1 for the worst high card (7-5-4-3-2) to
+    /// [HandValue::MAX_STRENGTH] for a royal flush, there is one strength for
+    /// each of the 7462 distinct 5 cards hands so it can be used to index lookup
+    /// tables. The default value has strength 0. The range is fixed by the
+    /// number of distinct hands and doesn't change across versions.
+    pub fn strength(&self) -> u32 {
+        (Self::MAX_STRENGTH + 1).saturating_sub(self.0 as u32)
+    }
 }
 
 impl Default for HandValue {
@@ -161,23 +307,161 @@ impl PartialEq for HandValue {
 
 impl Eq for HandValue {}
 
-/// Evaluate a seven cards hand.
+/// Returns the first card that appears more than once in a hand.
+fn find_duplicate(cards: &[Card]) -> Option<Card> {
+    cards
+        .iter()
+        .enumerate()
+        .find(|(idx, c)| cards[idx + 1..].contains(c))
+        .map(|(_, c)| *c)
+}
+
+/// Returns the nut hand for a board and the hole cards that make it.
 ///
-/// For higher throughput pass false to compute_best if only the hand value is needed.
-fn eval_seven_cards(cards: &[Card], compute_best: bool) -> (HandValue, Option<[Card; 5]>) {
-    let mut suit_counts = [0; 4];
-    for c in cards {
-        match c.suit_bits() {
-            0x8 => suit_counts[0] += 1,
-            0x4 => suit_counts[1] += 1,
-            0x2 => suit_counts[2] += 1,
-            0x1 => suit_counts[3] += 1,
-            _ => (),
+/// The nuts are found by enumerating all the hole cards that can be made with the
+/// cards that are not on the board, if more hole cards make the nuts the first
+/// one found is returned.
+///
+/// Panics if the board doesn't have 3, 4 or 5 cards.
+pub fn nuts(board: &[Card]) -> (HandValue, [Card; 2]) {
+    assert!((3..=5).contains(&board.len()), "3 <= board <= 5");
+
+    let mut deck = Deck::default();
+    for card in board {
+        deck.remove(*card);
+    }
+
+    let mut hand = board.to_vec();
+    hand.extend_from_slice(&[Card::default(); 2]);
+
+    let mut nuts = (HandValue::default(), [Card::default(); 2]);
+    deck.for_each(2, |hole| {
+        let n = hand.len();
+        hand[n - 2] = hole[0];
+        hand[n - 1] = hole[1];
+
+        let value = HandValue::eval(&hand);
+        if value > nuts.0 {
+            nuts = (value, [hole[0], hole[1]]);
+        }
+    });
+
+    nuts
+}
+
+/// The result of a heads up showdown from the point of view of the first hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HeadsUpResult {
+    /// The first hand wins.
+    HeroWins,
+    /// The hands have the same value and split the pot.
+    Split,
+    /// The second hand wins.
+    VillainWins,
+}
+
+impl From<Ordering> for HeadsUpResult {
+    fn from(ord: Ordering) -> Self {
+        match ord {
+            Ordering::Greater => HeadsUpResult::HeroWins,
+            Ordering::Equal => HeadsUpResult::Split,
+            Ordering::Less => HeadsUpResult::VillainWins,
         }
     }
+}
+
+/// Compares two 5, 6 or 7 cards hands, a stronger hand is greater.
+///
+/// The hands can have a different number of cards, i.e. a 7 cards hand can be
+/// compared with a 5 cards hand.
+pub fn compare(a: &[Card], b: &[Card]) -> Ordering {
+    HandValue::eval(a).cmp(&HandValue::eval(b))
+}
+
+/// Returns the result of a showdown between a hero and a villain hand.
+pub fn heads_up(hero: &[Card], villain: &[Card]) -> HeadsUpResult {
+    compare(hero, villain).into()
+}
+
+/// Returns the number of hands of each rank for all the `k` cards hands in a deck.
+///
+/// The counts are indexed by [HandRank], so that `counts[HandRank::Flush as usize]`
+/// is the number of flushes.
+///
+/// Panics if `k` is not 5, 6 or 7.
+pub fn rank_histogram(deck: &Deck, k: usize) -> [u64; 9] {
+    assert!((5..=7).contains(&k), "5 <= k <= 7");
 
+    let mut counts = [0; 9];
+    deck.for_each(k, |hand| {
+        counts[HandValue::eval(hand).rank() as usize] += 1;
+    });
+
+    counts
+}
+
+/// Returns the number of hands of each rank for all the `k` cards hands in a deck
+/// using `num_tasks` parallel tasks.
+///
+/// Each task counts its hands on its own counters that are added up at the end to
+/// avoid contention.
+///
+/// Panics if `k` is not 5, 6 or 7 or if `num_tasks` is zero.
+#[cfg(feature = "parallel")]
+pub fn rank_histogram_parallel(deck: &Deck, k: usize, num_tasks: usize) -> [u64; 9] {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    assert!((5..=7).contains(&k), "5 <= k <= 7");
+
+    let task_counts = (0..num_tasks)
+        .map(|_| std::array::from_fn::<_, 9, _>(|_| AtomicU64::new(0)))
+        .collect::<Vec<_>>();
+
+    deck.par_for_each(num_tasks, k, |task_id, hand| {
+        let rank = HandValue::eval(hand).rank();
+        task_counts[task_id][rank as usize].fetch_add(1, Ordering::Relaxed);
+    });
+
+    let mut counts = [0; 9];
+    for task in &task_counts {
+        for (count, task_count) in counts.iter_mut().zip(task) {
+            *count += task_count.load(Ordering::Relaxed);
+        }
+    }
+
+    counts
+}
+
+/// Returns the best five cards of a 5, 6 or 7 cards hand in display order.
+///
+/// Cards are grouped by rank with larger groups first and groups with the same
+/// size in descending rank order, so that a full house shows the three of a kind
+/// before the pair. Straights are ordered from the highest card with the ace last
+/// in a five high straight.
+pub fn best_hand_display(cards: &[Card]) -> Vec<Card> {
+    HandValue::eval_with_display_hand(cards).1.to_vec()
+}
+
+/// Sorts the best five cards of a hand in display order.
+fn sort_for_display(rank: HandRank, hand: &mut [Card; 5]) {
+    let count = |c: &Card| hand.iter().filter(|o| o.rank() == c.rank()).count();
+    let mut keyed = hand.map(|c| (count(&c), c));
+    keyed.sort_by(|(n1, c1), (n2, c2)| n2.cmp(n1).then(c2.rank().cmp(&c1.rank())));
+    *hand = keyed.map(|(_, c)| c);
+
+    // In a five high straight the ace plays low.
+    let is_straight = matches!(rank, HandRank::Straight | HandRank::StraightFlush);
+    if is_straight && hand[0].rank() == Rank::Ace && hand[1].rank() == Rank::Five {
+        hand.rotate_left(1);
+    }
+}
+
+/// Evaluate a seven cards hand.
+///
+/// For higher throughput pass false to compute_best if only the hand value is needed.
+fn eval_seven_cards(cards: &[Card], compute_best: bool) -> (HandValue, Option<[Card; 5]>) {
     // Use lookup table if suits are not significant (~97% of hands)
-    if suit_counts.iter().all(|c| *c < 5) {
+    if suit_counts(cards).iter().all(|c| *c < 5) {
         let (value, best_ranks) = eval7::hand_rank(cards);
         let best_hand = if compute_best {
             Some(extract_hand(cards, &best_ranks))
@@ -1447,10 +1731,26 @@ static PERM6: [[usize; 5]; 6] = [
     [1, 2, 3, 4, 5],
 ];
 
+static OMAHA_HOLE: [[usize; 2]; 6] = [[0, 1], [0, 2], [0, 3], [1, 2], [1, 3], [2, 3]];
+
+static OMAHA_BOARD: [[usize; 3]; 10] = [
+    [0, 1, 2],
+    [0, 1, 3],
+    [0, 1, 4],
+    [0, 2, 3],
+    [0, 2, 4],
+    [0, 3, 4],
+    [1, 2, 3],
+    [1, 2, 4],
+    [1, 3, 4],
+    [2, 3, 4],
+];
+
 #[cfg(test)]
 mod tests {
     use ahash::AHashMap;
     use freezeout_cards::{Card, Deck, Rank, Suit};
+    use rand::{SeedableRng, rngs::StdRng};
 
     use super::*;
 
@@ -1531,6 +1831,74 @@ mod tests {
         assert_eq!(h1val, h2val);
     }
 
+    #[test]
+    fn try_eval() {
+        let mut hand = vec![
+            Card::new(Rank::Ace, Suit::Hearts),
+            Card::new(Rank::King, Suit::Hearts),
+            Card::new(Rank::Queen, Suit::Hearts),
+            Card::new(Rank::Jack, Suit::Hearts),
+            Card::new(Rank::Ten, Suit::Hearts),
+            Card::new(Rank::Deuce, Suit::Clubs),
+        ];
+
+        let value = HandValue::try_eval(&hand).unwrap();
+        assert_eq!(value, HandValue::eval(&hand));
+        assert_eq!(value.rank(), HandRank::StraightFlush);
+
+        // A duplicate card is rejected.
+        hand.push(Card::new(Rank::King, Suit::Hearts));
+        assert_eq!(
+            HandValue::try_eval(&hand),
+            Err(EvalError::DuplicateCard(Card::new(
+                Rank::King,
+                Suit::Hearts
+            )))
+        );
+
+        // Too few and too many cards are rejected.
+        assert_eq!(
+            HandValue::try_eval(&hand[..4]),
+            Err(EvalError::InvalidHandSize(4))
+        );
+
+        hand[6] = Card::new(Rank::Trey, Suit::Clubs);
+        hand.push(Card::new(Rank::Four, Suit::Clubs));
+        assert_eq!(
+            HandValue::try_eval(&hand),
+            Err(EvalError::InvalidHandSize(8))
+        );
+    }
+
+    #[test]
+    fn nuts_hand() {
+        // On a monotone flop the nuts is the ace-high flush.
+        let board = [
+            Card::new(Rank::Four, Suit::Hearts),
+            Card::new(Rank::Nine, Suit::Hearts),
+            Card::new(Rank::Queen, Suit::Hearts),
+        ];
+        let (value, hole) = nuts(&board);
+        assert_eq!(value.rank(), HandRank::Flush);
+        assert!(hole.contains(&Card::new(Rank::Ace, Suit::Hearts)));
+        assert!(hole.iter().all(|c| c.suit() == Suit::Hearts));
+
+        // On a paired board the nuts is quads.
+        let board = [
+            Card::new(Rank::King, Suit::Spades),
+            Card::new(Rank::King, Suit::Diamonds),
+            Card::new(Rank::Seven, Suit::Clubs),
+            Card::new(Rank::Deuce, Suit::Hearts),
+        ];
+        let (value, hole) = nuts(&board);
+        assert_eq!(value.rank(), HandRank::FourOfAKind);
+        assert!(hole.iter().all(|c| c.rank() == Rank::King));
+
+        let mut hand = board.to_vec();
+        hand.extend_from_slice(&hole);
+        assert_eq!(HandValue::eval(&hand), value);
+    }
+
     /// In release mode this takes around 3.7 secs for 133M hands (~36M hands/s) to run it:
     ///
     /// ```bash
@@ -1562,4 +1930,246 @@ mod tests {
         let total = hands.values().sum::<u32>();
         assert_eq!(total, 133_784_560);
     }
+
+    #[test]
+    fn best_hand_display_order() {
+        let cards = |cs: &[(Rank, Suit)]| {
+            cs.iter()
+                .map(|(r, s)| Card::new(*r, *s))
+                .collect::<Vec<_>>()
+        };
+
+        // Full house, three of a kind first then the pair.
+        let hand = cards(&[
+            (Rank::Four, Suit::Hearts),
+            (Rank::King, Suit::Spades),
+            (Rank::Four, Suit::Clubs),
+            (Rank::Deuce, Suit::Diamonds),
+            (Rank::King, Suit::Hearts),
+            (Rank::Four, Suit::Diamonds),
+            (Rank::Nine, Suit::Clubs),
+        ]);
+        let ranks = best_hand_display(&hand)
+            .iter()
+            .map(|c| c.rank())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ranks,
+            vec![Rank::Four, Rank::Four, Rank::Four, Rank::King, Rank::King]
+        );
+
+        // Straight from the highest card.
+        let hand = cards(&[
+            (Rank::Seven, Suit::Hearts),
+            (Rank::Nine, Suit::Spades),
+            (Rank::Eight, Suit::Clubs),
+            (Rank::Deuce, Suit::Diamonds),
+            (Rank::Jack, Suit::Hearts),
+            (Rank::Ten, Suit::Diamonds),
+        ]);
+        let ranks = best_hand_display(&hand)
+            .iter()
+            .map(|c| c.rank())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ranks,
+            vec![Rank::Jack, Rank::Ten, Rank::Nine, Rank::Eight, Rank::Seven]
+        );
+
+        // Five high straight with the ace last.
+        let hand = cards(&[
+            (Rank::Ace, Suit::Hearts),
+            (Rank::Trey, Suit::Spades),
+            (Rank::Five, Suit::Clubs),
+            (Rank::Deuce, Suit::Diamonds),
+            (Rank::Four, Suit::Hearts),
+            (Rank::King, Suit::Diamonds),
+            (Rank::King, Suit::Clubs),
+        ]);
+        let ranks = best_hand_display(&hand)
+            .iter()
+            .map(|c| c.rank())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ranks,
+            vec![Rank::Five, Rank::Four, Rank::Trey, Rank::Deuce, Rank::Ace]
+        );
+    }
+
+    #[test]
+    fn rank_ord() {
+        assert!(HandRank::HighCard < HandRank::OnePair);
+        assert!(HandRank::Flush < HandRank::FullHouse);
+        assert!(HandRank::FourOfAKind < HandRank::StraightFlush);
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut last = HandValue::default();
+        Deck::default().sample_with_rng(100_000, 7, &mut rng, |hand| {
+            let value = HandValue::eval(hand);
+            if value > last {
+                assert!(value.rank() >= last.rank());
+            } else {
+                assert!(value.rank() <= last.rank());
+            }
+            last = value;
+        });
+    }
+
+    #[test]
+    fn hand_strength() {
+        let royal = Deck::parse_hand("AsKsQsJsTs").unwrap();
+        let royal = HandValue::eval(&royal);
+        assert_eq!(royal.strength(), HandValue::MAX_STRENGTH);
+
+        let worst = Deck::parse_hand("7s5h4d3c2s").unwrap();
+        assert_eq!(HandValue::eval(&worst).strength(), 1);
+        assert_eq!(HandValue::default().strength(), 0);
+
+        // Strength has the same order as hand values.
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut last = HandValue::default();
+        Deck::default().sample_with_rng(100_000, 7, &mut rng, |hand| {
+            let value = HandValue::eval(hand);
+            assert!((1..=HandValue::MAX_STRENGTH).contains(&value.strength()));
+            assert_eq!(value.cmp(&last), value.strength().cmp(&last.strength()));
+            last = value;
+        });
+    }
+
+    #[test]
+    fn eval_omaha() {
+        let omaha = |hole: &str, board: &str| {
+            let hole = Deck::parse_hand(hole).unwrap().try_into().unwrap();
+            let board = Deck::parse_hand(board).unwrap().try_into().unwrap();
+            HandValue::eval_omaha_with_best_hand(&hole, &board)
+        };
+
+        // A single spade in the hand doesn't make a flush.
+        let (value, hand) = omaha("AsKh7d8c", "2s3s4s9h9c");
+        assert_eq!(value.rank(), HandRank::OnePair);
+        assert_eq!(hand, *Deck::parse_hand("AsKh4s9h9c").unwrap());
+
+        // Four aces on the board play as three of a kind.
+        let (value, hand) = omaha("KsKhQdJc", "AsAhAdAc2s");
+        assert_eq!(value.rank(), HandRank::FullHouse);
+        assert_eq!(&hand[..2], &*Deck::parse_hand("KsKh").unwrap());
+
+        // The best hand always has two hole cards and three board cards.
+        let mut rng = StdRng::seed_from_u64(13);
+        Deck::default().sample_with_rng(10_000, 9, &mut rng, |cards| {
+            let hole = cards[..4].try_into().unwrap();
+            let board = cards[4..].try_into().unwrap();
+            let (value, hand) = HandValue::eval_omaha_with_best_hand(&hole, &board);
+            assert_eq!(value, HandValue::eval(&hand));
+            assert_eq!(value, HandValue::eval_omaha(&hole, &board));
+            assert!(hand[..2].iter().all(|c| hole.contains(c)));
+            assert!(hand[2..].iter().all(|c| board.contains(c)));
+        });
+    }
+
+    #[test]
+    fn best_hand_detailed() {
+        let detailed = |hand: &str| {
+            let cards = Deck::parse_hand(hand).unwrap();
+            let (value, best) = HandValue::eval_best_hand_detailed(&cards);
+            assert_eq!(value, HandValue::eval(&cards));
+            assert_eq!(best.rank, value.rank());
+
+            let ranks = |cards: &[Card]| cards.iter().map(|c| c.rank()).collect::<Vec<_>>();
+            (ranks(&best.made), ranks(&best.kickers))
+        };
+
+        // Two pair has the two pairs and a kicker.
+        assert_eq!(
+            detailed("2sKdAhKc9dAs7h"),
+            (
+                vec![Rank::Ace, Rank::Ace, Rank::King, Rank::King],
+                vec![Rank::Nine]
+            )
+        );
+
+        // One pair and three kickers in descending order.
+        assert_eq!(
+            detailed("5s8dQh5c2dJs3h"),
+            (
+                vec![Rank::Five, Rank::Five],
+                vec![Rank::Queen, Rank::Jack, Rank::Eight]
+            )
+        );
+
+        // High card plays the highest card.
+        assert_eq!(
+            detailed("Ks9dQh5c2d"),
+            (
+                vec![Rank::King],
+                vec![Rank::Queen, Rank::Nine, Rank::Five, Rank::Deuce]
+            )
+        );
+
+        // Four of a kind and trips with their kickers.
+        assert_eq!(
+            detailed("7s7d7h7cAd2s"),
+            (vec![Rank::Seven; 4], vec![Rank::Ace])
+        );
+        assert_eq!(
+            detailed("7s7d7hKcAd2s"),
+            (vec![Rank::Seven; 3], vec![Rank::Ace, Rank::King])
+        );
+
+        // Five cards hands have no kickers.
+        let (made, kickers) = detailed("Ts9dJh8c7d2s");
+        assert_eq!(made.len(), 5);
+        assert!(kickers.is_empty());
+        let (made, kickers) = detailed("KsKdKh2c2d");
+        assert_eq!(made.len(), 5);
+        assert!(kickers.is_empty());
+    }
+
+    #[test]
+    fn rank_histogram_5cards() {
+        let counts = rank_histogram(&Deck::default(), 5);
+        assert_eq!(
+            counts,
+            [
+                1_302_540, 1_098_240, 123_552, 54_912, 10_200, 5_108, 3_744, 624, 40
+            ]
+        );
+        assert_eq!(counts.iter().sum::<u64>(), 2_598_960);
+
+        #[cfg(feature = "parallel")]
+        assert_eq!(rank_histogram_parallel(&Deck::default(), 5, 4), counts);
+    }
+
+    #[test]
+    fn compare_hands() {
+        let hand = |s: &str| Deck::parse_hand(s).unwrap();
+
+        // The flush beats the straight.
+        let hero = hand("AsKs7s4s2sQdJh");
+        let villain = hand("9c8d7h6s5cQdJh");
+        assert_eq!(compare(&hero, &villain), Ordering::Greater);
+        assert_eq!(heads_up(&hero, &villain), HeadsUpResult::HeroWins);
+        assert_eq!(heads_up(&villain, &hero), HeadsUpResult::VillainWins);
+
+        // Both players play the board.
+        let hero = hand("2c3dAsKsQsJsTs");
+        let villain = hand("4c5dAsKsQsJsTs");
+        assert_eq!(compare(&hero, &villain), Ordering::Equal);
+        assert_eq!(heads_up(&hero, &villain), HeadsUpResult::Split);
+
+        // Same pair different kickers.
+        let hero = hand("AcKd9s9h2c5d");
+        let villain = hand("AdQh9c9d2s5c");
+        assert_eq!(heads_up(&hero, &villain), HeadsUpResult::HeroWins);
+
+        // Hands with a different number of cards.
+        assert_eq!(
+            heads_up(&hand("AsAhKsKhQc"), &hand("AsAhKsKhQc3d4d")),
+            HeadsUpResult::Split
+        );
+        assert_eq!(
+            heads_up(&hand("AsAhKsKh2c"), &hand("AsAhKsKhQc3d")),
+            HeadsUpResult::VillainWins
+        );
+    }
 }
@@ -0,0 +1,174 @@
+// Copyright (C) 2025 Vince Vasta
+// SPDX-License-Identifier: Apache-2.0
+
+//! Short deck (6+) hold'em evaluation.
+//!
+//! Short deck is played without the deuces to fives, a flush beats a full house
+//! and A-6-7-8-9 is the lowest straight. The hand values are computed from the
+//! standard five cards evaluator moving the wheel to A-6-7-8-9 and swapping the
+//! flush and full house values ranges.
+use std::cmp::Ordering;
+
+use freezeout_cards::{Card, Rank};
+
+use super::{HandRank, HandValue, PERM6, PERM7, eval_five_cards};
+
+/// The standard values of the lowest straight and straight flush.
+const WHEEL_STRAIGHT: u16 = 1609;
+const WHEEL_STRAIGHT_FLUSH: u16 = 10;
+
+/// The standard values ranges of full houses and flushes.
+const FULL_HOUSES: (u16, u16) = (167, 322);
+const FLUSHES: (u16, u16) = (323, 1599);
+
+/// The value of a short deck hand from 5, 6, or 7 cards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ShortDeckValue(u16);
+
+impl ShortDeckValue {
+    /// The hand rank with flushes ranked above full houses.
+    pub fn rank(&self) -> HandRank {
+        // Flushes take the values from the best full house.
+        let flushes_end = FULL_HOUSES.0 + FLUSHES.1 - FLUSHES.0;
+        if (FULL_HOUSES.0..=flushes_end).contains(&self.0) {
+            HandRank::Flush
+        } else if (flushes_end + 1..=FLUSHES.1).contains(&self.0) {
+            HandRank::FullHouse
+        } else {
+            HandRank::from_eval(self.0)
+        }
+    }
+
+    /// The hand value, a stronger hand has a smaller value.
+    pub fn value(&self) -> u16 {
+        self.0
+    }
+
+    /// Maps a standard five cards value to its short deck value.
+    fn from_five_cards(cards: &[Card]) -> Self {
+        let value = eval_five_cards(cards).value();
+
+        // A-6-7-8-9 takes the place of the five high straight.
+        let mut ranks = cards.iter().map(|c| c.rank()).collect::<Vec<_>>();
+        ranks.sort();
+        let is_wheel = ranks == [Rank::Six, Rank::Seven, Rank::Eight, Rank::Nine, Rank::Ace];
+        let value = if is_wheel && cards.iter().all(|c| c.suit() == cards[0].suit()) {
+            WHEEL_STRAIGHT_FLUSH
+        } else if is_wheel {
+            WHEEL_STRAIGHT
+        } else {
+            value
+        };
+
+        // Swap the full houses and flushes ranges.
+        let full_houses = FULL_HOUSES.1 - FULL_HOUSES.0 + 1;
+        let flushes = FLUSHES.1 - FLUSHES.0 + 1;
+        let value = if (FULL_HOUSES.0..=FULL_HOUSES.1).contains(&value) {
+            value + flushes
+        } else if (FLUSHES.0..=FLUSHES.1).contains(&value) {
+            value - full_houses
+        } else {
+            value
+        };
+
+        Self(value)
+    }
+}
+
+impl Default for ShortDeckValue {
+    fn default() -> Self {
+        // The lowest hand value
+        Self(u16::MAX)
+    }
+}
+
+impl Ord for ShortDeckValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Comparison is inverted as a stronger hand has smaller value.
+        other.0.cmp(&self.0)
+    }
+}
+
+impl PartialOrd for ShortDeckValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl HandValue {
+    /// Evaluates a short deck hand for 5, 6 or 7 cards.
+    ///
+    /// Cards must be sixes or higher.
+    pub fn eval_short_deck(cards: &[Card]) -> ShortDeckValue {
+        debug_assert!(
+            cards.iter().all(|c| c.rank() >= Rank::Six),
+            "Short deck hands cannot have cards below six {cards:?}"
+        );
+
+        let perms: &[[usize; 5]] = match cards.len() {
+            7 => &PERM7,
+            6 => &PERM6,
+            5 => &[[0, 1, 2, 3, 4]],
+            n => panic!("Hands size not supported {n}"),
+        };
+
+        perms
+            .iter()
+            .map(|p| ShortDeckValue::from_five_cards(&p.map(|i| cards[i])))
+            .max()
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use freezeout_cards::Deck;
+
+    use super::*;
+
+    fn eval(hand: &str) -> ShortDeckValue {
+        HandValue::eval_short_deck(&Deck::parse_hand(hand).unwrap())
+    }
+
+    #[test]
+    fn short_deck_ranking() {
+        // A-6-7-8-9 is the lowest straight.
+        let wheel = eval("As6h7d8c9s");
+        assert_eq!(wheel.rank(), HandRank::Straight);
+        assert!(wheel > eval("AsAhAd8c9s"));
+        assert!(wheel < eval("6s7h8d9cTs"));
+        assert_eq!(eval("As6s7s8s9s").rank(), HandRank::StraightFlush);
+
+        // A flush beats a full house.
+        let flush = eval("6s7s8sTsQs");
+        let full_house = eval("AsAhAdKcKs");
+        assert_eq!(flush.rank(), HandRank::Flush);
+        assert_eq!(full_house.rank(), HandRank::FullHouse);
+        assert!(flush > full_house);
+        assert!(eval("AsKsQsJs9s") > eval("6s7s8sTsQs"));
+        assert!(eval("AsAhAdKcKs") > eval("6s6h6dJcJs"));
+        assert!(eval("6s6h6d6cJs") > eval("AsKsQsJs9s"));
+
+        // The best five cards of seven cards.
+        let hand = eval("As6h7d8c9sKsKh");
+        assert_eq!(hand.rank(), HandRank::Straight);
+        assert_eq!(eval("AsAhAdKcKs7s8s"), full_house);
+        assert_eq!(eval("AsAhKcKs9sQsJs").rank(), HandRank::Flush);
+    }
+
+    #[test]
+    fn short_deck_frequencies() {
+        let deck = Deck::from_cards(Deck::default().iter().filter(|c| c.rank() >= Rank::Six));
+        assert_eq!(deck.count(), 36);
+
+        let mut counts = [0u32; 9];
+        deck.for_each(5, |hand| {
+            counts[HandValue::eval_short_deck(hand).rank() as usize] += 1;
+        });
+
+        assert_eq!(
+            counts,
+            [122_400, 193_536, 36_288, 16_128, 6_120, 480, 1_728, 288, 24]
+        );
+    }
+}
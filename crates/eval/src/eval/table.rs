@@ -0,0 +1,241 @@
+// Copyright (C) 2025 Vince Vasta
+// SPDX-License-Identifier: Apache-2.0
+
+//! Binary export of the 7 cards lookup table.
+//!
+//! The table is serialized as a compact little endian binary with the following
+//! layout:
+//!
+//! ```text
+//! magic "FZE7" | version u8 | adjs len u32 | values len u32 |
+//! adjs [u16] | values [(u16, [u8; 3])] | xxh3 checksum u64
+//! ```
+//!
+//! The checksum covers all the preceding bytes, see the `export_table` example
+//! for writing the table to a file that can be embedded with `include_bytes!`.
+//!
+//! The table exported by this crate is embedded only with the `embedded-table`
+//! feature as it adds about 280KB to the binary.
+#[cfg(feature = "embedded-table")]
+use std::sync::LazyLock;
+use thiserror::Error;
+use xxhash_rust::xxh3::xxh3_64;
+
+use freezeout_cards::{Card, suit_counts};
+
+use super::{HandValue, eval7};
+
+/// The embedded binary table.
+#[cfg(feature = "embedded-table")]
+static EMBEDDED_TABLE: &[u8] = include_bytes!("eval7.bin");
+
+#[cfg(feature = "embedded-table")]
+static EMBEDDED: LazyLock<EvalTable> = LazyLock::new(|| {
+    EvalTable::from_bytes(EMBEDDED_TABLE).expect("Embedded table should be valid")
+});
+
+/// An error from loading a binary table.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableError {
+    /// The data doesn't start with the table magic.
+    #[error("invalid table magic")]
+    InvalidMagic,
+    /// The table was exported with an unsupported version.
+    #[error("unsupported table version {0}")]
+    UnsupportedVersion(u8),
+    /// The data length doesn't match the table sizes.
+    #[error("invalid table length {0}")]
+    InvalidLength(usize),
+    /// The data checksum doesn't match.
+    #[error("table checksum mismatch")]
+    ChecksumMismatch,
+}
+
+/// A 7 cards lookup table loaded from its binary export.
+#[derive(Debug, Clone)]
+pub struct EvalTable {
+    hash_adjs: Vec<u16>,
+    hand_values: Vec<(u16, [u8; 3])>,
+}
+
+impl EvalTable {
+    /// The magic at the start of a binary table.
+    pub const MAGIC: &[u8; 4] = b"FZE7";
+
+    /// The binary table version.
+    pub const VERSION: u8 = 1;
+
+    const HEADER_LEN: usize = 4 + 1 + 4 + 4;
+    const VALUE_LEN: usize = 5;
+    const CHECKSUM_LEN: usize = 8;
+
+    /// Exports the evaluator lookup table to its binary format.
+    pub fn export() -> Vec<u8> {
+        let adjs = &eval7::HASH_ADJS;
+        let values = &eval7::HAND_VALUES;
+
+        let mut bytes = Vec::with_capacity(
+            Self::HEADER_LEN + adjs.len() * 2 + values.len() * Self::VALUE_LEN + Self::CHECKSUM_LEN,
+        );
+
+        bytes.extend_from_slice(Self::MAGIC);
+        bytes.push(Self::VERSION);
+        bytes.extend_from_slice(&(adjs.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(values.len() as u32).to_le_bytes());
+
+        for adj in adjs {
+            bytes.extend_from_slice(&adj.to_le_bytes());
+        }
+
+        for (value, ranks) in values {
+            bytes.extend_from_slice(&value.to_le_bytes());
+            bytes.extend_from_slice(ranks);
+        }
+
+        let checksum = xxh3_64(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+        bytes
+    }
+
+    /// Loads a table from its binary format validating its content.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TableError> {
+        if bytes.len() < Self::HEADER_LEN + Self::CHECKSUM_LEN {
+            return Err(TableError::InvalidLength(bytes.len()));
+        }
+
+        if &bytes[..4] != Self::MAGIC {
+            return Err(TableError::InvalidMagic);
+        }
+
+        if bytes[4] != Self::VERSION {
+            return Err(TableError::UnsupportedVersion(bytes[4]));
+        }
+
+        // The hash functions are tied to the evaluator tables sizes.
+        let read_len = |pos: usize| {
+            u32::from_le_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]])
+                as usize
+        };
+        let (adjs_len, values_len) = (read_len(5), read_len(9));
+        let expected_len =
+            Self::HEADER_LEN + adjs_len * 2 + values_len * Self::VALUE_LEN + Self::CHECKSUM_LEN;
+        if adjs_len != eval7::HASH_ADJS.len()
+            || values_len != eval7::HAND_VALUES.len()
+            || bytes.len() != expected_len
+        {
+            return Err(TableError::InvalidLength(bytes.len()));
+        }
+
+        let (data, checksum) = bytes.split_at(bytes.len() - Self::CHECKSUM_LEN);
+        if xxh3_64(data).to_le_bytes() != checksum {
+            return Err(TableError::ChecksumMismatch);
+        }
+
+        let (adjs, values) = data[Self::HEADER_LEN..].split_at(adjs_len * 2);
+        let hash_adjs = adjs
+            .chunks_exact(2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        let hand_values = values
+            .chunks_exact(Self::VALUE_LEN)
+            .map(|b| (u16::from_le_bytes([b[0], b[1]]), [b[2], b[3], b[4]]))
+            .collect();
+
+        Ok(Self {
+            hash_adjs,
+            hand_values,
+        })
+    }
+
+    /// Returns the table embedded in this crate.
+    #[cfg(feature = "embedded-table")]
+    pub fn embedded() -> &'static EvalTable {
+        &EMBEDDED
+    }
+
+    /// Evaluates a 7 cards hand using this table.
+    ///
+    /// Hands with five or more cards of the same suit are not in the table and
+    /// are evaluated with [HandValue::eval].
+    ///
+    /// Panics if the hand doesn't have 7 cards.
+    pub fn eval(&self, cards: &[Card]) -> HandValue {
+        assert_eq!(cards.len(), 7, "Hands size not supported {}", cards.len());
+
+        if suit_counts(cards).iter().all(|c| *c < 5) {
+            let hand_id = eval7::hand_id(cards);
+            let adj = self.hash_adjs[eval7::h_0(hand_id)];
+            HandValue(self.hand_values[eval7::h_n(hand_id, adj)].0)
+        } else {
+            HandValue::eval(cards)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use freezeout_cards::Deck;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn exported_table() {
+        let table = EvalTable::from_bytes(&EvalTable::export()).unwrap();
+        let mut rng = StdRng::seed_from_u64(101);
+        Deck::default().sample_with_rng(100_000, 7, &mut rng, |hand| {
+            assert_eq!(table.eval(hand), HandValue::eval(hand), "{hand:?}");
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-table")]
+    fn embedded_table() {
+        // The embedded table is the export of the evaluator table.
+        assert_eq!(EMBEDDED_TABLE, EvalTable::export().as_slice());
+
+        let table = EvalTable::embedded();
+        let mut rng = StdRng::seed_from_u64(101);
+        Deck::default().sample_with_rng(100_000, 7, &mut rng, |hand| {
+            assert_eq!(table.eval(hand), HandValue::eval(hand), "{hand:?}");
+        });
+    }
+
+    #[test]
+    fn invalid_table() {
+        let bytes = EvalTable::export();
+        assert!(EvalTable::from_bytes(&bytes).is_ok());
+
+        assert_eq!(
+            EvalTable::from_bytes(&bytes[..10]).unwrap_err(),
+            TableError::InvalidLength(10)
+        );
+
+        let mut data = bytes.clone();
+        data[0] = b'X';
+        assert_eq!(
+            EvalTable::from_bytes(&data).unwrap_err(),
+            TableError::InvalidMagic
+        );
+
+        let mut data = bytes.clone();
+        data[4] = 2;
+        assert_eq!(
+            EvalTable::from_bytes(&data).unwrap_err(),
+            TableError::UnsupportedVersion(2)
+        );
+
+        let mut data = bytes.clone();
+        data.pop();
+        assert!(matches!(
+            EvalTable::from_bytes(&data).unwrap_err(),
+            TableError::InvalidLength(_)
+        ));
+
+        let mut data = bytes;
+        data[100] ^= 1;
+        assert_eq!(
+            EvalTable::from_bytes(&data).unwrap_err(),
+            TableError::ChecksumMismatch
+        );
+    }
+}
@@ -21,10 +21,25 @@
 //!
 //! ```
 //!
+//! The **`parallel`** feature adds `equity::simulate_parallel` and
+//! `rank_histogram_parallel`, and enables the `parallel` feature of the cards
+//! crate for parallel sampling and iteration of a [Deck], so that only this
+//! feature has to be enabled.
+//!
 //! [kevlink]: http://suffe.cool/poker/evaluator.html
 #![warn(clippy::all, rust_2018_idioms, missing_docs)]
+pub mod equity;
 pub mod eval;
-pub use eval::{HandRank, HandValue};
+pub use eval::{
+    BestHand, EvalError, EvalTable, HandRank, HandValue, HeadsUpResult, LowValue, TableError,
+    best_hand_display, compare, heads_up, nuts, rank_histogram,
+};
+
+#[cfg(feature = "parallel")]
+pub use eval::rank_histogram_parallel;
+
+#[cfg(feature = "short-deck")]
+pub use eval::ShortDeckValue;
 
 // Reexport cards types.
-pub use freezeout_cards::{Card, Deck, Rank, Suit};
+pub use freezeout_cards::{Card, Deck, Rank, Suit, rank_counts, suit_counts};
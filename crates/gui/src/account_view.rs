@@ -4,7 +4,11 @@
 //! Connection dialog view.
 use eframe::egui::*;
 
-use freezeout_core::{game_state::GameState, message::Message, poker::Chips};
+use freezeout_core::{
+    game_state::GameState,
+    message::{Message, Standing},
+    poker::Chips,
+};
 
 use crate::{App, ConnectView, ConnectionEvent, GameView, View};
 
@@ -20,6 +24,8 @@ pub struct AccountView {
     connection_closed: bool,
     table_joined: bool,
     message: String,
+    can_rematch: bool,
+    standings: Vec<Standing>,
 }
 
 impl AccountView {
@@ -34,8 +40,22 @@ impl AccountView {
             connection_closed: false,
             table_joined: false,
             message: String::default(),
+            can_rematch: false,
+            standings: Vec::default(),
         }
     }
+
+    /// Shows a button to request a rematch with the players of the last game.
+    pub fn with_rematch(mut self, can_rematch: bool) -> Self {
+        self.can_rematch = can_rematch;
+        self
+    }
+
+    /// Shows the final standings of the last game.
+    pub fn with_standings(mut self, standings: &[Standing]) -> Self {
+        self.standings = standings.to_vec();
+        self
+    }
 }
 
 impl View for AccountView {
@@ -64,6 +84,19 @@ impl View for AccountView {
                         Message::PlayerAlreadyJoined => {
                             self.message = "This player has already joined".to_string();
                         }
+                        Message::NicknameTaken => {
+                            self.message =
+                                "Nickname already in use, choose another one".to_string();
+                        }
+                        Message::TooManyTables { max_tables } => {
+                            self.message = format!("You can join at most {max_tables} tables");
+                        }
+                        Message::JoinCooldown { seconds } => {
+                            self.message = format!("Wait {seconds} seconds before joining a table");
+                        }
+                        Message::IdleTimeout => {
+                            self.message = "Disconnected after being idle for too long".to_string();
+                        }
                         _ => {}
                     }
 
@@ -99,6 +132,27 @@ impl View for AccountView {
 
                 ui.add_space(10.0);
 
+                if !self.standings.is_empty() {
+                    ui.group(|ui| {
+                        Grid::new("standings_grid")
+                            .num_columns(3)
+                            .spacing([40.0, 4.0])
+                            .show(ui, |ui| {
+                                for standing in &self.standings {
+                                    let place = ordinal(standing.place);
+                                    ui.label(RichText::new(place).font(TEXT_FONT));
+                                    ui.label(RichText::new(&standing.nickname).font(TEXT_FONT));
+                                    ui.label(
+                                        RichText::new(standing.chips.to_string()).font(TEXT_FONT),
+                                    );
+                                    ui.end_row();
+                                }
+                            });
+                    });
+
+                    ui.add_space(10.0);
+                }
+
                 ui.vertical_centered(|ui| {
                     if !self.message.is_empty() {
                         ui.label(
@@ -114,6 +168,17 @@ impl View for AccountView {
                     if ui.add_sized(vec2(180.0, 30.0), btn).clicked() {
                         app.send_message(Message::JoinTable);
                     };
+
+                    if self.can_rematch {
+                        ui.add_space(5.0);
+
+                        let btn = Button::new(RichText::new("Rematch").font(TEXT_FONT));
+                        if ui.add_sized(vec2(180.0, 30.0), btn).clicked() {
+                            app.send_message(Message::RequestRematch);
+                            self.message = "Waiting for the other players".to_string();
+                            self.can_rematch = false;
+                        };
+                    }
                 });
             });
     }
@@ -127,6 +192,8 @@ impl View for AccountView {
         if self.connection_closed {
             Some(Box::new(ConnectView::new(frame.storage(), app)))
         } else if self.table_joined {
+            app.start_session(frame.storage_mut());
+
             let empty_state = GameState::new(app.player_id().clone(), app.nickname().to_string());
             Some(Box::new(GameView::new(
                 ctx,
@@ -137,3 +204,16 @@ impl View for AccountView {
         }
     }
 }
+
+/// Formats a finishing place as "1st", "2nd", "3rd", ...
+fn ordinal(place: usize) -> String {
+    let suffix = match (place % 10, place % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+
+    format!("{place}{suffix}")
+}
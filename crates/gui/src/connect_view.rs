@@ -5,9 +5,9 @@
 use eframe::egui::*;
 use log::error;
 
-use freezeout_core::{crypto::SigningKey, message::Message, poker::Chips};
+use freezeout_core::{crypto::SigningKey, game_state::GameState, message::Message, poker::Chips};
 
-use crate::{AccountView, App, AppData, ConnectionEvent, View};
+use crate::{AccountView, App, AppData, ConnectionEvent, GameView, Session, View, gui::unix_time};
 
 const TEXT_FONT: FontId = FontId::new(16.0, FontFamily::Monospace);
 const LABEL_FONT: FontId = FontId::new(16.0, FontFamily::Monospace);
@@ -20,6 +20,9 @@ pub struct ConnectView {
     chips: Chips,
     error: String,
     server_joined: bool,
+    session: Option<Session>,
+    resume: Option<GameState>,
+    table_joined: bool,
 }
 
 impl Default for ConnectView {
@@ -32,6 +35,9 @@ impl Default for ConnectView {
             chips: Chips::default(),
             error: String::default(),
             server_joined: false,
+            session: None,
+            resume: None,
+            table_joined: false,
         }
     }
 }
@@ -45,10 +51,13 @@ impl ConnectView {
                 ConnectView {
                     passphrase: sk.phrase(),
                     player_id: sk.verifying_key().peer_id().digits(),
+                    session: d.resumable_session(unix_time()).cloned(),
                     nickname: d.nickname,
                     chips: Chips::default(),
                     error: String::new(),
                     server_joined: false,
+                    resume: None,
+                    table_joined: false,
                 }
             })
             .unwrap_or_default()
@@ -58,6 +67,44 @@ impl ConnectView {
         self.passphrase = sk.phrase();
         self.player_id = sk.verifying_key().peer_id().digits();
     }
+
+    /// Connects to the server, if `resume` is set the player joins a table as
+    /// soon as it has joined the server to reclaim a seat held by the server.
+    fn connect(&mut self, ctx: &Context, frame: &mut eframe::Frame, app: &mut App, resume: bool) {
+        self.error.clear();
+
+        if self.nickname.trim().is_empty() {
+            self.error = "Invalid nickname".to_string();
+            return;
+        }
+
+        let sk = if let Ok(sk) = SigningKey::from_phrase(&self.passphrase) {
+            let data = AppData {
+                passphrase: self.passphrase.clone(),
+                nickname: self.nickname.clone(),
+                session: if resume { self.session.clone() } else { None },
+            };
+
+            app.set_storage(frame.storage_mut(), &data);
+
+            sk
+        } else {
+            self.error = "Invalid passphrase".to_string();
+            return;
+        };
+
+        if let Err(e) = app.connect(sk, self.nickname.trim(), ctx) {
+            self.error = "Connect error".to_string();
+            error!("Connect error: {e}");
+            return;
+        }
+
+        self.resume =
+            resume.then(|| GameState::new(app.player_id().clone(), app.nickname().to_string()));
+        if !resume {
+            self.session = None;
+        }
+    }
 }
 
 impl View for ConnectView {
@@ -71,15 +118,32 @@ impl View for ConnectView {
                 }
                 ConnectionEvent::Close => {
                     self.error = "Connection closed".to_string();
+                    self.resume = None;
                 }
                 ConnectionEvent::Error(e) => {
                     self.error = format!("Connection error {e}");
+                    self.resume = None;
                 }
                 ConnectionEvent::Message(msg) => {
                     if let Message::ServerJoined { nickname, chips } = msg.message() {
                         self.nickname = nickname.to_string();
                         self.chips = *chips;
-                        self.server_joined = true;
+
+                        if self.resume.is_some() {
+                            app.send_message(Message::JoinTable);
+                        } else {
+                            self.server_joined = true;
+                        }
+                    } else if let Some(game_state) = self.resume.as_mut() {
+                        if matches!(msg.message(), Message::TableJoined { .. }) {
+                            self.table_joined = true;
+                        } else if !self.table_joined {
+                            // The table could not be joined, fall back to the
+                            // account view to join a table from there.
+                            self.server_joined = true;
+                        }
+
+                        game_state.handle_message(msg);
                     }
                 }
             }
@@ -124,11 +188,14 @@ impl View for ConnectView {
                     ui.input(|i| {
                         for event in &i.events {
                             if let Event::Paste(text) = event {
-                                if let Ok(sk) = SigningKey::from_phrase(text) {
-                                    self.error.clear();
-                                    self.assign_key(&sk);
-                                } else {
-                                    self.error = "Invalid clipboard passphrase".to_string();
+                                match SigningKey::from_mnemonic(text) {
+                                    Ok(sk) => {
+                                        self.error.clear();
+                                        self.assign_key(&sk);
+                                    }
+                                    Err(e) => {
+                                        self.error = format!("Invalid clipboard passphrase, {e}");
+                                    }
                                 }
                             }
                         }
@@ -169,30 +236,16 @@ impl View for ConnectView {
 
                     let btn = Button::new(RichText::new("Connect").font(TEXT_FONT));
                     if ui.add_sized(vec2(120.0, 30.0), btn).clicked() {
-                        self.error.clear();
-
-                        if self.nickname.trim().is_empty() {
-                            self.error = "Invalid nickname".to_string();
-                            return;
-                        }
-
-                        let sk = if let Ok(sk) = SigningKey::from_phrase(&self.passphrase) {
-                            let data = AppData {
-                                passphrase: self.passphrase.clone(),
-                                nickname: self.nickname.clone(),
-                            };
-
-                            app.set_storage(frame.storage_mut(), &data);
+                        self.connect(ctx, frame, app, false);
+                    }
 
-                            sk
-                        } else {
-                            self.error = "Invalid passphrase".to_string();
-                            return;
-                        };
+                    // Offer to resume a table session interrupted by a disconnection.
+                    if self.session.is_some() {
+                        ui.add_space(5.0);
 
-                        if let Err(e) = app.connect(sk, self.nickname.trim(), ctx) {
-                            self.error = "Connect error".to_string();
-                            error!("Connect error: {e}");
+                        let btn = Button::new(RichText::new("Resume Game").font(TEXT_FONT));
+                        if ui.add_sized(vec2(120.0, 30.0), btn).clicked() {
+                            self.connect(ctx, frame, app, true);
                         }
                     }
                 });
@@ -201,11 +254,16 @@ impl View for ConnectView {
 
     fn next(
         &mut self,
-        _ctx: &Context,
-        _frame: &mut eframe::Frame,
+        ctx: &Context,
+        frame: &mut eframe::Frame,
         app: &mut App,
     ) -> Option<Box<dyn View>> {
-        if self.server_joined {
+        if self.table_joined
+            && let Some(game_state) = self.resume.take()
+        {
+            app.start_session(frame.storage_mut());
+            Some(Box::new(GameView::new(ctx, game_state)))
+        } else if self.server_joined {
             Some(Box::new(AccountView::new(self.chips, app)))
         } else {
             None
@@ -4,6 +4,7 @@
 //! Game view.
 use eframe::egui::*;
 use log::error;
+use std::time::Duration;
 
 use freezeout_cards::egui::Textures;
 use freezeout_core::{
@@ -22,10 +23,12 @@ pub struct GameView {
     bet_params: Option<BetParams>,
     show_account: Option<Chips>,
     show_legend: bool,
+    confirm_leave: bool,
 }
 
 struct BetParams {
     min_raise: u32,
+    max_bet: u32,
     big_blind: u32,
     raise_value: u32,
 }
@@ -52,11 +55,15 @@ impl View for GameView {
                         self.show_account = Some(*chips);
                     }
 
-                    if let Message::StartHand = msg.message() {
+                    if let Message::StartHand { .. } = msg.message() {
                         self.bet_params = None;
                     }
 
                     self.game_state.handle_message(msg);
+
+                    if let Some(msg) = self.game_state.take_resync_request() {
+                        app.send_message(msg);
+                    }
                 }
             }
         }
@@ -76,9 +83,18 @@ impl View for GameView {
                 self.paint_players(ui, &rect, app);
                 self.paint_close_button(ui, &rect, app);
                 self.paint_help_button(ui, &rect);
-                self.paint_server_key(ui, &rect);
+                self.paint_server_key(ui, &rect, app);
+                self.paint_blinds(ui, &rect);
                 self.paint_legend(ui, &rect);
             });
+
+        self.paint_leave_dialog(ctx, app);
+
+        // Repaint on new messages or input, and keep refreshing while a
+        // countdown is shown in case the timer updates are late.
+        if self.game_state.has_countdown() {
+            ctx.request_repaint_after(Self::COUNTDOWN_REPAINT);
+        }
     }
 
     fn next(
@@ -88,9 +104,16 @@ impl View for GameView {
         app: &mut App,
     ) -> Option<Box<dyn View>> {
         if self.connection_closed {
+            app.suspend_session();
             Some(Box::new(ConnectView::new(frame.storage(), app)))
         } else if let Some(chips) = self.show_account {
-            Some(Box::new(AccountView::new(chips, app)))
+            app.end_session(frame.storage_mut());
+            let can_rematch = self.game_state.game_started();
+            Some(Box::new(
+                AccountView::new(chips, app)
+                    .with_rematch(can_rematch)
+                    .with_standings(self.game_state.standings()),
+            ))
         } else {
             None
         }
@@ -104,6 +127,7 @@ impl GameView {
     const ACTION_BUTTON_LX: f32 = 81.0;
     const ACTION_BUTTON_LY: f32 = 35.0;
     const SMALL_BUTTON_SZ: Vec2 = vec2(30.0, 30.0);
+    const COUNTDOWN_REPAINT: Duration = Duration::from_millis(500);
 
     /// Creates a new [GameView].
     pub fn new(ctx: &Context, game_state: GameState) -> Self {
@@ -116,6 +140,7 @@ impl GameView {
             bet_params: None,
             show_account: None,
             show_legend: false,
+            confirm_leave: false,
         }
     }
 
@@ -298,7 +323,7 @@ impl GameView {
         let layout_job = text::LayoutJob {
             wrap: text::TextWrapping::wrap_at_width(75.0),
             ..text::LayoutJob::single_section(
-                player.player_id_digits.clone(),
+                player.player_id_short.clone(),
                 TextFormat {
                     font_id: FontId::new(13.0, FontFamily::Monospace),
                     extra_letter_spacing: 1.0,
@@ -322,7 +347,16 @@ impl GameView {
         let bg_rect = rect.expand(5.0);
         paint_border(ui, &bg_rect);
 
-        if let Some(timer) = player.action_timer {
+        if let Some(bank) = player.time_bank {
+            // Show the player is using the time bank.
+            ui.painter().text(
+                rect.center(),
+                Align2::CENTER_CENTER,
+                format!("BANK\n{bank}s"),
+                FontId::new(20.0, FontFamily::Monospace),
+                Color32::from_rgb(230, 120, 40),
+            );
+        } else if let Some(timer) = player.action_timer {
             ui.painter().text(
                 rect.center(),
                 Align2::CENTER_CENTER,
@@ -330,6 +364,15 @@ impl GameView {
                 FontId::new(50.0, FontFamily::Monospace),
                 Self::TEXT_COLOR,
             );
+        } else if let Some(timer) = player.reconnect_timer {
+            // Show the time left to a disconnected player to reconnect.
+            ui.painter().text(
+                rect.center(),
+                Align2::CENTER_CENTER,
+                format!("OFFLINE\n{timer}s"),
+                FontId::new(16.0, FontFamily::Monospace),
+                Self::TEXT_COLOR,
+            );
         } else {
             let text_pos = rect.left_top();
             ui.painter().galley(text_pos, galley, Color32::DARK_GRAY);
@@ -464,9 +507,19 @@ impl GameView {
             let mut card_rect =
                 Rect::from_min_size(cards_rect.left_top() + vec2(4.0, 4.0), card_size);
 
-            for card in &payoff.cards {
+            // Gray out the kickers so that the cards making the hand stand out.
+            let made = payoff.cards.len().saturating_sub(payoff.kickers as usize);
+            for (idx, card) in payoff.cards.iter().enumerate() {
                 let tx = textures.card(*card);
-                Image::new(&tx).corner_radius(2.0).paint_at(ui, card_rect);
+                let tint = if idx < made {
+                    Color32::WHITE
+                } else {
+                    Color32::from_gray(140)
+                };
+                Image::new(&tx)
+                    .corner_radius(2.0)
+                    .tint(tint)
+                    .paint_at(ui, card_rect);
 
                 card_rect = card_rect.translate(vec2(card_lx + 1.0, 0.0));
             }
@@ -573,6 +626,28 @@ impl GameView {
                     .galley(amount_rect.left_top(), galley.clone(), Self::TEXT_COLOR);
             }
         }
+
+        // Show the chance of winning while the board runs out.
+        if let Some(equity) = player.equity {
+            ui.painter().text(
+                rect.right_bottom() + vec2(-5.0, -3.0),
+                Align2::RIGHT_BOTTOM,
+                format!("{:.0}%", equity * 100.0),
+                FontId::new(13.0, FontFamily::Monospace),
+                Self::TEXT_COLOR,
+            );
+        }
+
+        // Spectators see the players made hands while the board runs out.
+        if let Some(hand) = &player.made_hand {
+            ui.painter().text(
+                rect.left_bottom() + vec2(5.0, -3.0),
+                Align2::LEFT_BOTTOM,
+                hand,
+                FontId::new(13.0, FontFamily::Monospace),
+                Self::TEXT_COLOR,
+            );
+        }
     }
 
     fn paint_action_controls(&mut self, ui: &mut Ui, rect: &Rect, app: &mut App) {
@@ -586,7 +661,8 @@ impl GameView {
                 vec2(Self::ACTION_BUTTON_LX, Self::ACTION_BUTTON_LY),
             );
 
-            for action in &req.actions {
+            for option in &req.actions {
+                let action = &option.action;
                 paint_border(ui, &btn_rect);
 
                 let label = match action {
@@ -636,9 +712,10 @@ impl GameView {
                             && self.bet_params.is_none()
                         {
                             self.bet_params = Some(BetParams {
-                                min_raise: req.min_raise.into(),
+                                min_raise: option.min.into(),
+                                max_bet: option.max.into(),
                                 big_blind: req.big_blind.into(),
-                                raise_value: req.min_raise.into(),
+                                raise_value: option.min.into(),
                             });
                         }
                     }
@@ -695,17 +772,10 @@ impl GameView {
 
             let big_blind = params.big_blind;
 
-            // Maximum bet is the local player chips.
-            let max_bet = self
-                .game_state
-                .players()
-                .first()
-                .map(|p| (p.chips + p.bet).into())
-                .unwrap();
-
-            // Handle case when minimum raise is greater than this player chips, so
-            // that the player can go all in.
-            let min_raise = params.min_raise.min(max_bet);
+            // The server caps the minimum raise to the player chips so that the
+            // player can always go all in.
+            let max_bet = params.max_bet;
+            let min_raise = params.min_raise;
             let slider = Slider::new(&mut params.raise_value, min_raise..=max_bet)
                 .show_value(false)
                 .step_by(big_blind as f64)
@@ -772,7 +842,7 @@ impl GameView {
         }
     }
 
-    fn paint_close_button(&self, ui: &mut Ui, rect: &Rect, app: &mut App) {
+    fn paint_close_button(&mut self, ui: &mut Ui, rect: &Rect, app: &mut App) {
         let btn = Button::new(
             RichText::new("X")
                 .font(Self::TEXT_FONT)
@@ -782,8 +852,52 @@ impl GameView {
 
         let rect = Rect::from_min_size(rect.left_top(), Self::SMALL_BUTTON_SZ);
         if ui.put(rect, btn).clicked() {
-            app.send_message(Message::LeaveTable);
+            // Ask for confirmation if leaving would forfeit the player bets.
+            if self.game_state.leave_needs_confirmation() {
+                self.confirm_leave = true;
+            } else {
+                app.send_message(Message::LeaveTable);
+            }
+        }
+    }
+
+    fn paint_leave_dialog(&mut self, ctx: &Context, app: &mut App) {
+        // Close the dialog if the hand ended while it was open.
+        if !self.game_state.leave_needs_confirmation() {
+            self.confirm_leave = false;
+        }
+
+        if !self.confirm_leave {
+            return;
         }
+
+        Window::new("Leave Table")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.label(
+                        RichText::new("A hand is in progress, leaving forfeits your bets.")
+                            .font(Self::TEXT_FONT),
+                    );
+
+                    ui.add_space(10.0);
+
+                    ui.horizontal(|ui| {
+                        let btn = Button::new(RichText::new("Leave").font(Self::TEXT_FONT));
+                        if ui.add_sized(vec2(120.0, 30.0), btn).clicked() {
+                            app.send_message(Message::LeaveTable);
+                            self.confirm_leave = false;
+                        }
+
+                        let btn = Button::new(RichText::new("Stay").font(Self::TEXT_FONT));
+                        if ui.add_sized(vec2(120.0, 30.0), btn).clicked() {
+                            self.confirm_leave = false;
+                        }
+                    });
+                });
+            });
     }
 
     fn paint_help_button(&mut self, ui: &mut Ui, rect: &Rect) {
@@ -847,9 +961,75 @@ impl GameView {
         }
     }
 
-    fn paint_server_key(&self, ui: &mut Ui, rect: &Rect) {
+    fn paint_server_key(&self, ui: &mut Ui, rect: &Rect, app: &App) {
+        let Some(server_id) = self.game_state.server_id() else {
+            return;
+        };
+
+        let format = |color| TextFormat {
+            font_id: Self::TEXT_FONT,
+            color,
+            ..Default::default()
+        };
+
+        // The server is verified if its key matches the trusted key.
+        let (badge, badge_color) = match &app.config.server_key {
+            Some(key) if key == server_id => ("VERIFIED", Self::TEXT_COLOR),
+            Some(_) => ("UNTRUSTED", Color32::RED),
+            None => ("UNVERIFIED", Color32::GRAY),
+        };
+
+        let [r, g, b] = server_id.color();
+        let mut layout_job = text::LayoutJob::default();
+        layout_job.append("Server: ", 0.0, format(Self::TEXT_COLOR));
+        layout_job.append(
+            &server_id.fingerprint(),
+            0.0,
+            format(Color32::from_rgb(r, g, b)),
+        );
+        layout_job.append(badge, 8.0, format(badge_color));
+
+        let galley = ui.painter().layout_job(layout_job);
+
+        const BORDER: f32 = 4.0;
+        let text_size = galley.rect.size() + Vec2::splat(BORDER * 2.0);
+        let text_pos = rect.left_bottom() + vec2(0.0, -text_size.y);
+        let rect = Rect::from_min_size(text_pos, text_size);
+
+        ui.painter().rect(
+            rect,
+            CornerRadius {
+                ne: 5,
+                ..Default::default()
+            },
+            Color32::from_gray(20),
+            Stroke::NONE,
+            StrokeKind::Inside,
+        );
+
+        ui.painter()
+            .galley(text_pos + Vec2::splat(BORDER), galley, Color32::DARK_GRAY);
+    }
+
+    fn paint_blinds(&self, ui: &mut Ui, rect: &Rect) {
+        if !self.game_state.game_started() {
+            return;
+        }
+
+        let blinds = self.game_state.blinds();
+        let mut text = format!(
+            "Level {}: {}/{}",
+            blinds.level, blinds.small_blind, blinds.big_blind
+        );
+
+        match blinds.hands_to_next {
+            Some(1) => text.push_str(", next hand up"),
+            Some(n) => text.push_str(&format!(", up in {n} hands")),
+            None => {}
+        }
+
         let layout_job = text::LayoutJob::single_section(
-            format!("Server: {}", self.game_state.server_key()),
+            text,
             TextFormat {
                 font_id: Self::TEXT_FONT,
                 color: Self::TEXT_COLOR,
@@ -861,13 +1041,13 @@ impl GameView {
 
         const BORDER: f32 = 4.0;
         let text_size = galley.rect.size() + Vec2::splat(BORDER * 2.0);
-        let text_pos = rect.left_bottom() + vec2(0.0, -text_size.y);
+        let text_pos = rect.right_bottom() - text_size;
         let rect = Rect::from_min_size(text_pos, text_size);
 
         ui.painter().rect(
             rect,
             CornerRadius {
-                ne: 5,
+                nw: 5,
                 ..Default::default()
             },
             Color32::from_gray(20),
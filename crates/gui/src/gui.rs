@@ -6,9 +6,10 @@ use anyhow::Result;
 use eframe::egui::*;
 use serde::{Deserialize, Serialize};
 
-use freezeout_cards::egui::Textures;
+use freezeout_cards::egui::{ImageScale, Textures};
 use freezeout_core::{
     crypto::{PeerId, SigningKey},
+    game_state::ActionBuffer,
     message::{Message, SignedMessage},
 };
 
@@ -19,6 +20,8 @@ use crate::{ConnectView, Connection, ConnectionEvent};
 pub struct Config {
     /// The server address in 'host:port' format.
     pub server_url: String,
+    /// The trusted server key, servers are shown as unverified if `None`.
+    pub server_key: Option<PeerId>,
 }
 
 /// Data persisted across sessions.
@@ -28,6 +31,30 @@ pub struct AppData {
     pub passphrase: String,
     /// The last saved nickname.
     pub nickname: String,
+    /// The last table session, if any.
+    #[serde(default)]
+    pub session: Option<Session>,
+}
+
+impl AppData {
+    /// Seconds after the last activity a table session can be resumed.
+    pub const SESSION_TIMEOUT: u64 = 60;
+
+    /// Returns the table session if it was active within the session timeout.
+    ///
+    /// The time is given in seconds since the Unix epoch.
+    pub fn resumable_session(&self, now: u64) -> Option<&Session> {
+        self.session
+            .as_ref()
+            .filter(|s| now.saturating_sub(s.last_seen) <= Self::SESSION_TIMEOUT)
+    }
+}
+
+/// A table session that can be resumed after a disconnection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// The last time the session was active in seconds since the Unix epoch.
+    pub last_seen: u64,
 }
 
 /// The application state shared by all views.
@@ -44,6 +71,10 @@ pub struct App {
     nickname: String,
     /// This client connection.
     connection: Option<Connection>,
+    /// The player is seated at a table.
+    in_session: bool,
+    /// The last action sent, sent again if lost in a connection drop.
+    action_buffer: ActionBuffer,
 }
 
 impl App {
@@ -58,6 +89,8 @@ impl App {
             sk,
             nickname: String::default(),
             connection: None,
+            in_session: false,
+            action_buffer: ActionBuffer::default(),
         }
     }
 
@@ -70,6 +103,12 @@ impl App {
         }
 
         self.connection = Some(con);
+
+        // A buffered action can only be sent again by the same player.
+        if self.player_id != sk.verifying_key().peer_id() {
+            self.action_buffer = ActionBuffer::default();
+        }
+
         self.player_id = sk.verifying_key().peer_id();
         self.sk = sk;
         self.nickname = nickname.to_string();
@@ -84,11 +123,18 @@ impl App {
 
     /// Polls the active connection.
     pub fn poll_network(&mut self) -> Option<ConnectionEvent> {
-        if let Some(c) = self.connection.as_mut() {
-            c.poll()
-        } else {
-            None
+        let event = self.connection.as_mut()?.poll();
+
+        // Send again an action that was lost when the connection dropped.
+        if let Some(ConnectionEvent::Message(msg)) = &event
+            && let Some(msg) = self
+                .action_buffer
+                .handle_message(&self.player_id, msg.message())
+        {
+            self.send_message(msg);
         }
+
+        event
     }
 
     /// This client player id.
@@ -103,6 +149,10 @@ impl App {
 
     /// Sends a message to the server.
     pub fn send_message(&mut self, msg: Message) {
+        if let Message::ActionResponse { action, amount } = &msg {
+            self.action_buffer.push(*action, *amount);
+        }
+
         if let Some(c) = self.connection.as_mut() {
             let msg = SignedMessage::new(&self.sk, msg);
             c.send(&msg);
@@ -132,6 +182,56 @@ impl App {
             s.flush();
         }
     }
+
+    /// Starts a table session after the player joined a table.
+    pub fn start_session(&mut self, storage: Option<&mut (dyn eframe::Storage + 'static)>) {
+        self.in_session = true;
+        if let Some(s) = storage {
+            self.save_session(s);
+        }
+    }
+
+    /// Suspends the table session when the connection is lost.
+    ///
+    /// The stored session is kept so that the player can resume it.
+    pub fn suspend_session(&mut self) {
+        self.in_session = false;
+    }
+
+    /// Ends the table session after the player left the table.
+    pub fn end_session(&mut self, storage: Option<&mut (dyn eframe::Storage + 'static)>) {
+        self.in_session = false;
+        if let Some(s) = storage {
+            self.save_session(s);
+        }
+    }
+
+    /// Stores the session if active or removes the stored session.
+    fn save_session(&self, storage: &mut dyn eframe::Storage) {
+        if let Some(mut data) = eframe::get_value::<AppData>(storage, Self::STORAGE_KEY) {
+            data.session = self.in_session.then(|| Session {
+                last_seen: unix_time(),
+            });
+            eframe::set_value::<AppData>(storage, Self::STORAGE_KEY, &data);
+            storage.flush();
+        }
+    }
+}
+
+/// Returns the current time in seconds since the Unix epoch.
+pub(crate) fn unix_time() -> u64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        (web_sys::js_sys::Date::now() / 1000.0) as u64
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default()
+    }
 }
 
 /// Traits for UI views.
@@ -160,7 +260,8 @@ impl AppFrame {
         cc.egui_ctx.set_theme(Theme::Dark);
 
         log::info!("Creating new app with config: {config:?}");
-        let app = App::new(config, Textures::new(&cc.egui_ctx));
+        let scale = ImageScale::from_pixels_per_point(cc.egui_ctx.pixels_per_point());
+        let app = App::new(config, Textures::new_with_scale(&cc.egui_ctx, scale));
         let panel = Box::new(ConnectView::new(cc.storage, &app));
 
         AppFrame { app, panel }
@@ -177,7 +278,43 @@ impl eframe::App for AppFrame {
         }
     }
 
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        // Refresh the session so that it can be resumed after a disconnection.
+        if self.app.in_session {
+            self.app.save_session(storage);
+        }
+    }
+
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
         self.app.close_connection();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resumable_session() {
+        let mut data = AppData {
+            passphrase: String::default(),
+            nickname: "alice".to_string(),
+            session: None,
+        };
+        assert!(data.resumable_session(1_000).is_none());
+
+        // A session within the timeout can be resumed.
+        data.session = Some(Session { last_seen: 1_000 });
+        assert!(data.resumable_session(1_000).is_some());
+        assert!(
+            data.resumable_session(1_000 + AppData::SESSION_TIMEOUT)
+                .is_some()
+        );
+
+        // An expired session cannot.
+        assert!(
+            data.resumable_session(1_001 + AppData::SESSION_TIMEOUT)
+                .is_none()
+        );
+    }
+}
@@ -9,12 +9,15 @@ fn main() -> eframe::Result<()> {
 
     #[derive(Debug, Parser)]
     struct Cli {
-        /// The server WebSocket url.
+        /// The server WebSocket url, use wss:// for TLS servers.
         #[arg(long, short, default_value = "ws://127.0.0.1:9871")]
         url: String,
         /// The configuration storage key.
         #[arg(long, short)]
         storage: Option<String>,
+        /// The trusted server key used to verify the server identity.
+        #[arg(long)]
+        server_key: Option<freezeout_core::crypto::PeerId>,
     }
 
     env_logger::builder()
@@ -37,6 +40,7 @@ fn main() -> eframe::Result<()> {
 
     let config = freezeout_gui::Config {
         server_url: cli.url,
+        server_key: cli.server_key,
     };
 
     let app_name = cli
@@ -74,7 +78,10 @@ fn main() {
             .expect("Failed to find server-address element")
             .inner_html();
 
-        let config = freezeout_gui::Config { server_url };
+        let config = freezeout_gui::Config {
+            server_url,
+            server_key: None,
+        };
 
         eframe::WebRunner::new()
             .start(
@@ -4,10 +4,18 @@
 //! Database types for persisting state.
 use anyhow::{Result, bail};
 use parking_lot::Mutex;
-use rusqlite::{Connection, params};
-use std::{path::Path, sync::Arc};
-
-use freezeout_core::{crypto::PeerId, poker::Chips};
+use rusqlite::{Connection, OpenFlags, OptionalExtension, params};
+use std::{
+    fmt::{self, Write},
+    path::Path,
+    str::FromStr,
+    sync::Arc,
+};
+
+use freezeout_core::{
+    crypto::{HandHash, PeerId, Signature},
+    poker::{Chips, TableId},
+};
 
 /// A database player row.
 #[derive(Debug)]
@@ -20,10 +28,108 @@ pub struct Player {
     pub chips: Chips,
 }
 
+/// A database hand row.
+#[derive(Debug)]
+pub struct HandRow {
+    /// The table where the hand was played.
+    pub table_id: String,
+    /// The hand number in the table game.
+    pub hand: u64,
+    /// The hand hash digits.
+    pub hash: String,
+    /// When the hand was saved.
+    pub created_at: String,
+}
+
+/// The operation a chips transaction belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TxKind {
+    /// A player pays the chips to join a table.
+    BuyIn,
+    /// A player gets back the buy-in after failing to join a table.
+    BuyInRefund,
+    /// A player chips are refilled to join a table.
+    Refill,
+    /// A player gets back the chips when the table closes before the game ends.
+    Refund,
+    /// A player is paid the chips left at the end of a game.
+    CashOut,
+    /// The rake taken from a hand is paid to the house account.
+    Rake,
+}
+
+impl TxKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TxKind::BuyIn => "buyin",
+            TxKind::BuyInRefund => "buyin-refund",
+            TxKind::Refill => "refill",
+            TxKind::Refund => "refund",
+            TxKind::CashOut => "cashout",
+            TxKind::Rake => "rake",
+        }
+    }
+}
+
+/// A unique chips transaction id.
+///
+/// Payments with the same id are applied only once so that a payment can be
+/// safely retried.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TxId(String);
+
+impl TxId {
+    /// Creates the id of an operation from its kind, scope, and player, see
+    /// [Db::tx_id].
+    fn new(kind: TxKind, scope: &str, player_id: &PeerId) -> Self {
+        Self(format!("{}:{scope}:{}", kind.as_str(), player_id.digits()))
+    }
+
+    /// Creates a new random transaction id.
+    pub fn new_id() -> Self {
+        Self(format!("{:032x}", rand::random::<u128>()))
+    }
+}
+
+impl fmt::Display for TxId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Parses a players seed file.
+///
+/// Each line has a player id, the player chips, and the player nickname separated
+/// by spaces, empty lines and lines starting with `#` are skipped.
+pub fn parse_seed_players(text: &str) -> Result<Vec<(PeerId, String, Chips)>> {
+    let mut players = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut parts = line.splitn(3, char::is_whitespace);
+        let (Some(player_id), Some(chips), Some(nickname)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            bail!("Invalid seed player at line {}", idx + 1);
+        };
+
+        let player_id = PeerId::from_str(player_id)?;
+        let chips = Chips::new(chips.parse()?);
+        players.push((player_id, nickname.trim().to_string(), chips));
+    }
+
+    Ok(players)
+}
+
 /// Database for persisting game and players state.
 #[derive(Debug, Clone)]
 pub struct Db {
     conn: Arc<Mutex<Connection>>,
+    /// Random id of this database instance used to scope transaction ids.
+    instance: u64,
 }
 
 impl Db {
@@ -33,9 +139,15 @@ impl Db {
 
         Self::init_database(&conn)?;
 
-        Ok(Db {
-            conn: Arc::new(Mutex::new(conn)),
-        })
+        Ok(Self::with_connection(conn))
+    }
+
+    /// Open an existing database at the given path for reading only.
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        let conn = Connection::open_with_flags(path, flags)?;
+
+        Ok(Self::with_connection(conn))
     }
 
     /// Open an in memory database.
@@ -44,9 +156,23 @@ impl Db {
 
         Self::init_database(&conn)?;
 
-        Ok(Db {
+        Ok(Self::with_connection(conn))
+    }
+
+    fn with_connection(conn: Connection) -> Self {
+        Db {
             conn: Arc::new(Mutex::new(conn)),
-        })
+            instance: rand::random(),
+        }
+    }
+
+    /// Creates the id of an operation on a player chips.
+    ///
+    /// The scope identifies the operation, for example a table game and hand, so
+    /// that retrying the same operation gives the same id. Tables and counters
+    /// restart with the server so ids are also scoped to this database instance.
+    pub fn tx_id(&self, kind: TxKind, scope: &str, player_id: &PeerId) -> TxId {
+        TxId::new(kind, &format!("{:016x}:{scope}", self.instance), player_id)
     }
 
     fn init_database(conn: &Connection) -> Result<()> {
@@ -64,19 +190,42 @@ impl Db {
             (),
         )?;
 
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS hands (
+               id INTEGER PRIMARY KEY AUTOINCREMENT,
+               table_id TEXT NOT NULL,
+               hand INTEGER NOT NULL,
+               hash TEXT NOT NULL,
+               signature BLOB NOT NULL,
+               created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            (),
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS transactions (
+               id TEXT PRIMARY KEY,
+               player_id TEXT NOT NULL,
+               amount INTEGER NOT NULL,
+               created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            (),
+        )?;
+
         Ok(())
     }
 
     /// A player join the server.
     ///
-    /// If the player doesn't exist it creates one with the given chips, for now if
-    /// the player exists but has fewer chips than join chips the chips are updated
-    /// so that the player has enough chips to join.
+    /// If the player doesn't exist it creates one with the given chips, if the
+    /// player exists, has fewer chips than join chips and `refill` is set the chips
+    /// are updated so that the player has enough chips to join.
     pub async fn join_server(
         &self,
         player_id: PeerId,
         nickname: &str,
         join_chips: Chips,
+        refill: bool,
     ) -> Result<Player> {
         let conn = self.conn.clone();
         let nickname = nickname.to_string();
@@ -103,7 +252,7 @@ impl Db {
                     let mut do_update = false;
 
                     // Reset player chips if less than join chips.
-                    if player.chips < join_chips {
+                    if refill && player.chips < join_chips {
                         player.chips = join_chips;
                         do_update = true;
                     }
@@ -153,20 +302,61 @@ impl Db {
         .await?
     }
 
+    /// Creates the given players if they don't exist.
+    ///
+    /// Existing players are not changed so that seeding can be repeated at every
+    /// start, returns the number of players created.
+    pub async fn seed_players(&self, players: &[(PeerId, String, Chips)]) -> Result<usize> {
+        let conn = self.conn.clone();
+        let players = players.to_vec();
+
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn.lock();
+            let tx = conn.transaction()?;
+
+            let mut created = 0;
+            for (player_id, nickname, chips) in players {
+                created += tx.execute(
+                    "INSERT INTO players (id, nickname, chips, last_update)
+                     VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)
+                     ON CONFLICT(id) DO NOTHING",
+                    params![player_id.digits(), nickname, chips.amount()],
+                )?;
+            }
+
+            tx.commit()?;
+
+            Ok(created)
+        })
+        .await?
+    }
+
     /// Pay an amount of chips from a player.
     ///
     /// Returns Ok(false) if the player doesn't have enough chips or an error if the
-    /// player cannot be found.
-    pub async fn pay_from_player(&self, player_id: PeerId, amount: Chips) -> Result<bool> {
+    /// player cannot be found. If a transaction with the same id has already been
+    /// applied the player balance is not changed and Ok(true) is returned.
+    pub async fn pay_from_player(
+        &self,
+        tx_id: TxId,
+        player_id: PeerId,
+        amount: Chips,
+    ) -> Result<bool> {
         let conn = self.conn.clone();
 
         tokio::task::spawn_blocking(move || {
-            let conn = conn.lock();
+            let mut conn = conn.lock();
+            let tx = conn.transaction()?;
+
+            if Self::has_transaction(&tx, &tx_id)? {
+                return Ok(true);
+            }
 
-            let mut stmt = conn.prepare("SELECT chips FROM players WHERE id = ?1")?;
+            let mut stmt = tx.prepare("SELECT chips FROM players WHERE id = ?1")?;
             let res = stmt.query_row(params![player_id.digits()], |row| {
                 Ok(Chips::from(row.get::<usize, i32>(0)? as u32))
             });
+            drop(stmt);
 
             match res {
                 Ok(chips) => {
@@ -177,7 +367,7 @@ impl Db {
                     let remaining_chips = chips - amount;
 
                     // Update chips for this player.
-                    conn.execute(
+                    tx.execute(
                         "UPDATE players SET
                            chips = ?2,
                            last_update = CURRENT_TIMESTAMP
@@ -185,6 +375,9 @@ impl Db {
                         params![player_id.digits(), remaining_chips.amount(),],
                     )?;
 
+                    Self::insert_transaction(&tx, &tx_id, &player_id, -i64::from(amount.amount()))?;
+                    tx.commit()?;
+
                     Ok(true)
                 }
                 Err(e) => Err(e.into()),
@@ -195,14 +388,20 @@ impl Db {
 
     /// Pay an amount of chips to a player.
     ///
-    /// Returns an error if the player has not been found.
-    pub async fn pay_to_player(&self, player_id: PeerId, amount: Chips) -> Result<()> {
+    /// Returns an error if the player has not been found. If a transaction with the
+    /// same id has already been applied the player balance is not changed.
+    pub async fn pay_to_player(&self, tx_id: TxId, player_id: PeerId, amount: Chips) -> Result<()> {
         let conn = self.conn.clone();
 
         tokio::task::spawn_blocking(move || {
-            let conn = conn.lock();
+            let mut conn = conn.lock();
+            let tx = conn.transaction()?;
 
-            let num_rows = conn.execute(
+            if Self::has_transaction(&tx, &tx_id)? {
+                return Ok(());
+            }
+
+            let num_rows = tx.execute(
                 "UPDATE players SET
                    chips = chips + ?2,
                    last_update = CURRENT_TIMESTAMP
@@ -212,9 +411,99 @@ impl Db {
 
             if num_rows == 0 {
                 bail!("Player {player_id} not found");
-            } else {
-                Ok(())
             }
+
+            Self::insert_transaction(&tx, &tx_id, &player_id, i64::from(amount.amount()))?;
+            tx.commit()?;
+
+            Ok(())
+        })
+        .await?
+    }
+
+    /// Checks if a transaction has already been applied.
+    fn has_transaction(conn: &Connection, tx_id: &TxId) -> Result<bool> {
+        let res = conn
+            .query_row(
+                "SELECT 1 FROM transactions WHERE id = ?1",
+                params![tx_id.0],
+                |_| Ok(()),
+            )
+            .optional()?;
+        Ok(res.is_some())
+    }
+
+    /// Records a transaction, debits have a negative amount.
+    fn insert_transaction(
+        conn: &Connection,
+        tx_id: &TxId,
+        player_id: &PeerId,
+        amount: i64,
+    ) -> Result<()> {
+        conn.execute(
+            "INSERT INTO transactions (id, player_id, amount) VALUES (?1, ?2, ?3)",
+            params![tx_id.0, player_id.digits(), amount],
+        )?;
+        Ok(())
+    }
+
+    /// Pays the rake to a house account, the account is created if it doesn't exist.
+    ///
+    /// If a transaction with the same id has already been applied the account
+    /// balance is not changed.
+    pub async fn pay_rake(&self, tx_id: TxId, account: PeerId, amount: Chips) -> Result<()> {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn.lock();
+            let tx = conn.transaction()?;
+
+            if Self::has_transaction(&tx, &tx_id)? {
+                return Ok(());
+            }
+
+            tx.execute(
+                "INSERT INTO players (id, nickname, chips, last_update)
+                 VALUES (?1, 'house', ?2, CURRENT_TIMESTAMP)
+                 ON CONFLICT(id) DO UPDATE SET
+                   chips = chips + ?2,
+                   last_update = CURRENT_TIMESTAMP",
+                params![account.digits(), amount.amount()],
+            )?;
+
+            Self::insert_transaction(&tx, &tx_id, &account, i64::from(amount.amount()))?;
+            tx.commit()?;
+
+            Ok(())
+        })
+        .await?
+    }
+
+    /// Stores a completed hand hash and its signature.
+    pub async fn save_hand(
+        &self,
+        table_id: TableId,
+        hand: usize,
+        hash: HandHash,
+        signature: Signature,
+    ) -> Result<()> {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock();
+
+            conn.execute(
+                "INSERT INTO hands (table_id, hand, hash, signature)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    table_id.to_string(),
+                    hand as i64,
+                    hash.digits(),
+                    signature.to_bytes().as_slice()
+                ],
+            )?;
+
+            Ok(())
         })
         .await?
     }
@@ -243,6 +532,102 @@ impl Db {
         })
         .await?
     }
+
+    /// Returns all players ordered by chips, richest first.
+    pub async fn players(&self) -> Result<Vec<Player>> {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock();
+
+            let mut stmt = conn.prepare(
+                "SELECT id, nickname, chips
+                 FROM players
+                 ORDER BY chips DESC, nickname",
+            )?;
+
+            let rows = stmt.query_map((), |row| {
+                Ok((
+                    row.get::<usize, String>(0)?,
+                    row.get::<usize, String>(1)?,
+                    row.get::<usize, i32>(2)?,
+                ))
+            })?;
+
+            let mut players = Vec::new();
+            for row in rows {
+                let (player_id, nickname, chips) = row?;
+                players.push(Player {
+                    player_id: PeerId::from_str(&player_id)?,
+                    nickname,
+                    chips: Chips::from(chips as u32),
+                });
+            }
+
+            Ok(players)
+        })
+        .await?
+    }
+
+    /// Returns up to `limit` saved hands, most recent first.
+    pub async fn recent_hands(&self, limit: usize) -> Result<Vec<HandRow>> {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock();
+
+            let mut stmt = conn.prepare(
+                "SELECT table_id, hand, hash, created_at
+                 FROM hands
+                 ORDER BY id DESC
+                 LIMIT ?1",
+            )?;
+
+            let rows = stmt.query_map(params![limit as i64], |row| {
+                Ok(HandRow {
+                    table_id: row.get(0)?,
+                    hand: row.get::<usize, i64>(1)? as u64,
+                    hash: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            })?;
+
+            rows.collect::<rusqlite::Result<Vec<_>>>()
+                .map_err(anyhow::Error::from)
+        })
+        .await?
+    }
+
+    /// Formats players balances and up to `hands` recent hands as text.
+    pub async fn dump(&self, hands: usize) -> Result<String> {
+        let players = self.players().await?;
+        let recent = self.recent_hands(hands).await?;
+
+        let mut out = String::new();
+        writeln!(out, "Players ({}):", players.len())?;
+        for p in &players {
+            writeln!(
+                out,
+                "{} {:>12} {}",
+                p.player_id,
+                p.chips.amount(),
+                p.nickname
+            )?;
+        }
+
+        if hands > 0 {
+            writeln!(out, "\nRecent hands ({}):", recent.len())?;
+            for h in &recent {
+                writeln!(
+                    out,
+                    "{} {} #{} {}",
+                    h.created_at, h.table_id, h.hand, h.hash
+                )?;
+            }
+        }
+
+        Ok(out)
+    }
 }
 
 #[cfg(test)]
@@ -260,7 +645,7 @@ mod tests {
 
         // Test new player.
         let player = db
-            .join_server(player_id.clone(), NICKNAME, JOIN_CHIPS)
+            .join_server(player_id.clone(), NICKNAME, JOIN_CHIPS, true)
             .await
             .unwrap();
 
@@ -269,14 +654,14 @@ mod tests {
 
         // Update nickname.
         let player = db
-            .join_server(player_id.clone(), "bob", JOIN_CHIPS)
+            .join_server(player_id.clone(), "bob", JOIN_CHIPS, true)
             .await
             .unwrap();
         assert_eq!(player.nickname, "bob");
 
         // Update chips.
         let player = db
-            .join_server(player_id.clone(), NICKNAME, JOIN_CHIPS * 2)
+            .join_server(player_id.clone(), NICKNAME, JOIN_CHIPS * 2, true)
             .await
             .unwrap();
         assert_eq!(player.chips, JOIN_CHIPS * 2);
@@ -296,12 +681,12 @@ mod tests {
         let player_id = SigningKey::default().verifying_key().peer_id();
 
         // Create a new player.
-        db.join_server(player_id.clone(), NICKNAME, JOIN_CHIPS)
+        db.join_server(player_id.clone(), NICKNAME, JOIN_CHIPS, true)
             .await
             .unwrap();
 
         // Give player 2 x JOIN_CHIPS.
-        db.pay_to_player(player_id.clone(), JOIN_CHIPS * 2)
+        db.pay_to_player(TxId::new_id(), player_id.clone(), JOIN_CHIPS * 2)
             .await
             .unwrap();
 
@@ -312,7 +697,7 @@ mod tests {
 
         // Pay from player.
         let has_chips = db
-            .pay_from_player(player_id.clone(), JOIN_CHIPS)
+            .pay_from_player(TxId::new_id(), player_id.clone(), JOIN_CHIPS)
             .await
             .unwrap();
         assert!(has_chips);
@@ -323,16 +708,309 @@ mod tests {
 
         // Pay remaining chips.
         let has_chips = db
-            .pay_from_player(player_id.clone(), JOIN_CHIPS * 2)
+            .pay_from_player(TxId::new_id(), player_id.clone(), JOIN_CHIPS * 2)
             .await
             .unwrap();
         assert!(has_chips);
 
         // Now we cannot pay anymore as we run out of chips.
         let has_chips = db
-            .pay_from_player(player_id.clone(), JOIN_CHIPS)
+            .pay_from_player(TxId::new_id(), player_id.clone(), JOIN_CHIPS)
+            .await
+            .unwrap();
+        assert!(!has_chips);
+    }
+
+    #[tokio::test]
+    async fn idempotent_payments() {
+        const JOIN_CHIPS: Chips = Chips::new(1_000_000);
+
+        let db = Db::open_in_memory().unwrap();
+        let player_id = SigningKey::default().verifying_key().peer_id();
+        db.join_server(player_id.clone(), "alice", JOIN_CHIPS, true)
+            .await
+            .unwrap();
+
+        // Applying the same debit twice changes the balance only once.
+        let tx_id = TxId::new_id();
+        for _ in 0..2 {
+            let has_chips = db
+                .pay_from_player(tx_id.clone(), player_id.clone(), Chips::new(100_000))
+                .await
+                .unwrap();
+            assert!(has_chips);
+        }
+
+        let player = db.get_player(player_id.clone()).await.unwrap();
+        assert_eq!(player.chips, Chips::new(900_000));
+
+        // Same for credits.
+        let tx_id = TxId::new_id();
+        for _ in 0..2 {
+            db.pay_to_player(tx_id.clone(), player_id.clone(), Chips::new(50_000))
+                .await
+                .unwrap();
+        }
+
+        let player = db.get_player(player_id.clone()).await.unwrap();
+        assert_eq!(player.chips, Chips::new(950_000));
+
+        // A failed debit is not recorded so it can be retried.
+        let tx_id = TxId::new_id();
+        let has_chips = db
+            .pay_from_player(tx_id.clone(), player_id.clone(), JOIN_CHIPS)
+            .await
+            .unwrap();
+        assert!(!has_chips);
+
+        db.pay_to_player(TxId::new_id(), player_id.clone(), JOIN_CHIPS)
+            .await
+            .unwrap();
+        let has_chips = db
+            .pay_from_player(tx_id, player_id.clone(), JOIN_CHIPS)
+            .await
+            .unwrap();
+        assert!(has_chips);
+
+        let player = db.get_player(player_id.clone()).await.unwrap();
+        assert_eq!(player.chips, Chips::new(950_000));
+
+        // All transactions are in the log.
+        let conn = db.conn.lock();
+        let (count, total) = conn
+            .query_row(
+                "SELECT COUNT(*), SUM(amount) FROM transactions WHERE player_id = ?1",
+                params![player_id.digits()],
+                |row| Ok((row.get::<usize, i64>(0)?, row.get::<usize, i64>(1)?)),
+            )
+            .unwrap();
+        assert_eq!(count, 4);
+        assert_eq!(total, -50_000);
+    }
+
+    #[tokio::test]
+    async fn pay_rake() {
+        let db = Db::open_in_memory().unwrap();
+        let account = SigningKey::default().verifying_key().peer_id();
+
+        // The first payment creates the account.
+        let scope = "table:1:1";
+        let tx_id = TxId::new(TxKind::Rake, scope, &account);
+        db.pay_rake(tx_id.clone(), account.clone(), Chips::new(500))
+            .await
+            .unwrap();
+        db.pay_rake(tx_id, account.clone(), Chips::new(500))
+            .await
+            .unwrap();
+
+        let tx_id = TxId::new(TxKind::Rake, "table:1:2", &account);
+        db.pay_rake(tx_id, account.clone(), Chips::new(700))
+            .await
+            .unwrap();
+
+        let player = db.get_player(account).await.unwrap();
+        assert_eq!(player.chips, Chips::new(1_200));
+        assert_eq!(player.nickname, "house");
+    }
+
+    #[tokio::test]
+    async fn save_hand() {
+        let db = Db::open_in_memory().unwrap();
+        let sk = SigningKey::default();
+        let table_id = TableId::new_id();
+
+        let hash = HandHash::new(&"hand");
+        let signature = sk.sign(&hash);
+        db.save_hand(table_id, 1, hash, signature).await.unwrap();
+
+        let conn = db.conn.lock();
+        let (hand, digits, sig) = conn
+            .query_row(
+                "SELECT hand, hash, signature FROM hands WHERE table_id = ?1",
+                params![table_id.to_string()],
+                |row| {
+                    Ok((
+                        row.get::<usize, i64>(0)?,
+                        row.get::<usize, String>(1)?,
+                        row.get::<usize, Vec<u8>>(2)?,
+                    ))
+                },
+            )
+            .unwrap();
+
+        assert_eq!(hand, 1);
+        assert_eq!(digits, hash.digits());
+        assert_eq!(sig, signature.to_bytes());
+    }
+
+    #[tokio::test]
+    async fn join_server_without_refill() {
+        const JOIN_CHIPS: Chips = Chips::new(1_000_000);
+
+        let db = Db::open_in_memory().unwrap();
+        let player_id = SigningKey::default().verifying_key().peer_id();
+
+        // A new player gets the join chips once.
+        let player = db
+            .join_server(player_id.clone(), "alice", JOIN_CHIPS, false)
+            .await
+            .unwrap();
+        assert_eq!(player.chips, JOIN_CHIPS);
+
+        // Spend all chips.
+        let has_chips = db
+            .pay_from_player(TxId::new_id(), player_id.clone(), JOIN_CHIPS)
+            .await
+            .unwrap();
+        assert!(has_chips);
+
+        // Joining again doesn't refill the player chips.
+        let player = db
+            .join_server(player_id.clone(), "alice", JOIN_CHIPS, false)
+            .await
+            .unwrap();
+        assert_eq!(player.chips, Chips::ZERO);
+
+        // And the player cannot buy in again.
+        let has_chips = db
+            .pay_from_player(TxId::new_id(), player_id.clone(), JOIN_CHIPS)
             .await
             .unwrap();
         assert!(!has_chips);
     }
+
+    #[tokio::test]
+    async fn seed_players() {
+        let alice = SigningKey::default().verifying_key().peer_id();
+        let bob = SigningKey::default().verifying_key().peer_id();
+
+        let text = format!("# Demo players\n{alice} 500000 alice\n\n{bob} 2000000 bob smith\n");
+        let players = parse_seed_players(&text).unwrap();
+        assert_eq!(players.len(), 2);
+        assert_eq!(players[1].1, "bob smith");
+        assert!(parse_seed_players("invalid").is_err());
+
+        let path = std::env::temp_dir().join(format!("seed-{}.db", TxId::new_id()));
+
+        // Seeding creates the players.
+        let db = Db::open(&path).unwrap();
+        assert_eq!(db.seed_players(&players).await.unwrap(), 2);
+
+        let player = db.get_player(bob.clone()).await.unwrap();
+        assert_eq!(player.nickname, "bob smith");
+        assert_eq!(player.chips, Chips::new(2_000_000));
+
+        db.pay_to_player(TxId::new_id(), alice.clone(), Chips::new(1_000))
+            .await
+            .unwrap();
+        drop(db);
+
+        // Seeding again on restart doesn't change existing players.
+        let db = Db::open(&path).unwrap();
+        assert_eq!(db.seed_players(&players).await.unwrap(), 0);
+
+        let player = db.get_player(alice).await.unwrap();
+        assert_eq!(player.chips, Chips::new(501_000));
+        drop(db);
+
+        for ext in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{ext}", path.display()));
+        }
+    }
+
+    #[tokio::test]
+    async fn dump_players() {
+        let alice = SigningKey::default().verifying_key().peer_id();
+        let bob = SigningKey::default().verifying_key().peer_id();
+        let players = vec![
+            (alice.clone(), "alice".to_string(), Chips::new(500_000)),
+            (bob.clone(), "bob".to_string(), Chips::new(2_000_000)),
+        ];
+
+        let path = std::env::temp_dir().join(format!("dump-{}.db", TxId::new_id()));
+        let db = Db::open(&path).unwrap();
+        db.seed_players(&players).await.unwrap();
+        db.pay_to_player(TxId::new_id(), alice.clone(), Chips::new(1_000))
+            .await
+            .unwrap();
+
+        let hash = HandHash::new(&"hand");
+        let table_id = TableId::new_id();
+        db.save_hand(table_id, 3, hash, SigningKey::default().sign(&hash))
+            .await
+            .unwrap();
+        drop(db);
+
+        let db = Db::open_read_only(&path).unwrap();
+        let players = db.players().await.unwrap();
+        assert_eq!(players.len(), 2);
+        assert_eq!(players[0].player_id, bob);
+        assert_eq!(players[0].chips, Chips::new(2_000_000));
+        assert_eq!(players[1].player_id, alice);
+        assert_eq!(players[1].chips, Chips::new(501_000));
+
+        let dump = db.dump(10).await.unwrap();
+        assert!(dump.contains(&format!("{alice}       501000 alice")));
+        assert!(dump.contains(&format!("{bob}      2000000 bob")));
+        assert!(dump.contains(&format!("{table_id} #3 {}", hash.digits())));
+
+        // A read only database cannot be changed.
+        let carol = SigningKey::default().verifying_key().peer_id();
+        let seed = [(carol, "carol".to_string(), Chips::new(1))];
+        assert!(db.seed_players(&seed).await.is_err());
+        drop(db);
+
+        for ext in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{ext}", path.display()));
+        }
+    }
+
+    #[test]
+    fn tx_id_per_operation() {
+        let alice = SigningKey::default().verifying_key().peer_id();
+        let bob = SigningKey::default().verifying_key().peer_id();
+
+        // The same operation always gets the same id.
+        let tx_id = TxId::new(TxKind::CashOut, "table:1", &alice);
+        assert_eq!(tx_id, TxId::new(TxKind::CashOut, "table:1", &alice));
+
+        // Any difference in the operation gives a different id.
+        assert_ne!(tx_id, TxId::new(TxKind::Refund, "table:1", &alice));
+        assert_ne!(tx_id, TxId::new(TxKind::CashOut, "table:2", &alice));
+        assert_ne!(tx_id, TxId::new(TxKind::CashOut, "table:1", &bob));
+    }
+
+    #[tokio::test]
+    async fn tx_id_per_instance() {
+        const JOIN_CHIPS: Chips = Chips::new(1_000_000);
+
+        let player_id = SigningKey::default().verifying_key().peer_id();
+        let path = std::env::temp_dir().join(format!("tx-{}.db", TxId::new_id()));
+
+        // The same table operation pays the player once per database instance.
+        for _ in 0..2 {
+            let db = Db::open(&path).unwrap();
+            db.join_server(player_id.clone(), "alice", JOIN_CHIPS, false)
+                .await
+                .unwrap();
+
+            let tx_id = db.tx_id(TxKind::CashOut, "1:0:1", &player_id);
+            assert_eq!(tx_id, db.tx_id(TxKind::CashOut, "1:0:1", &player_id));
+            for _ in 0..2 {
+                db.pay_to_player(tx_id.clone(), player_id.clone(), Chips::new(1_000))
+                    .await
+                    .unwrap();
+            }
+        }
+
+        // A restarted server doesn't skip payments with the same table scope.
+        let db = Db::open(&path).unwrap();
+        let player = db.get_player(player_id).await.unwrap();
+        assert_eq!(player.chips, JOIN_CHIPS + Chips::new(2_000));
+        drop(db);
+
+        for ext in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{ext}", path.display()));
+        }
+    }
 }
@@ -1,9 +1,15 @@
 // Copyright (C) 2025 Vince Vasta
 // SPDX-License-Identifier: Apache-2.0
-use clap::Parser;
-use freezeout_server::server;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum, error::ErrorKind};
+use freezeout_core::{crypto::PeerId, poker::Chips};
+use freezeout_server::{
+    server,
+    table::{
+        ActionTimeouts, EquityBudget, NicknamePolicy, RakeConfig, StraddlePosition, TableConfig,
+    },
+};
 use log::error;
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 #[derive(Debug, Parser)]
 struct Cli {
@@ -19,15 +25,144 @@ struct Cli {
     /// Number of seats per table.
     #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u8).range(2..=6))]
     seats: u8,
+    /// Percentage of each pot taken as rake, no rake if not set.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=100))]
+    rake_percent: Option<u32>,
+    /// Maximum rake taken from a hand.
+    #[arg(long, requires = "rake_percent")]
+    rake_cap: Option<u32>,
+    /// Account id credited with the rake, defaults to the server account.
+    #[arg(long, requires = "rake_percent")]
+    rake_account: Option<PeerId>,
+    /// Account id allowed to pause and resume tables, can be repeated.
+    #[arg(long = "admin")]
+    admins: Vec<PeerId>,
+    /// Minimum seconds between a player leaving a table and joining another.
+    #[arg(long, default_value_t = 0)]
+    join_cooldown: u64,
+    /// Maximum number of tables a player can join at the same time.
+    #[arg(long)]
+    max_tables_per_player: Option<usize>,
+    /// Seat players in join order instead of shuffling seats.
+    #[arg(long)]
+    fixed_seats: bool,
+    /// Seconds a disconnected player seat is held waiting for the player to reconnect.
+    #[arg(long, default_value_t = 0)]
+    reconnect_grace: u64,
+    /// Seconds allowed to clients to complete the connection handshake.
+    #[arg(long, default_value_t = 10)]
+    handshake_timeout: u64,
+    /// Seconds a client that is not at a table can be idle before being
+    /// disconnected, no timeout if zero.
+    #[arg(long, default_value_t = 0)]
+    idle_timeout: u64,
+    /// Grant join chips only once, players that lose their chips cannot join again.
+    #[arg(long)]
+    no_refill: bool,
+    /// Position of the player who posts a straddle, no straddle if not set.
+    #[arg(long, value_enum)]
+    straddle: Option<Straddle>,
+    /// Restrict preflop actions to fold or all in.
+    #[arg(long)]
+    push_fold: bool,
+    /// Maximum number of raises per street, no limit if not set.
+    #[arg(long)]
+    raise_cap: Option<usize>,
+    /// Minimum milliseconds between players actions broadcasts.
+    #[arg(long, default_value_t = 0)]
+    min_action_pace: u64,
+    /// Number of boards dealt in each hand, at most 9 with 2 seats and 8 with
+    /// 6 seats.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..))]
+    boards: u8,
+    /// Reveal all in players cards and broadcast their chance of winning.
+    #[arg(long)]
+    all_in_equity: bool,
+    /// Reveal all hands at showdown even for players that muck losing hands.
+    #[arg(long)]
+    mandatory_reveal: bool,
+    /// Ante each player posts as a percentage of the big blind, no antes if zero.
+    #[arg(long, default_value_t = 0)]
+    ante_percent: u32,
+    /// Show spectators the players made hands during all in runouts.
+    #[arg(long)]
+    spectator_hands: bool,
+    /// Maximum number of equities and spectators hands computed each second by
+    /// all tables, no limit if not set.
+    #[arg(long)]
+    max_equities_per_sec: Option<u32>,
+    /// Let players see the rest of the board after a hand ends before the river.
+    #[arg(long)]
+    rabbit_hunt: bool,
+    /// How to handle players joining a table with a nickname already in use.
+    #[arg(long, value_enum, default_value_t = Nicknames::Allow)]
+    nicknames: Nicknames,
+    /// Seconds a player has to act before the flop.
+    #[arg(long, default_value_t = 15)]
+    preflop_timeout: u64,
+    /// Seconds a player has to act on the flop.
+    #[arg(long, default_value_t = 15)]
+    flop_timeout: u64,
+    /// Seconds a player has to act on the turn.
+    #[arg(long, default_value_t = 15)]
+    turn_timeout: u64,
+    /// Seconds a player has to act on the river.
+    #[arg(long, default_value_t = 15)]
+    river_timeout: u64,
+    /// Consecutive action timeouts after which a player is removed from the table.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    max_timeouts: Option<u32>,
+    /// Start a game when this many players joined instead of waiting for a full table.
+    #[arg(long)]
+    min_players: Option<usize>,
+    /// Seconds of time bank each player can use after the action timer expires.
+    #[arg(long, default_value_t = 0)]
+    time_bank: u64,
     /// Application data path.
     #[arg(long)]
     data_path: Option<PathBuf>,
+    /// Players seed file, each line has a player id, chips, and nickname.
+    #[arg(long)]
+    seed_path: Option<PathBuf>,
     /// TLS private key PEM path.
     #[arg(long, requires = "chain_path")]
     key_path: Option<PathBuf>,
     /// TLS certificate chain PEM path.
     #[arg(long, requires = "key_path")]
     chain_path: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Server subcommands, the server runs if none is given.
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print players balances and recent hands from the database and exit.
+    Dump {
+        /// Number of recent hands to print.
+        #[arg(long, default_value_t = 10)]
+        hands: usize,
+    },
+}
+
+/// Straddle position options.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Straddle {
+    /// The player after the big blind straddles.
+    Utg,
+    /// The player with the button straddles.
+    Button,
+}
+
+/// Nickname collision policy options.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Nicknames {
+    /// Players can join with the same nickname.
+    Allow,
+    /// Players with a nickname already in use cannot join.
+    Reject,
+    /// Add a numeric suffix to nicknames already in use.
+    Suffix,
 }
 
 #[tokio::main]
@@ -39,12 +174,81 @@ async fn main() {
         .init();
 
     let cli = Cli::parse();
+    if let Some(Command::Dump { hands }) = cli.command {
+        match server::dump(&cli.data_path, hands).await {
+            Ok(text) => print!("{text}"),
+            Err(e) => error!("{e}"),
+        }
+
+        return;
+    }
+
+    let max_boards = TableConfig::max_boards(cli.seats as usize);
+    if cli.boards as usize > max_boards {
+        Cli::command()
+            .error(
+                ErrorKind::ValueValidation,
+                format!(
+                    "cannot deal {} boards with {} seats, the maximum is {max_boards}",
+                    cli.boards, cli.seats
+                ),
+            )
+            .exit();
+    }
+
     let config = freezeout_server::Config {
         address: cli.address,
         port: cli.port,
         tables: cli.tables as usize,
         seats: cli.seats as usize,
+        table: TableConfig {
+            rake: cli.rake_percent.map(|percent| RakeConfig {
+                percent,
+                cap: Chips::new(cli.rake_cap.unwrap_or(u32::MAX)),
+                account: cli.rake_account,
+            }),
+            admins: cli.admins,
+            join_cooldown: Duration::from_secs(cli.join_cooldown),
+            max_tables_per_player: cli.max_tables_per_player,
+            fixed_seats: cli.fixed_seats,
+            reconnect_grace: Duration::from_secs(cli.reconnect_grace),
+            straddle: cli.straddle.map(|s| match s {
+                Straddle::Utg => StraddlePosition::Utg,
+                Straddle::Button => StraddlePosition::Button,
+            }),
+            push_fold: cli.push_fold,
+            raise_cap: cli.raise_cap,
+            min_action_pace: Duration::from_millis(cli.min_action_pace),
+            boards: cli.boards as usize,
+            all_in_equity: cli.all_in_equity,
+            mandatory_reveal: cli.mandatory_reveal,
+            ante_percent: cli.ante_percent,
+            spectator_hands: cli.spectator_hands,
+            equity_budget: cli
+                .max_equities_per_sec
+                .map(|limit| Arc::new(EquityBudget::new(limit, Duration::from_secs(1)))),
+            rabbit_hunt: cli.rabbit_hunt,
+            nickname_policy: match cli.nicknames {
+                Nicknames::Allow => NicknamePolicy::Allow,
+                Nicknames::Reject => NicknamePolicy::Reject,
+                Nicknames::Suffix => NicknamePolicy::Suffix,
+            },
+            time_bank: Duration::from_secs(cli.time_bank),
+            action_timeouts: ActionTimeouts {
+                preflop: Duration::from_secs(cli.preflop_timeout),
+                flop: Duration::from_secs(cli.flop_timeout),
+                turn: Duration::from_secs(cli.turn_timeout),
+                river: Duration::from_secs(cli.river_timeout),
+            },
+            max_timeouts: cli.max_timeouts.map(|n| n as usize),
+            min_players: cli.min_players,
+            observer: None,
+        },
+        handshake_timeout: Duration::from_secs(cli.handshake_timeout),
+        idle_timeout: Duration::from_secs(cli.idle_timeout),
+        refill_chips: !cli.no_refill,
         data_path: cli.data_path,
+        seed_path: cli.seed_path,
         key_path: cli.key_path,
         chain_path: cli.chain_path,
     };
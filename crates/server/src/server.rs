@@ -33,8 +33,8 @@ use freezeout_core::{
 };
 
 use crate::{
-    db::Db,
-    table::{Table, TableMessage},
+    db::{self, Db, TxKind},
+    table::{Table, TableConfig, TableMessage},
     tables_pool::{TablesPool, TablesPoolsError},
 };
 
@@ -49,8 +49,20 @@ pub struct Config {
     pub tables: usize,
     /// The number of seats per table.
     pub seats: usize,
+    /// The tables configuration.
+    pub table: TableConfig,
+    /// Time allowed to clients to complete the connection handshake.
+    pub handshake_timeout: Duration,
+    /// Time a client that is not at a table can be idle before the connection
+    /// is closed, no timeout if zero.
+    pub idle_timeout: Duration,
+    /// Refill players chips when they don't have enough chips to join a table,
+    /// if not set the join chips are granted only once.
+    pub refill_chips: bool,
     /// Application data path.
     pub data_path: Option<PathBuf>,
+    /// Optional file with players created at startup if they don't exist.
+    pub seed_path: Option<PathBuf>,
     /// TLS private key PEM path.
     pub key_path: Option<PathBuf>,
     /// TLS certificate chain PEM path.
@@ -59,6 +71,15 @@ pub struct Config {
 
 /// Server entry point.
 pub async fn run(config: Config) -> Result<()> {
+    let max_boards = TableConfig::max_boards(config.seats);
+    if config.table.boards > max_boards {
+        bail!(
+            "Cannot deal {} boards with {} seats, the maximum is {max_boards}",
+            config.table.boards,
+            config.seats
+        );
+    }
+
     let addr = format!("{}:{}", config.address, config.port);
     info!(
         "Listening on {} with {} tables and {} seats per table",
@@ -71,6 +92,11 @@ pub async fn run(config: Config) -> Result<()> {
 
     let sk = load_signing_key(&config.data_path)?;
     let db = open_database(&config.data_path)?;
+    if let Some(path) = &config.seed_path {
+        let players = db::parse_seed_players(&std::fs::read_to_string(path)?)?;
+        let created = db.seed_players(&players).await?;
+        info!("Seeded {created} players from {}", path.display());
+    }
     let tls = match (config.key_path, config.chain_path) {
         (Some(key), Some(chain)) => Some(load_tls(&key, &chain)?),
         _ => {
@@ -86,6 +112,7 @@ pub async fn run(config: Config) -> Result<()> {
     let tables = TablesPool::new(
         config.tables,
         config.seats,
+        config.table,
         sk.clone(),
         db.clone(),
         &shutdown_broadcast_tx,
@@ -98,6 +125,10 @@ pub async fn run(config: Config) -> Result<()> {
         db,
         listener,
         tls,
+        handshake_timeout: config.handshake_timeout,
+        idle_timeout: config.idle_timeout,
+        refill_chips: config.refill_chips,
+        max_client_chips: Handler::JOIN_TABLE_CHIPS * config.seats as u32,
         shutdown_broadcast_tx,
         shutdown_complete_tx,
     };
@@ -139,6 +170,14 @@ struct Server {
     listener: TcpListener,
     /// The async accetor for TLS connections.
     tls: Option<TlsAcceptor>,
+    /// Time allowed to clients to complete the connection handshake.
+    handshake_timeout: Duration,
+    /// Time a client that is not at a table can be idle.
+    idle_timeout: Duration,
+    /// Refill players chips when they don't have enough chips to join a table.
+    refill_chips: bool,
+    /// The largest chips amount accepted from clients.
+    max_client_chips: Chips,
     /// Shutdown notification channel.
     shutdown_broadcast_tx: broadcast::Sender<()>,
     /// Shutdown sender cloned by each connection.
@@ -156,18 +195,32 @@ impl Server {
                 tables: self.tables.clone(),
                 sk: self.sk.clone(),
                 db: self.db.clone(),
+                session_id: format!("{:032x}", rand::random::<u128>()),
+                tx_count: 0,
                 table: None,
+                rematch_players: None,
+                spectating: false,
+                handshake_timeout: self.handshake_timeout,
+                idle_timeout: self.idle_timeout,
+                refill_chips: self.refill_chips,
+                max_client_chips: self.max_client_chips,
                 shutdown_broadcast_rx: self.shutdown_broadcast_tx.subscribe(),
                 _shutdown_complete_tx: self.shutdown_complete_tx.clone(),
             };
 
             let tls_acceptor = self.tls.clone();
+            let handshake_timeout = self.handshake_timeout;
             // Spawn a task to handle connection messages.
             tokio::spawn(async move {
                 let res = if let Some(acceptor) = tls_acceptor {
-                    match acceptor.accept(stream).await {
-                        Ok(stream) => handler.run_tls(stream).await,
-                        Err(e) => Err(e.into()),
+                    // Don't let clients hold a connection open without completing
+                    // the TLS handshake.
+                    match time::timeout(handshake_timeout, acceptor.accept(stream)).await {
+                        Ok(Ok(stream)) => handler.run_tls(stream).await,
+                        Ok(Err(e)) => Err(e.into()),
+                        Err(_) => Err(anyhow!(
+                            "TLS handshake timed out after {handshake_timeout:?}"
+                        )),
                     }
                 } else {
                     handler.run_tcp(stream).await
@@ -211,8 +264,25 @@ struct Handler {
     sk: Arc<SigningKey>,
     /// The players DB.
     db: Db,
+    /// Random id of this connection used to scope its chips transactions.
+    session_id: String,
+    /// The number of chips operations started on this connection.
+    tx_count: u64,
     /// This client table.
     table: Option<Arc<Table>>,
+    /// The players of the last game this client played, used for rematches.
+    rematch_players: Option<Vec<PeerId>>,
+    /// The client is watching the game at its table without a seat.
+    spectating: bool,
+    /// Time allowed to the client to complete the connection handshake.
+    handshake_timeout: Duration,
+    /// Time the client can be idle when not at a table, no timeout if zero.
+    idle_timeout: Duration,
+    /// Refill the player chips when they don't have enough chips to join a table.
+    refill_chips: bool,
+    /// The largest chips amount accepted from the client, the chips of all
+    /// players at a table.
+    max_client_chips: Chips,
     /// Channel for listening shutdown notification.
     shutdown_broadcast_rx: broadcast::Receiver<()>,
     /// Sender that drops when this connection is done.
@@ -221,10 +291,12 @@ struct Handler {
 
 impl Handler {
     const JOIN_TABLE_CHIPS: Chips = Chips::new(1_000_000);
+    const INVALID_MESSAGES_BUDGET: usize = 3;
 
     /// Handle TLS stream.
     async fn run_tls(&mut self, stream: TlsStream<TcpStream>) -> Result<()> {
-        let mut conn = connection::accept_async(stream).await?;
+        let mut conn =
+            connection::accept_async_with_timeout(stream, self.handshake_timeout).await?;
         let res = self.handle_connection(&mut conn).await;
         conn.close().await;
         res
@@ -232,7 +304,8 @@ impl Handler {
 
     /// Handle unsecured stream.
     async fn run_tcp(&mut self, stream: TcpStream) -> Result<()> {
-        let mut conn = connection::accept_async(stream).await?;
+        let mut conn =
+            connection::accept_async_with_timeout(stream, self.handshake_timeout).await?;
         let res = self.handle_connection(&mut conn).await;
         conn.close().await;
         res
@@ -243,6 +316,13 @@ impl Handler {
     where
         S: AsyncRead + AsyncWrite + Unpin,
     {
+        // Skip a few invalid messages before closing the connection.
+        conn.set_error_budget(Self::INVALID_MESSAGES_BUDGET);
+
+        // Idle clients that are not at a table are disconnected.
+        let has_idle_timeout = !self.idle_timeout.is_zero();
+        let mut idle_deadline = time::Instant::now() + self.idle_timeout;
+
         // Wait for a JoinServer message from the client to join this server and get
         // the client nickname and player id.
         let msg = tokio::select! {
@@ -251,6 +331,9 @@ impl Handler {
                 Some(Err(err)) => return Err(err),
                 None => return Ok(()),
             },
+            _ = time::sleep_until(idle_deadline), if has_idle_timeout => {
+                return Ok(());
+            }
             _ = self.shutdown_broadcast_rx.recv() => {
                 return Ok(());
             }
@@ -260,7 +343,12 @@ impl Handler {
             Message::JoinServer { nickname } => {
                 let player = self
                     .db
-                    .join_server(msg.sender(), nickname, Self::JOIN_TABLE_CHIPS)
+                    .join_server(
+                        msg.sender(),
+                        nickname,
+                        Self::JOIN_TABLE_CHIPS,
+                        self.refill_chips,
+                    )
                     .await?;
 
                 // Notify client with the player account.
@@ -289,6 +377,7 @@ impl Handler {
             enum Branch {
                 Conn(SignedMessage),
                 Table(TableMessage),
+                Idle,
             }
 
             let branch = tokio::select! {
@@ -303,20 +392,48 @@ impl Handler {
                     Some(msg) => Branch::Table(msg),
                     None => break Ok(()),
                 },
+                // The client has been idle for too long.
+                _ = time::sleep_until(idle_deadline), if has_idle_timeout && self.table.is_none() => {
+                    Branch::Idle
+                }
                 // Server is shutting down exit this handler.
                 _ = self.shutdown_broadcast_rx.recv() => break Ok(()),
             };
 
+            idle_deadline = time::Instant::now() + self.idle_timeout;
+
+            // Drop client messages with chips amounts out of range.
+            if let Branch::Conn(msg) = &branch
+                && let Err(e) = check_client_chips(msg.message(), self.max_client_chips)
+            {
+                warn!("Invalid message from player {player_id}: {e}");
+                continue;
+            }
+
             match branch {
                 Branch::Conn(msg) => match msg.message() {
-                    Message::JoinTable => {
-                        // For now refill player chips if needed.
+                    Message::JoinTable if !self.spectating => {
+                        // Take back a seat held after a lost connection.
+                        if let Some(table) =
+                            self.tables.reconnect(&player_id, table_tx.clone()).await
+                        {
+                            self.table = Some(table);
+                            self.drop_rematch(&player_id).await;
+                            continue;
+                        }
+
+                        // Refill player chips if needed and enabled.
                         self.get_or_refill_chips(&player_id).await?;
 
                         // Pay chips to joins a table.
+                        let scope = self.next_tx_scope();
                         let has_chips = self
                             .db
-                            .pay_from_player(player_id.clone(), Self::JOIN_TABLE_CHIPS)
+                            .pay_from_player(
+                                self.db.tx_id(TxKind::BuyIn, &scope, &player_id),
+                                player_id.clone(),
+                                Self::JOIN_TABLE_CHIPS,
+                            )
                             .await?;
                         if has_chips {
                             let res = self
@@ -329,11 +446,18 @@ impl Handler {
                                 )
                                 .await;
                             match res {
-                                Ok(table) => self.table = Some(table),
+                                Ok(table) => {
+                                    self.table = Some(table);
+                                    self.drop_rematch(&player_id).await;
+                                }
                                 Err(e) => {
                                     // Refund chips and notify client.
                                     self.db
-                                        .pay_to_player(player_id.clone(), Self::JOIN_TABLE_CHIPS)
+                                        .pay_to_player(
+                                            self.db.tx_id(TxKind::BuyInRefund, &scope, &player_id),
+                                            player_id.clone(),
+                                            Self::JOIN_TABLE_CHIPS,
+                                        )
                                         .await?;
 
                                     let msg = match e {
@@ -341,6 +465,17 @@ impl Handler {
                                         TablesPoolsError::AlreadyJoined => {
                                             Message::PlayerAlreadyJoined
                                         }
+                                        TablesPoolsError::NicknameTaken => Message::NicknameTaken,
+                                        TablesPoolsError::TooManyTables(max_tables) => {
+                                            Message::TooManyTables {
+                                                max_tables: max_tables as u16,
+                                            }
+                                        }
+                                        TablesPoolsError::JoinCooldown(left) => {
+                                            Message::JoinCooldown {
+                                                seconds: left.as_secs_f32().ceil() as u16,
+                                            }
+                                        }
                                     };
 
                                     conn.send(&SignedMessage::new(&self.sk, msg)).await?;
@@ -356,6 +491,43 @@ impl Handler {
                     Message::LeaveTable => {
                         if let Some(table) = &self.table {
                             table.leave(&player_id).await;
+                            if !self.spectating {
+                                self.tables.player_left(&player_id).await;
+                            }
+                        }
+                    }
+                    Message::SpectateTable if self.table.is_none() => {
+                        match self.tables.spectate(&player_id, table_tx.clone()).await {
+                            Some(table) => {
+                                self.table = Some(table);
+                                self.spectating = true;
+                            }
+                            None => {
+                                let msg = SignedMessage::new(&self.sk, Message::NoTablesLeft);
+                                conn.send(&msg).await?;
+                            }
+                        }
+                    }
+                    Message::RequestRematch if self.table.is_none() => {
+                        if let Some(players) = &self.rematch_players {
+                            let res = self
+                                .tables
+                                .request_rematch(&player_id, players, table_tx.clone())
+                                .await;
+                            if res.is_err() {
+                                let msg = SignedMessage::new(&self.sk, Message::NoTablesLeft);
+                                conn.send(&msg).await?;
+                            }
+                        }
+                    }
+                    Message::PauseTable { table_id } => {
+                        if !self.tables.set_paused(&player_id, *table_id, true).await {
+                            warn!("Player {player_id} cannot pause table {table_id}");
+                        }
+                    }
+                    Message::ResumeTable { table_id } => {
+                        if !self.tables.set_paused(&player_id, *table_id, false).await {
+                            warn!("Player {player_id} cannot resume table {table_id}");
                         }
                     }
                     _ => {
@@ -374,12 +546,31 @@ impl Handler {
                         // If a player leaves the table reset the table and send
                         // updated player account information to the client.
                         self.table = None;
+                        self.spectating = false;
+                        self.tables.table_left(&player_id).await;
 
                         // Tell the client to show the account dialog.
                         let chips = self.get_or_refill_chips(&player_id).await?;
                         let msg = Message::ShowAccount { chips };
                         conn.send(&SignedMessage::new(&self.sk, msg)).await?;
                     }
+                    TableMessage::GameEnded(players) => {
+                        self.rematch_players = Some(players);
+                    }
+                    TableMessage::Rematch(table) => {
+                        self.rematch_players = None;
+                        if self.table.is_some() {
+                            // The player is already at another table, release
+                            // the rematch seat instead of seating it twice.
+                            info!("Player {player_id} declined a rematch while seated");
+                            self.tables.table_left(&player_id).await;
+                        } else if let Some(msg) = self
+                            .join_rematch(&player_id, &nickname, table_tx.clone(), table)
+                            .await?
+                        {
+                            conn.send(&SignedMessage::new(&self.sk, msg)).await?;
+                        }
+                    }
                     TableMessage::Throttle(dt) => {
                         time::sleep(dt).await;
                     }
@@ -388,23 +579,104 @@ impl Handler {
                         break Ok(());
                     }
                 },
+                Branch::Idle => {
+                    info!("Closing idle connection for player {player_id}");
+                    let msg = SignedMessage::new(&self.sk, Message::IdleTimeout);
+                    let _ = conn.send(&msg).await;
+                    break Ok(());
+                }
             }
         };
 
-        if let Some(table) = &self.table {
+        if let Some(table) = &self.table
+            && self.spectating
+        {
             table.leave(&player_id).await;
+        } else if let Some(table) = &self.table {
+            // The seat still counts towards the player tables while it is held.
+            if table.disconnect(&player_id).await {
+                self.tables
+                    .player_disconnected(&player_id, table.clone())
+                    .await;
+            } else {
+                self.tables.table_left(&player_id).await;
+            }
+            self.tables.player_left(&player_id).await;
         }
 
+        self.tables.cancel_rematch(&player_id).await;
+
         res
     }
 
+    /// Drops the rematch offer of a player that joined another table.
+    async fn drop_rematch(&mut self, player_id: &PeerId) {
+        self.rematch_players = None;
+        self.tables.cancel_rematch(player_id).await;
+    }
+
+    /// Joins the table reserved for a rematch, returns a message for the client
+    /// if the player cannot join.
+    async fn join_rematch(
+        &mut self,
+        player_id: &PeerId,
+        nickname: &str,
+        table_tx: mpsc::Sender<TableMessage>,
+        table: Arc<Table>,
+    ) -> Result<Option<Message>> {
+        self.get_or_refill_chips(player_id).await?;
+
+        let scope = self.next_tx_scope();
+        let has_chips = self
+            .db
+            .pay_from_player(
+                self.db.tx_id(TxKind::BuyIn, &scope, player_id),
+                player_id.clone(),
+                Self::JOIN_TABLE_CHIPS,
+            )
+            .await?;
+        if !has_chips {
+            self.tables.table_left(player_id).await;
+            return Ok(Some(Message::NotEnoughChips));
+        }
+
+        let res = table
+            .try_join(player_id, nickname, Self::JOIN_TABLE_CHIPS, table_tx)
+            .await;
+        if res.is_err() {
+            self.db
+                .pay_to_player(
+                    self.db.tx_id(TxKind::BuyInRefund, &scope, player_id),
+                    player_id.clone(),
+                    Self::JOIN_TABLE_CHIPS,
+                )
+                .await?;
+            self.tables.table_left(player_id).await;
+            return Ok(Some(Message::NoTablesLeft));
+        }
+
+        self.table = Some(table);
+        Ok(None)
+    }
+
+    /// Returns the scope for the transactions of a new chips operation, an
+    /// operation and its refund share the scope so that each is applied once.
+    fn next_tx_scope(&mut self) -> String {
+        self.tx_count += 1;
+        format!("{}:{}", self.session_id, self.tx_count)
+    }
+
     async fn get_or_refill_chips(&mut self, player_id: &PeerId) -> Result<Chips> {
         let mut player = self.db.get_player(player_id.clone()).await?;
 
-        // For now refill player to be able to join a table.
-        if player.chips < Self::JOIN_TABLE_CHIPS {
+        // Refill player to be able to join a table.
+        if self.refill_chips && player.chips < Self::JOIN_TABLE_CHIPS {
             let refill = Self::JOIN_TABLE_CHIPS - player.chips;
-            self.db.pay_to_player(player_id.clone(), refill).await?;
+            let scope = self.next_tx_scope();
+            let tx_id = self.db.tx_id(TxKind::Refill, &scope, player_id);
+            self.db
+                .pay_to_player(tx_id, player_id.clone(), refill)
+                .await?;
             player.chips = Self::JOIN_TABLE_CHIPS;
         }
 
@@ -442,28 +714,42 @@ fn load_signing_key(path: &Option<PathBuf>) -> Result<Arc<SigningKey>> {
     }
 }
 
-fn open_database(path: &Option<PathBuf>) -> Result<Db> {
-    fn load_or_create(path: &Path) -> Result<Db> {
-        let db_path = path.join("game.db");
-        if db_path.exists() {
-            info!("Loading database {}", db_path.display());
-            Db::open(db_path)
-        } else {
-            std::fs::create_dir_all(path)?;
-            info!("Writing database {}", db_path.display());
-            Db::open(db_path)
-        }
+/// Returns players balances and up to `hands` recent hands from the database.
+///
+/// The database is opened read only so that it can be inspected while a server
+/// is running.
+pub async fn dump(data_path: &Option<PathBuf>, hands: usize) -> Result<String> {
+    let db_path = data_dir(data_path)?.join("game.db");
+    if !db_path.exists() {
+        bail!("Cannot find database {}", db_path.display());
     }
 
+    Db::open_read_only(db_path)?.dump(hands).await
+}
+
+fn open_database(path: &Option<PathBuf>) -> Result<Db> {
     // Load database from user path or try to create one if it doesn't exist.
+    let path = data_dir(path)?;
+    let db_path = path.join("game.db");
+    if db_path.exists() {
+        info!("Loading database {}", db_path.display());
+        Db::open(db_path)
+    } else {
+        std::fs::create_dir_all(&path)?;
+        info!("Writing database {}", db_path.display());
+        Db::open(db_path)
+    }
+}
+
+fn data_dir(path: &Option<PathBuf>) -> Result<PathBuf> {
     if let Some(path) = path {
-        load_or_create(path)
+        Ok(path.clone())
     } else {
         let Some(proj_dirs) = directories::ProjectDirs::from("", "", "freezeout") else {
             bail!("Cannot find project dirs");
         };
 
-        load_or_create(proj_dirs.config_dir())
+        Ok(proj_dirs.config_dir().to_path_buf())
     }
 }
 
@@ -480,3 +766,109 @@ fn load_tls(key_path: &PathBuf, chain_path: &PathBuf) -> Result<TlsAcceptor> {
 
     Ok(TlsAcceptor::from(Arc::new(config)))
 }
+
+/// Checks the chips amounts in a message from a client.
+///
+/// Clients are not trusted, messages with amounts larger than `max` are rejected
+/// before they are used.
+fn check_client_chips(msg: &Message, max: Chips) -> Result<()> {
+    let amount = match msg {
+        Message::ActionResponse { amount, .. } => *amount,
+        _ => return Ok(()),
+    };
+
+    if amount > max {
+        bail!("chips amount {amount} larger than {max}");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use freezeout_core::message::PlayerAction;
+    use std::time::Instant;
+
+    #[test]
+    fn client_chips() {
+        let max = Handler::JOIN_TABLE_CHIPS * 6;
+        let response = |amount| Message::ActionResponse {
+            action: PlayerAction::Raise,
+            amount,
+        };
+
+        assert!(check_client_chips(&response(Chips::new(40_000)), max).is_ok());
+        assert!(check_client_chips(&response(max), max).is_ok());
+
+        // Amounts larger than all the chips at a table are rejected.
+        assert!(check_client_chips(&response(max + Chips::new(1)), max).is_err());
+        assert!(check_client_chips(&response(Chips::new(u32::MAX)), max).is_err());
+
+        // Messages without chips are not affected.
+        assert!(check_client_chips(&Message::JoinTable, max).is_ok());
+    }
+
+    #[tokio::test]
+    async fn idle_connection_timeout() {
+        const IDLE_TIMEOUT: Duration = Duration::from_millis(300);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let sk = Arc::new(SigningKey::default());
+        let db = Db::open_in_memory().unwrap();
+        let (shutdown_broadcast_tx, _) = broadcast::channel(1);
+        let (shutdown_complete_tx, _shutdown_complete_rx) = mpsc::channel(1);
+        let tables = TablesPool::new(
+            1,
+            2,
+            TableConfig::default(),
+            sk.clone(),
+            db.clone(),
+            &shutdown_broadcast_tx,
+            &shutdown_complete_tx,
+        );
+
+        let mut handler = Handler {
+            tables,
+            sk,
+            db,
+            session_id: "test".to_string(),
+            tx_count: 0,
+            table: None,
+            rematch_players: None,
+            spectating: false,
+            handshake_timeout: Duration::from_secs(5),
+            idle_timeout: IDLE_TIMEOUT,
+            refill_chips: true,
+            max_client_chips: Handler::JOIN_TABLE_CHIPS * 2,
+            shutdown_broadcast_rx: shutdown_broadcast_tx.subscribe(),
+            _shutdown_complete_tx: shutdown_complete_tx.clone(),
+        };
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            handler.run_tcp(stream).await
+        });
+
+        let mut conn = connection::connect_async(&format!("ws://{addr}"))
+            .await
+            .unwrap();
+        let keypair = SigningKey::default();
+        let msg = Message::JoinServer {
+            nickname: "Bob".to_string(),
+        };
+        conn.send(&SignedMessage::new(&keypair, msg)).await.unwrap();
+
+        let msg = conn.recv().await.unwrap().unwrap();
+        assert!(matches!(msg.message(), Message::ServerJoined { .. }));
+        let joined = Instant::now();
+
+        // The client is told it was idle before the connection is closed.
+        let msg = conn.recv().await.unwrap().unwrap();
+        assert!(matches!(msg.message(), Message::IdleTimeout));
+        assert!(joined.elapsed() >= IDLE_TIMEOUT - Duration::from_millis(50));
+        assert!(conn.recv().await.is_none_or(|res| res.is_err()));
+    }
+}
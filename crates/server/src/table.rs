@@ -4,7 +4,12 @@
 //! Table implementation.
 use anyhow::Result;
 use log::{error, info};
-use std::{sync::Arc, time::Duration};
+use parking_lot::Mutex;
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tokio::{
     sync::{broadcast, mpsc, oneshot},
     time,
@@ -12,17 +17,225 @@ use tokio::{
 
 use freezeout_core::{
     crypto::{PeerId, SigningKey},
-    message::SignedMessage,
-    poker::{Chips, TableId},
+    message::{HandPayoff, SignedMessage},
+    poker::{Card, Chips, Deck, TableId},
 };
 
 use crate::db::Db;
 
+mod hand_log;
 mod player;
 mod state;
+#[cfg(test)]
+mod test_support;
 
+pub use hand_log::{BettingRound, HandLog};
 pub use state::TableJoinError;
 
+/// Table configuration.
+#[derive(Debug, Clone, Default)]
+pub struct TableConfig {
+    /// Optional rake taken from each pot, no rake if `None`.
+    pub rake: Option<RakeConfig>,
+    /// Players allowed to pause and resume the table.
+    pub admins: Vec<PeerId>,
+    /// Minimum time between a player leaving a table and joining another one.
+    pub join_cooldown: Duration,
+    /// Maximum number of tables a player can join at the same time, no limit if
+    /// `None`.
+    pub max_tables_per_player: Option<usize>,
+    /// Keep players in join order instead of shuffling seats when a game starts.
+    pub fixed_seats: bool,
+    /// Time a disconnected player seat is held waiting for the player to reconnect,
+    /// disconnected players leave immediately if zero.
+    pub reconnect_grace: Duration,
+    /// Ante each player in the hand posts before the blinds as a percentage of
+    /// the big blind, no antes if zero.
+    pub ante_percent: u32,
+    /// Optional straddle posted before the cards are dealt, no straddle if `None`.
+    pub straddle: Option<StraddlePosition>,
+    /// Restrict preflop actions to fold, call an all in, or go all in.
+    pub push_fold: bool,
+    /// Maximum number of raises per street, no limit if `None`.
+    pub raise_cap: Option<usize>,
+    /// Minimum time players connections wait after each player action and
+    /// between hand phases, so that fast tables can be followed by humans.
+    pub min_action_pace: Duration,
+    /// Number of boards dealt in each hand, each board wins an equal share of
+    /// the pots, a single board if zero or one.
+    pub boards: usize,
+    /// Reveal the cards of all in players and broadcast their chance of winning
+    /// while the board runs out.
+    pub all_in_equity: bool,
+    /// Reveal all hands at showdown, players with auto muck cannot hide their
+    /// losing hands.
+    pub mandatory_reveal: bool,
+    /// Send spectators the made hand of each player during all in runouts,
+    /// seated players never get it.
+    pub spectator_hands: bool,
+    /// Optional budget shared by all tables for computing all in equities and
+    /// spectators made hands, no limit if `None`.
+    pub equity_budget: Option<Arc<EquityBudget>>,
+    /// Let players see the rest of the board after a hand that ended before the
+    /// river.
+    pub rabbit_hunt: bool,
+    /// How to handle players joining with a nickname already used at the table.
+    pub nickname_policy: NicknamePolicy,
+    /// Extra time each player can use for the game after the action timer
+    /// expires, no time bank if zero.
+    pub time_bank: Duration,
+    /// Time players have to act on each betting street.
+    pub action_timeouts: ActionTimeouts,
+    /// Number of consecutive action timeouts after which a player is removed
+    /// from the table, no limit if `None`.
+    pub max_timeouts: Option<usize>,
+    /// Number of players that start a game without waiting for all seats to be
+    /// taken, the game starts when the table is full if `None`.
+    pub min_players: Option<usize>,
+    /// Optional observer notified on hand lifecycle transitions.
+    pub observer: Option<Arc<dyn StateObserver>>,
+}
+
+impl TableConfig {
+    /// Returns the maximum number of boards that can be dealt from one deck at a
+    /// table with the given number of seats.
+    pub const fn max_boards(seats: usize) -> usize {
+        Deck::SIZE.saturating_sub(seats * 2) / 5
+    }
+}
+
+/// Observer for the hand lifecycle of a table.
+///
+/// The observer is called from the table task so its methods should return
+/// quickly, all methods default to doing nothing.
+pub trait StateObserver: fmt::Debug + Send + Sync {
+    /// Called when a new hand starts.
+    fn on_hand_start(&self, _table_id: TableId, _hand: usize) {}
+
+    /// Called when the flop, turn, or river have been dealt.
+    fn on_street(&self, _table_id: TableId, _board: &[Card]) {}
+
+    /// Called when the players cards are revealed at showdown.
+    fn on_showdown(&self, _table_id: TableId, _board: &[Card]) {}
+
+    /// Called when the pots have been paid to the winners.
+    fn on_payout(&self, _table_id: TableId, _payoffs: &[HandPayoff]) {}
+}
+
+/// The position of the player who posts a straddle.
+///
+/// A straddle is a blind bet of twice the big blind posted with three or more
+/// players in the hand, the straddling player is the last to act preflop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StraddlePosition {
+    /// The player after the big blind straddles and action starts from the next
+    /// player.
+    Utg,
+    /// The player with the button straddles and action starts from the small blind.
+    Button,
+}
+
+/// Policy for players joining a table with a nickname already in use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NicknamePolicy {
+    /// Players can join with the same nickname.
+    #[default]
+    Allow,
+    /// Players with a nickname already in use cannot join.
+    Reject,
+    /// Nicknames already in use get a numeric suffix, e.g. "Bob (2)".
+    Suffix,
+}
+
+/// Time players have to act on each betting street.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActionTimeouts {
+    /// Action timeout before the flop.
+    pub preflop: Duration,
+    /// Action timeout on the flop.
+    pub flop: Duration,
+    /// Action timeout on the turn.
+    pub turn: Duration,
+    /// Action timeout on the river.
+    pub river: Duration,
+}
+
+impl ActionTimeouts {
+    /// Creates timeouts with the same duration on all streets.
+    pub const fn uniform(timeout: Duration) -> Self {
+        Self {
+            preflop: timeout,
+            flop: timeout,
+            turn: timeout,
+            river: timeout,
+        }
+    }
+}
+
+impl Default for ActionTimeouts {
+    fn default() -> Self {
+        Self::uniform(Duration::from_secs(15))
+    }
+}
+
+/// Rake configuration.
+#[derive(Debug, Clone)]
+pub struct RakeConfig {
+    /// The percentage of each pot taken as rake.
+    pub percent: u32,
+    /// The maximum rake taken from a hand.
+    pub cap: Chips,
+    /// The account credited with the rake, if `None` the server account is used.
+    pub account: Option<PeerId>,
+}
+
+impl RakeConfig {
+    /// Returns the rake for a pot given the rake already taken in this hand.
+    pub fn rake(&self, pot: Chips, taken: Chips) -> Chips {
+        let rake = u64::from(pot.amount()) * u64::from(self.percent.min(100)) / 100;
+        let remaining = self.cap.amount().saturating_sub(taken.amount());
+        Chips::new((rake as u32).min(remaining))
+    }
+}
+
+/// A limit on the number of equity computations in a period of time.
+///
+/// Tables skip a computation instead of waiting when the budget for the current
+/// period has been used, the budget can be shared by all tables cloning its [Arc].
+#[derive(Debug)]
+pub struct EquityBudget {
+    limit: u32,
+    period: Duration,
+    window: Mutex<(Instant, u32)>,
+}
+
+impl EquityBudget {
+    /// Creates a budget of `limit` computations for each `period`.
+    pub fn new(limit: u32, period: Duration) -> Self {
+        Self {
+            limit,
+            period,
+            window: Mutex::new((Instant::now(), 0)),
+        }
+    }
+
+    /// Takes a computation from the budget, returns false if the budget for the
+    /// current period has been used.
+    pub fn try_acquire(&self) -> bool {
+        let mut window = self.window.lock();
+        if window.0.elapsed() >= self.period {
+            *window = (Instant::now(), 0);
+        }
+
+        if window.1 < self.limit {
+            window.1 += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
 /// Table state shared by all players who joined the table.
 #[derive(Debug)]
 pub struct Table {
@@ -39,6 +252,11 @@ pub enum TableMessage {
     Send(SignedMessage),
     /// Tell the client to leave the table.
     PlayerLeft,
+    /// Tell the client the game ended for this player with the list of players
+    /// who started the game.
+    GameEnded(Vec<PeerId>),
+    /// Tell the client to join the table reserved for a rematch.
+    Rematch(Arc<Table>),
     /// Tell the client to introduce a delay between messages.
     Throttle(Duration),
     /// Close a client connection.
@@ -58,8 +276,39 @@ enum TableCommand {
     },
     /// Query if a player can join the table.
     PlayerCanJoin { resp_tx: oneshot::Sender<bool> },
+    /// Reserve an empty table for the given players.
+    Reserve {
+        players: Vec<PeerId>,
+        resp_tx: oneshot::Sender<bool>,
+    },
+    /// Watch the game at this table.
+    Spectate {
+        player_id: PeerId,
+        table_tx: mpsc::Sender<TableMessage>,
+        resp_tx: oneshot::Sender<bool>,
+    },
     /// Leave this table.
     Leave(PeerId),
+    /// A player connection was lost.
+    Disconnect {
+        player_id: PeerId,
+        resp_tx: oneshot::Sender<bool>,
+    },
+    /// Suspend a player action timer while the player answers a server prompt.
+    SuspendTimer(PeerId),
+    /// Resume a player action timer after a server prompt.
+    ResumeTimer(PeerId),
+    /// Reconnect a disconnected player.
+    Reconnect {
+        player_id: PeerId,
+        table_tx: mpsc::Sender<TableMessage>,
+        resp_tx: oneshot::Sender<bool>,
+    },
+    /// Pause or resume the table.
+    SetPaused {
+        paused: bool,
+        resp_tx: oneshot::Sender<bool>,
+    },
     /// Handle a player message.
     Message(SignedMessage),
 }
@@ -68,6 +317,7 @@ impl Table {
     /// Creates a new table that manages players and game state.
     pub fn new(
         seats: usize,
+        config: TableConfig,
         sk: Arc<SigningKey>,
         db: Db,
         shutdown_broadcast_rx: broadcast::Receiver<()>,
@@ -83,6 +333,7 @@ impl Table {
         let mut task = TableTask {
             table_id,
             seats,
+            config,
             sk,
             db,
             commands_rx,
@@ -121,6 +372,24 @@ impl Table {
         res && resp_rx.await.unwrap_or(false)
     }
 
+    /// Reserves this table for the given players, returns true if the table was
+    /// empty and has been reserved.
+    ///
+    /// While the table is reserved only the given players can join it.
+    pub async fn reserve(&self, players: &[PeerId]) -> bool {
+        let (resp_tx, resp_rx) = oneshot::channel();
+
+        let res = self
+            .commands_tx
+            .send(TableCommand::Reserve {
+                players: players.to_vec(),
+                resp_tx,
+            })
+            .await
+            .is_ok();
+        res && resp_rx.await.unwrap_or(false)
+    }
+
     /// A player tried to join this table, returns true if the player joined.
     pub async fn try_join(
         &self,
@@ -145,6 +414,25 @@ impl Table {
         resp_rx.await.map_err(|_| TableJoinError::Unknown)?
     }
 
+    /// A player watches the game at this table, returns true if there is a game
+    /// in progress the player is not playing.
+    ///
+    /// Spectators get the table public updates, they leave the table with
+    /// [Table::leave].
+    pub async fn spectate(&self, player_id: &PeerId, table_tx: mpsc::Sender<TableMessage>) -> bool {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let res = self
+            .commands_tx
+            .send(TableCommand::Spectate {
+                player_id: player_id.clone(),
+                table_tx,
+                resp_tx,
+            })
+            .await
+            .is_ok();
+        res && resp_rx.await.unwrap_or(false)
+    }
+
     /// A player leaves the table.
     pub async fn leave(&self, player_id: &PeerId) {
         let _ = self
@@ -153,6 +441,72 @@ impl Table {
             .await;
     }
 
+    /// A player connection was lost, the player seat is held for the reconnect
+    /// grace period.
+    ///
+    /// Returns true if the table is holding the player seat.
+    pub async fn disconnect(&self, player_id: &PeerId) -> bool {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let res = self
+            .commands_tx
+            .send(TableCommand::Disconnect {
+                player_id: player_id.clone(),
+                resp_tx,
+            })
+            .await
+            .is_ok();
+        res && resp_rx.await.unwrap_or(false)
+    }
+
+    /// Suspends a player action timer while the player answers a server prompt,
+    /// so that the player doesn't fold for not acting during the prompt.
+    pub async fn suspend_timer(&self, player_id: &PeerId) {
+        let _ = self
+            .commands_tx
+            .send(TableCommand::SuspendTimer(player_id.clone()))
+            .await;
+    }
+
+    /// Resumes a player action timer suspended by [Table::suspend_timer].
+    pub async fn resume_timer(&self, player_id: &PeerId) {
+        let _ = self
+            .commands_tx
+            .send(TableCommand::ResumeTimer(player_id.clone()))
+            .await;
+    }
+
+    /// Reconnects a disconnected player, returns true if the player took back its
+    /// seat.
+    pub async fn reconnect(
+        &self,
+        player_id: &PeerId,
+        table_tx: mpsc::Sender<TableMessage>,
+    ) -> bool {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let res = self
+            .commands_tx
+            .send(TableCommand::Reconnect {
+                player_id: player_id.clone(),
+                table_tx,
+                resp_tx,
+            })
+            .await
+            .is_ok();
+        res && resp_rx.await.unwrap_or(false)
+    }
+
+    /// Pauses the table at the end of the current hand or resumes it, returns
+    /// false if the table was already paused or running.
+    pub async fn set_paused(&self, paused: bool) -> bool {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        let res = self
+            .commands_tx
+            .send(TableCommand::SetPaused { paused, resp_tx })
+            .await
+            .is_ok();
+        res && resp_rx.await.unwrap_or(false)
+    }
+
     /// Handle a message from a player.
     pub async fn message(&self, msg: SignedMessage) {
         let _ = self.commands_tx.send(TableCommand::Message(msg)).await;
@@ -164,6 +518,8 @@ struct TableTask {
     table_id: TableId,
     /// Table seats.
     seats: usize,
+    /// Table configuration.
+    config: TableConfig,
     /// Table key.
     sk: Arc<SigningKey>,
     /// Game db.
@@ -178,14 +534,26 @@ struct TableTask {
 
 impl TableTask {
     async fn run(&mut self) -> Result<()> {
-        let mut state =
-            state::State::new(self.table_id, self.seats, self.sk.clone(), self.db.clone());
+        let mut state = state::State::new(
+            self.table_id,
+            self.seats,
+            self.config.clone(),
+            self.sk.clone(),
+            self.db.clone(),
+        );
         let mut ticks = time::interval(Duration::from_millis(500));
 
         loop {
             tokio::select! {
-                // Server is shutting down exit this handler.
-                _ = self.shutdown_broadcast_rx.recv() => break Ok(()),
+                // Check for shutdown first so that players chips are refunded before
+                // processing leave commands sent by handlers that are shutting down.
+                biased;
+
+                // Server is shutting down refund players and exit this handler.
+                _ = self.shutdown_broadcast_rx.recv() => {
+                    state.refund_players().await;
+                    break Ok(());
+                }
                 _ = ticks.tick() => {
                     state.tick().await;
                 }
@@ -199,9 +567,35 @@ impl TableTask {
                         let res = state.player_can_join();
                         let _ = resp_tx.send(res);
                     }
+                    Some(TableCommand::Reserve { players, resp_tx }) => {
+                        let res = state.reserve(players);
+                        let _ = resp_tx.send(res);
+                    }
+                    Some(TableCommand::Spectate { player_id, table_tx, resp_tx }) => {
+                        let res = state.spectate(&player_id, table_tx).await;
+                        let _ = resp_tx.send(res);
+                    }
                     Some(TableCommand::Leave(peer_id)) => {
                         state.leave(&peer_id).await;
                     }
+                    Some(TableCommand::Disconnect { player_id, resp_tx }) => {
+                        let res = state.disconnect(&player_id).await;
+                        let _ = resp_tx.send(res);
+                    }
+                    Some(TableCommand::SuspendTimer(peer_id)) => {
+                        state.suspend_timer(&peer_id).await;
+                    }
+                    Some(TableCommand::ResumeTimer(peer_id)) => {
+                        state.resume_timer(&peer_id).await;
+                    }
+                    Some(TableCommand::Reconnect { player_id, table_tx, resp_tx }) => {
+                        let res = state.reconnect(&player_id, table_tx).await;
+                        let _ = resp_tx.send(res);
+                    }
+                    Some(TableCommand::SetPaused { paused, resp_tx }) => {
+                        let res = state.set_paused(paused).await;
+                        let _ = resp_tx.send(res);
+                    }
                     Some(TableCommand::Message(msg)) => {
                         state.message(msg).await;
 
@@ -0,0 +1,343 @@
+// Copyright (C) 2025 Vince Vasta
+// SPDX-License-Identifier: Apache-2.0
+
+//! Hand log used to audit hand results.
+use freezeout_core::{
+    crypto::{HandHash, PeerId},
+    message::{HandPayoff, PlayerAction},
+    poker::{Card, Chips, HandValue, PlayerCards, TableId},
+};
+
+/// Records the players actions in a betting round in the order they are played.
+#[derive(Debug, Default)]
+pub struct BettingRound {
+    /// Players actions with the player bet after the action.
+    actions: Vec<(PeerId, PlayerAction, Chips)>,
+}
+
+impl BettingRound {
+    /// Clears the actions for a new betting round.
+    pub fn reset(&mut self) {
+        self.actions.clear();
+    }
+
+    /// Records a player action with the player bet after the action.
+    pub fn push(&mut self, player_id: &PeerId, action: PlayerAction, bet: Chips) {
+        self.actions.push((player_id.clone(), action, bet));
+    }
+
+    /// Returns the actions in this round in the order they have been played.
+    pub fn actions(&self) -> &[(PeerId, PlayerAction, Chips)] {
+        &self.actions
+    }
+}
+
+/// Records the inputs of a hand so that its result can be hashed.
+#[derive(Debug, Default)]
+pub struct HandLog {
+    /// The hand number in the current game.
+    hand: usize,
+    /// The seed used to shuffle the hand deck.
+    seed: u64,
+    /// Players in seat order with their starting chips and hole cards.
+    ///
+    /// Hole cards of players that folded are kept for analysis, they are never
+    /// broadcast to other players.
+    players: Vec<(PeerId, Chips, PlayerCards)>,
+    /// Players actions in the order they have been played.
+    actions: Vec<(PeerId, PlayerAction, Chips)>,
+}
+
+impl HandLog {
+    /// Resets the log for a new hand.
+    pub fn start(&mut self, hand: usize) {
+        self.hand = hand;
+        self.seed = 0;
+        self.players.clear();
+        self.actions.clear();
+    }
+
+    /// Records the seed used to shuffle the hand deck.
+    pub fn seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// Records the cards dealt to a player.
+    pub fn deal(&mut self, player_id: &PeerId, chips: Chips, cards: PlayerCards) {
+        self.players.push((player_id.clone(), chips, cards));
+    }
+
+    /// Records a player action with the player bet after the action.
+    pub fn action(&mut self, player_id: &PeerId, action: PlayerAction, bet: Chips) {
+        self.actions.push((player_id.clone(), action, bet));
+    }
+
+    /// Returns the hand number.
+    pub fn hand(&self) -> usize {
+        self.hand
+    }
+
+    /// Returns the chips a player had at the start of the hand.
+    pub fn starting_chips(&self, player_id: &PeerId) -> Option<Chips> {
+        self.players
+            .iter()
+            .find(|(id, _, _)| id == player_id)
+            .map(|(_, chips, _)| *chips)
+    }
+
+    /// Returns the players that would have won the hand on the given board if
+    /// `player_id` had not folded.
+    ///
+    /// The winners are computed among the players that went to showdown plus the
+    /// given player, with more than one winner if the pot would have been split.
+    pub fn what_if_winners(&self, board: &[Card], player_id: &PeerId) -> Vec<PeerId> {
+        let folded = |id: &PeerId| {
+            self.actions
+                .iter()
+                .any(|(p, a, _)| p == id && *a == PlayerAction::Fold)
+        };
+
+        let hands = self
+            .players
+            .iter()
+            .filter(|(id, _, _)| id == player_id || !folded(id))
+            .filter_map(|(id, _, cards)| match cards {
+                PlayerCards::None | PlayerCards::Covered => None,
+                PlayerCards::Cards(c1, c2) => {
+                    let mut cards = vec![*c1, *c2];
+                    cards.extend_from_slice(board);
+                    Some((id, HandValue::eval(&cards)))
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let Some(best) = hands.iter().map(|(_, v)| *v).max() else {
+            return Vec::default();
+        };
+
+        hands
+            .into_iter()
+            .filter(|(_, v)| *v == best)
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// Computes the hash of this hand with the given board, payoffs and rake.
+    ///
+    /// The hash only depends on the hand inputs and results so that the same hand
+    /// always produces the same hash, the deck seed is included so that the deal
+    /// can be verified against the hash.
+    pub fn hash(
+        &self,
+        table_id: TableId,
+        board: &[Card],
+        payoffs: &[HandPayoff],
+        rake: Chips,
+    ) -> HandHash {
+        HandHash::new(&(
+            table_id,
+            self.hand as u64,
+            self.seed,
+            &self.players,
+            &self.actions,
+            board,
+            payoffs,
+            rake,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use freezeout_core::{
+        crypto::SigningKey,
+        poker::{Rank, Suit},
+    };
+
+    struct TestHand {
+        table_id: TableId,
+        p1: PeerId,
+        p2: PeerId,
+        log: HandLog,
+        board: Vec<Card>,
+        payoffs: Vec<HandPayoff>,
+        rake: Chips,
+    }
+
+    impl TestHand {
+        fn new(table_id: TableId, p1: PeerId, p2: PeerId) -> Self {
+            let mut log = HandLog::default();
+            log.start(1);
+            log.seed(0x1234_5678);
+            log.deal(
+                &p1,
+                Chips::new(100_000),
+                PlayerCards::Cards(
+                    Card::new(Rank::Ace, Suit::Spades),
+                    Card::new(Rank::King, Suit::Spades),
+                ),
+            );
+            log.deal(
+                &p2,
+                Chips::new(100_000),
+                PlayerCards::Cards(
+                    Card::new(Rank::Deuce, Suit::Hearts),
+                    Card::new(Rank::Seven, Suit::Clubs),
+                ),
+            );
+            log.action(&p1, PlayerAction::SmallBlind, Chips::new(10_000));
+            log.action(&p2, PlayerAction::BigBlind, Chips::new(20_000));
+            log.action(&p1, PlayerAction::Raise, Chips::new(60_000));
+            log.action(&p2, PlayerAction::Call, Chips::new(60_000));
+
+            let board = vec![
+                Card::new(Rank::Ace, Suit::Hearts),
+                Card::new(Rank::Ten, Suit::Diamonds),
+                Card::new(Rank::Four, Suit::Clubs),
+                Card::new(Rank::Nine, Suit::Spades),
+                Card::new(Rank::Trey, Suit::Hearts),
+            ];
+
+            let payoffs = vec![HandPayoff {
+                player_id: p1.clone(),
+                chips: Chips::new(120_000),
+                cards: Vec::default(),
+                rank: "PAIR".to_string(),
+                kickers: 0,
+            }];
+
+            Self {
+                table_id,
+                p1,
+                p2,
+                log,
+                board,
+                payoffs,
+                rake: Chips::ZERO,
+            }
+        }
+
+        fn hash(&self) -> HandHash {
+            self.log
+                .hash(self.table_id, &self.board, &self.payoffs, self.rake)
+        }
+    }
+
+    fn peer_id() -> PeerId {
+        SigningKey::default().verifying_key().peer_id()
+    }
+
+    #[test]
+    fn stable_hash() {
+        let table_id = TableId::new_id();
+        let (p1, p2) = (peer_id(), peer_id());
+
+        let h1 = TestHand::new(table_id, p1.clone(), p2.clone());
+        let h2 = TestHand::new(table_id, p1, p2);
+        assert_eq!(h1.hash(), h2.hash());
+        assert_eq!(h1.hash(), h1.hash());
+    }
+
+    #[test]
+    fn hash_changes_with_inputs() {
+        let table_id = TableId::new_id();
+        let (p1, p2) = (peer_id(), peer_id());
+        let expected = TestHand::new(table_id, p1.clone(), p2.clone()).hash();
+
+        let new_hand = || TestHand::new(table_id, p1.clone(), p2.clone());
+
+        // Different table.
+        let mut h = new_hand();
+        h.table_id = TableId::new_id();
+        assert_ne!(h.hash(), expected);
+
+        // Different hand number.
+        let mut h = new_hand();
+        h.log.hand = 2;
+        assert_ne!(h.hash(), expected);
+
+        // Different deck seed.
+        let mut h = new_hand();
+        h.log.seed(0x8765_4321);
+        assert_ne!(h.hash(), expected);
+
+        // Different hole cards.
+        let mut h = new_hand();
+        h.log.players[1].2 = PlayerCards::Cards(
+            Card::new(Rank::Deuce, Suit::Hearts),
+            Card::new(Rank::Eight, Suit::Clubs),
+        );
+        assert_ne!(h.hash(), expected);
+
+        // Different action amount.
+        let mut h = new_hand();
+        h.log.actions[2].2 = Chips::new(80_000);
+        assert_ne!(h.hash(), expected);
+
+        // Additional action.
+        let mut h = new_hand();
+        h.log.action(&h.p1, PlayerAction::Check, Chips::ZERO);
+        assert_ne!(h.hash(), expected);
+
+        // Different board.
+        let mut h = new_hand();
+        h.board[4] = Card::new(Rank::Ten, Suit::Hearts);
+        assert_ne!(h.hash(), expected);
+
+        // Different payoff.
+        let mut h = new_hand();
+        h.payoffs[0].player_id = h.p2.clone();
+        assert_ne!(h.hash(), expected);
+
+        let mut h = new_hand();
+        h.payoffs[0].chips = Chips::new(110_000);
+        assert_ne!(h.hash(), expected);
+
+        // Different rake.
+        let mut h = new_hand();
+        h.rake = Chips::new(1_000);
+        assert_ne!(h.hash(), expected);
+    }
+
+    #[test]
+    fn what_if_winners() {
+        let (p1, p2, p3) = (peer_id(), peer_id(), peer_id());
+
+        let mut log = HandLog::default();
+        log.start(1);
+        let cards = |r1, s1, r2, s2| PlayerCards::Cards(Card::new(r1, s1), Card::new(r2, s2));
+        log.deal(
+            &p1,
+            Chips::new(100_000),
+            cards(Rank::Ace, Suit::Spades, Rank::King, Suit::Spades),
+        );
+        log.deal(
+            &p2,
+            Chips::new(100_000),
+            cards(Rank::Four, Suit::Hearts, Rank::Four, Suit::Diamonds),
+        );
+        log.deal(
+            &p3,
+            Chips::new(100_000),
+            cards(Rank::Seven, Suit::Clubs, Rank::Deuce, Suit::Hearts),
+        );
+        log.action(&p1, PlayerAction::Raise, Chips::new(60_000));
+        log.action(&p2, PlayerAction::Fold, Chips::ZERO);
+        log.action(&p3, PlayerAction::Call, Chips::new(60_000));
+
+        let board = vec![
+            Card::new(Rank::Ace, Suit::Hearts),
+            Card::new(Rank::Ten, Suit::Diamonds),
+            Card::new(Rank::Four, Suit::Clubs),
+            Card::new(Rank::Nine, Suit::Spades),
+            Card::new(Rank::Trey, Suit::Hearts),
+        ];
+
+        // The player that folded would have won with a set of fours.
+        assert_eq!(log.what_if_winners(&board, &p2), vec![p2.clone()]);
+
+        // A player that didn't fold doesn't change the outcome.
+        assert_eq!(log.what_if_winners(&board, &p3), vec![p1.clone()]);
+    }
+}
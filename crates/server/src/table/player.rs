@@ -34,6 +34,8 @@ pub struct Player {
     pub action: PlayerAction,
     /// The player action timer.
     pub action_timer: Option<Instant>,
+    /// The action time used before the timer was suspended by a server prompt.
+    pub suspended_timer: Option<Duration>,
     /// This player cards that are visible to all other players.
     pub public_cards: PlayerCards,
     /// This player private cards.
@@ -42,6 +44,19 @@ pub struct Player {
     pub is_active: bool,
     /// The player has the button.
     pub has_button: bool,
+    /// When the player connection was lost, the seat is held until the reconnect
+    /// grace period expires.
+    pub disconnected: Option<Instant>,
+    /// The player chance of winning the hand when players are all in.
+    pub equity: Option<f32>,
+    /// The time left in the player time bank.
+    pub time_bank: Duration,
+    /// Muck losing hands at showdown.
+    pub auto_muck: bool,
+    /// Number of consecutive actions this player let time out.
+    pub timeouts: usize,
+    /// The ante this player posted in the current hand.
+    pub ante: Chips,
 }
 
 impl Player {
@@ -60,10 +75,17 @@ impl Player {
             bet: Chips::default(),
             action: PlayerAction::None,
             action_timer: None,
+            suspended_timer: None,
             public_cards: PlayerCards::None,
             hole_cards: PlayerCards::None,
             is_active: true,
             has_button: false,
+            disconnected: None,
+            equity: None,
+            time_bank: Duration::ZERO,
+            auto_muck: false,
+            timeouts: 0,
+            ante: Chips::ZERO,
         }
     }
 
@@ -99,6 +121,31 @@ impl Player {
         self.action = action;
     }
 
+    /// Stops the action timer charging the time used after the timeout to the
+    /// player time bank.
+    pub fn stop_action_timer(&mut self, timeout: Duration) {
+        let elapsed = self.action_timer.take().map(|t| t.elapsed());
+        if let Some(elapsed) = elapsed.or(self.suspended_timer.take()) {
+            let used = elapsed.saturating_sub(timeout);
+            self.time_bank = self.time_bank.saturating_sub(used);
+        }
+    }
+
+    /// Suspends the action timer keeping the time used so far.
+    pub fn suspend_action_timer(&mut self) {
+        if let Some(timer) = self.action_timer.take() {
+            self.suspended_timer = Some(timer.elapsed());
+        }
+    }
+
+    /// Resumes a suspended action timer from the time used before suspending it.
+    pub fn resume_action_timer(&mut self) {
+        if let Some(elapsed) = self.suspended_timer.take() {
+            let now = Instant::now();
+            self.action_timer = Some(now.checked_sub(elapsed).unwrap_or(now));
+        }
+    }
+
     /// Sets this player in fold state.
     pub fn fold(&mut self) {
         self.is_active = false;
@@ -106,6 +153,7 @@ impl Player {
         self.hole_cards = PlayerCards::None;
         self.public_cards = PlayerCards::None;
         self.action_timer = None;
+        self.suspended_timer = None;
     }
 
     /// Reset state for a new hand.
@@ -113,6 +161,7 @@ impl Player {
         self.is_active = self.chips > Chips::ZERO;
         self.has_button = false;
         self.bet = Chips::ZERO;
+        self.ante = Chips::ZERO;
         self.action = PlayerAction::None;
         self.public_cards = PlayerCards::None;
         self.hole_cards = PlayerCards::None;
@@ -122,6 +171,7 @@ impl Player {
     fn end_hand(&mut self) {
         self.action = PlayerAction::None;
         self.action_timer = None;
+        self.suspended_timer = None;
     }
 }
 
@@ -130,6 +180,7 @@ impl Player {
 pub struct PlayersState {
     players: Vec<Player>,
     active_player: Option<usize>,
+    heads_up: bool,
 }
 
 impl PlayersState {
@@ -273,17 +324,30 @@ impl PlayersState {
         }
     }
 
+    /// Activates the player with the button.
+    pub fn activate_button(&mut self) {
+        self.active_player = self.players.iter().position(|p| p.has_button);
+    }
+
     /// Set state for a new hand.
     pub fn start_hand(&mut self) {
         for player in &mut self.players {
             player.start_hand();
         }
 
+        self.heads_up = self.count_active() == 2;
         if self.count_active() > 1 {
             // Rotate players so that the first player becomes the button.
             loop {
                 self.players.rotate_left(1);
                 if self.players[0].is_active {
+                    if self.heads_up {
+                        // Heads up the button posts the small blind and acts first
+                        // preflop.
+                        self.players[0].has_button = true;
+                        break;
+                    }
+
                     // Checked above there are at least 2 active players, go back and
                     // set the button.
                     for p in self.players.iter_mut().rev() {
@@ -311,7 +375,8 @@ impl PlayersState {
         // more player with chips.
         if self.count_active_with_chips() > 1 {
             for (idx, p) in self.players.iter().enumerate() {
-                if p.chips > Chips::ZERO && p.is_active {
+                // Heads up the big blind acts first after the flop.
+                if p.chips > Chips::ZERO && p.is_active && !(self.heads_up && p.has_button) {
                     self.active_player = Some(idx);
                     return;
                 }
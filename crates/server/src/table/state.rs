@@ -3,8 +3,8 @@
 
 //! Table state types.
 use ahash::AHashSet;
-use log::{error, info};
-use rand::{SeedableRng, rngs::StdRng};
+use log::{debug, error, info, warn};
+use rand::{RngCore, SeedableRng, rngs::StdRng};
 use std::{
     sync::Arc,
     time::{Duration, Instant},
@@ -13,15 +13,20 @@ use thiserror::Error;
 use tokio::sync::mpsc;
 
 use freezeout_core::{
-    crypto::{PeerId, SigningKey},
-    message::{HandPayoff, Message, PlayerAction, PlayerUpdate, SignedMessage},
+    crypto::{HandHash, PeerId, SigningKey},
+    message::{
+        ActionOption, BlindLevel, HandPayoff, Message, PlayerAction, PlayerUpdate, SignedMessage,
+        Standing,
+    },
     poker::{Card, Chips, Deck, HandValue, PlayerCards, TableId},
 };
+use freezeout_eval::equity;
 
-use crate::db::Db;
+use crate::db::{Db, TxId, TxKind};
 
 use super::{
-    TableMessage,
+    NicknamePolicy, StateObserver, StraddlePosition, TableConfig, TableMessage,
+    hand_log::{BettingRound, HandLog},
     player::{Player, PlayersState},
 };
 
@@ -46,6 +51,8 @@ enum HandState {
     Showdown,
     /// The hand has ended.
     EndHand,
+    /// The table is paused between hands.
+    Paused,
     /// The game has ended with a winner.
     EndGame,
 }
@@ -69,6 +76,9 @@ pub enum TableJoinError {
     /// The player has already joined the table.
     #[error("player already joined")]
     AlreadyJoined,
+    /// Another player at the table has the same nickname.
+    #[error("nickname taken")]
+    NicknameTaken,
     /// An unknown error used by upper layers.
     #[error("unknown error")]
     Unknown,
@@ -79,37 +89,63 @@ pub enum TableJoinError {
 pub struct State {
     table_id: TableId,
     seats: usize,
+    config: TableConfig,
     sk: Arc<SigningKey>,
     db: Db,
     hand_state: HandState,
     small_blind: Chips,
     big_blind: Chips,
     hand_count: usize,
+    game_number: u64,
     players: PlayersState,
     deck: Deck,
     last_bet: Chips,
     min_raise: Chips,
+    raises: usize,
     pots: Vec<Pot>,
     board: Vec<Card>,
+    extra_boards: Vec<Vec<Card>>,
+    rabbit_hunted: bool,
     rng: StdRng,
     new_hand_timer: Option<Instant>,
     new_hand_timeout: Duration,
+    hand_log: HandLog,
+    betting_round: BettingRound,
+    hand_seed: u64,
+    paused: bool,
+    update_seq: u64,
+    game_players: Vec<PeerId>,
+    eliminated: Vec<Standing>,
+    spectators: Vec<(PeerId, mpsc::Sender<TableMessage>)>,
+    reservation: Option<(Vec<PeerId>, Instant)>,
 }
 
 impl State {
-    const ACTION_TIMEOUT: Duration = Duration::from_secs(15);
+    const RESERVATION_TIMEOUT: Duration = Duration::from_secs(30);
+    const SHOWDOWN_PAYOUT_PAUSE: Duration = Duration::from_millis(1_000);
+    const FOLD_PAYOUT_PAUSE: Duration = Duration::from_millis(250);
+    const MAX_CHAT_LEN: usize = 200;
+    const HANDS_PER_LEVEL: usize = 4;
+    const LAST_LEVEL: usize = 4;
     const START_GAME_SB: Chips = Chips::new(10_000);
     const START_GAME_BB: Chips = Chips::new(20_000);
 
     /// Create a new state.
-    pub fn new(table_id: TableId, seats: usize, sk: Arc<SigningKey>, db: Db) -> Self {
-        Self::with_rng(table_id, seats, sk, db, StdRng::from_os_rng())
+    pub fn new(
+        table_id: TableId,
+        seats: usize,
+        config: TableConfig,
+        sk: Arc<SigningKey>,
+        db: Db,
+    ) -> Self {
+        Self::with_rng(table_id, seats, config, sk, db, StdRng::from_os_rng())
     }
 
     /// Create a new state with user initialized randomness.
     fn with_rng(
         table_id: TableId,
         seats: usize,
+        config: TableConfig,
         sk: Arc<SigningKey>,
         db: Db,
         mut rng: StdRng,
@@ -117,33 +153,70 @@ impl State {
         Self {
             table_id,
             seats,
+            config,
             sk,
             db,
             hand_state: HandState::WaitForPlayers,
             small_blind: Self::START_GAME_SB,
             big_blind: Self::START_GAME_BB,
             hand_count: 0,
+            game_number: 0,
             players: PlayersState::default(),
             deck: Deck::shuffled(&mut rng),
             last_bet: Chips::ZERO,
             min_raise: Chips::ZERO,
+            raises: 0,
             pots: vec![Pot::default()],
             board: Vec::default(),
+            extra_boards: Vec::default(),
+            rabbit_hunted: false,
             rng,
             new_hand_timer: None,
             new_hand_timeout: Duration::default(),
+            hand_log: HandLog::default(),
+            betting_round: BettingRound::default(),
+            hand_seed: 0,
+            paused: false,
+            update_seq: 0,
+            game_players: Vec::default(),
+            eliminated: Vec::default(),
+            spectators: Vec::default(),
+            reservation: None,
         }
     }
 
     /// Checks if a player can join this table.
     pub fn player_can_join(&self) -> bool {
-        if !matches!(self.hand_state, HandState::WaitForPlayers) {
+        if !matches!(self.hand_state, HandState::WaitForPlayers) || self.is_reserved() {
             false
         } else {
             self.players.count() < self.seats
         }
     }
 
+    /// Reserves this table for a group of players if the table is empty.
+    ///
+    /// The reservation expires if the players don't join in time.
+    pub fn reserve(&mut self, players: Vec<PeerId>) -> bool {
+        let can_reserve = matches!(self.hand_state, HandState::WaitForPlayers)
+            && self.players.count() == 0
+            && players.len() <= self.seats
+            && !self.is_reserved();
+        if can_reserve {
+            info!("Table {} reserved for a rematch", self.table_id);
+            self.reservation = Some((players, Instant::now()));
+        }
+
+        can_reserve
+    }
+
+    /// Checks if this table is reserved for a group of players.
+    fn is_reserved(&self) -> bool {
+        self.reservation
+            .as_ref()
+            .is_some_and(|(_, t)| t.elapsed() < Self::RESERVATION_TIMEOUT)
+    }
+
     /// A player tries to join the table.
     pub async fn try_join(
         &mut self,
@@ -164,13 +237,20 @@ impl State {
             return Err(TableJoinError::AlreadyJoined);
         }
 
+        // Only players with a reservation can join a reserved table.
+        if self.is_reserved()
+            && let Some((players, _)) = &self.reservation
+            && !players.contains(player_id)
+        {
+            return Err(TableJoinError::TableFull);
+        }
+
+        let nickname = self.table_nickname(nickname)?;
+
         // Add new player to the table.
-        let join_player = Player::new(
-            player_id.clone(),
-            nickname.to_string(),
-            join_chips,
-            table_tx,
-        );
+        let mut join_player =
+            Player::new(player_id.clone(), nickname.clone(), join_chips, table_tx);
+        join_player.time_bank = self.config.time_bank;
 
         // Send a table joined confirmation to the player who joined.
         let msg = Message::TableJoined {
@@ -196,7 +276,7 @@ impl State {
         // player has not beed added to the table yet it won't get the broadcast.
         let msg = Message::PlayerJoined {
             player_id: player_id.clone(),
-            nickname: nickname.to_string(),
+            nickname,
             chips: join_player.chips,
         };
         self.broadcast_message(msg).await;
@@ -206,23 +286,63 @@ impl State {
 
         info!("Player {player_id} joined table {}", self.table_id);
 
-        // If all seats are full start the game.
-        if self.players.count() == self.seats {
+        // If all seats are full, or enough players joined, start the game.
+        let min_players = self.config.min_players.map_or(self.seats, |n| n.max(2));
+        if self.players.count() >= min_players.min(self.seats) {
             self.enter_start_game().await;
         }
 
         Ok(())
     }
 
+    /// Returns the nickname a joining player uses at this table.
+    fn table_nickname(&self, nickname: &str) -> Result<String, TableJoinError> {
+        let is_taken = |name: &str| self.players.iter().any(|p| p.nickname == name);
+        if !is_taken(nickname) {
+            return Ok(nickname.to_string());
+        }
+
+        match self.config.nickname_policy {
+            NicknamePolicy::Allow => Ok(nickname.to_string()),
+            NicknamePolicy::Reject => Err(TableJoinError::NicknameTaken),
+            NicknamePolicy::Suffix => Ok((2..)
+                .map(|n| format!("{nickname} ({n})"))
+                .find(|name| !is_taken(name))
+                .unwrap()),
+        }
+    }
+
     /// A player leaves the table.
     pub async fn leave(&mut self, player_id: &PeerId) {
+        if let Some(pos) = self.spectators.iter().position(|(id, _)| id == player_id) {
+            let (_, table_tx) = self.spectators.remove(pos);
+            let _ = table_tx.send(TableMessage::PlayerLeft).await;
+            return;
+        }
+
         let active_is_leaving = self.players.is_active(player_id);
         if let Some(player) = self.players.leave(player_id) {
+            if player.is_active {
+                self.hand_log
+                    .action(player_id, PlayerAction::Fold, player.bet);
+            }
+
             // Store the player bets into the pot.
             if let Some(pot) = self.pots.last_mut() {
                 pot.chips += player.bet;
             }
 
+            // A player leaving during a game finishes behind the players still
+            // at the table.
+            if self.game_players.contains(player_id) {
+                self.eliminated.push(Standing {
+                    player_id: player_id.clone(),
+                    nickname: player.nickname.clone(),
+                    chips: Chips::ZERO,
+                    place: 0,
+                });
+            }
+
             // Tell the other players this player has left.
             let msg = Message::PlayerLeft(player_id.clone());
             self.broadcast_message(msg).await;
@@ -235,66 +355,435 @@ impl State {
                 return;
             }
 
+            // The player leaving may have been the last to act in this round.
             if active_is_leaving {
-                self.request_action().await;
+                if self.is_round_complete() {
+                    self.next_round().await;
+                } else {
+                    self.request_action().await;
+                }
+            }
+        }
+    }
+
+    /// A player connection was lost.
+    ///
+    /// If the game has started the player seat is held for the reconnect grace
+    /// period and the other players are told how long the player has to reconnect,
+    /// the player actions time out as usual while disconnected.
+    ///
+    /// Returns true if the player seat is held.
+    pub async fn disconnect(&mut self, player_id: &PeerId) -> bool {
+        // Spectators don't hold a seat.
+        self.spectators.retain(|(id, _)| id != player_id);
+
+        let grace = self.config.reconnect_grace;
+        if grace.is_zero() || matches!(self.hand_state, HandState::WaitForPlayers) {
+            self.leave(player_id).await;
+            return false;
+        }
+
+        let player = self.players.iter_mut().find(|p| &p.player_id == player_id);
+        let Some(player) = player else {
+            return false;
+        };
+
+        player.disconnected = Some(Instant::now());
+
+        info!(
+            "Player {player_id} disconnected from table {}, holding seat for {}s",
+            self.table_id,
+            grace.as_secs()
+        );
+
+        let msg = Message::PlayerDisconnected {
+            player_id: player_id.clone(),
+            seconds: grace.as_secs() as u16,
+        };
+        self.broadcast_message(msg).await;
+
+        true
+    }
+
+    /// Suspends a player action timer while the player answers a server prompt.
+    ///
+    /// The time already used is kept and the player cannot time out until the
+    /// timer is resumed.
+    pub async fn suspend_timer(&mut self, player_id: &PeerId) {
+        let player = self.players.iter_mut().find(|p| &p.player_id == player_id);
+        if let Some(player) = player
+            && player.action_timer.is_some()
+        {
+            player.suspend_action_timer();
+            self.broadcast_game_update().await;
+        }
+    }
+
+    /// Resumes a player action timer from the time used before the prompt.
+    pub async fn resume_timer(&mut self, player_id: &PeerId) {
+        let player = self.players.iter_mut().find(|p| &p.player_id == player_id);
+        if let Some(player) = player
+            && player.suspended_timer.is_some()
+        {
+            player.resume_action_timer();
+            self.broadcast_game_update().await;
+        }
+    }
+
+    /// A disconnected player reconnects with a new connection.
+    ///
+    /// Returns false if the player seat is not held at this table.
+    pub async fn reconnect(
+        &mut self,
+        player_id: &PeerId,
+        table_tx: mpsc::Sender<TableMessage>,
+    ) -> bool {
+        let Some(player) = self
+            .players
+            .iter_mut()
+            .find(|p| &p.player_id == player_id && p.disconnected.is_some())
+        else {
+            return false;
+        };
+
+        player.disconnected = None;
+        player.table_tx = table_tx;
+
+        // Send the table state to the new player connection.
+        let player = self
+            .players
+            .iter()
+            .find(|p| &p.player_id == player_id)
+            .unwrap();
+
+        let mut msgs = vec![Message::TableJoined {
+            table_id: self.table_id,
+            chips: player.chips,
+            seats: self.seats as u8,
+        }];
+
+        for other in self.players.iter().filter(|p| &p.player_id != player_id) {
+            msgs.push(Message::PlayerJoined {
+                player_id: other.player_id.clone(),
+                nickname: other.nickname.clone(),
+                chips: other.chips,
+            });
+        }
+
+        let seats = self.players.iter().map(|p| p.player_id.clone()).collect();
+        msgs.push(Message::StartGame(seats));
+
+        if let PlayerCards::Cards(c1, c2) = player.hole_cards
+            && player.is_active
+        {
+            msgs.push(Message::StartHand {
+                hand_number: self.hand_count as u64,
+            });
+            msgs.push(Message::DealCards(c1, c2));
+        }
+
+        for msg in msgs {
+            player.send_message(SignedMessage::new(&self.sk, msg)).await;
+        }
+
+        info!("Player {player_id} reconnected to table {}", self.table_id);
+
+        self.broadcast_message(Message::PlayerReconnected(player_id.clone()))
+            .await;
+        self.broadcast_game_update().await;
+
+        // Request the action again if the player was asked to act.
+        let is_waiting_action = self
+            .players
+            .active_player()
+            .is_some_and(|p| &p.player_id == player_id && p.action_timer.is_some());
+        if is_waiting_action {
+            self.request_action().await;
+        }
+
+        true
+    }
+
+    /// Refunds the chips of the players at the table when the server shuts down.
+    ///
+    /// If a hand is in progress the hand is cancelled and players get back the
+    /// chips they had at the start of the hand.
+    pub async fn refund_players(&mut self) {
+        let in_hand = matches!(
+            self.hand_state,
+            HandState::PreflopBetting
+                | HandState::FlopBetting
+                | HandState::TurnBetting
+                | HandState::RiverBetting
+                | HandState::Showdown
+        );
+
+        for player in self.players.iter() {
+            let chips = if in_hand {
+                self.hand_log
+                    .starting_chips(&player.player_id)
+                    .unwrap_or(player.chips + player.bet)
+            } else {
+                player.chips
+            };
+
+            let res = self
+                .db
+                .pay_to_player(
+                    self.tx_id(TxKind::Refund, &player.player_id),
+                    player.player_id.clone(),
+                    chips,
+                )
+                .await;
+            if let Err(e) = res {
+                error!("Db refund for player {} failed {e}", player.player_id);
             }
         }
+
+        self.players.clear();
+        self.game_number += 1;
+        self.hand_state = HandState::WaitForPlayers;
     }
 
     /// Handle a message from a player.
     pub async fn message(&mut self, msg: SignedMessage) {
-        if let Message::ActionResponse { action, amount } = msg.message() {
-            if let Some(player) = self.players.active_player() {
-                // Only process responses coming from active player.
-                if player.player_id == msg.sender() {
-                    player.action = *action;
-                    player.action_timer = None;
-
-                    match action {
-                        PlayerAction::Fold => {
-                            player.fold();
-                        }
-                        PlayerAction::Call => {
-                            player.bet(*action, self.last_bet);
-                        }
-                        PlayerAction::Check => {}
-                        PlayerAction::Bet | PlayerAction::Raise => {
-                            let amount = *amount.min(&(player.bet + player.chips));
-                            self.min_raise = (amount - self.last_bet).max(self.min_raise);
-                            self.last_bet = amount.max(self.last_bet);
-                            player.bet(*action, amount);
-                        }
-                        _ => {}
+        match msg.message() {
+            Message::ActionResponse { action, amount } => {
+                self.action_response(&msg.sender(), *action, *amount).await;
+            }
+            Message::ResyncRequest => {
+                self.resync(&msg.sender()).await;
+            }
+            Message::SetAutoMuck(auto_muck) => {
+                let sender = msg.sender();
+                if let Some(player) = self.players.iter_mut().find(|p| p.player_id == sender) {
+                    player.auto_muck = *auto_muck;
+                }
+            }
+            Message::SpectatorChat { text, .. } => {
+                self.spectator_chat(&msg.sender(), text).await;
+            }
+            Message::RabbitHunt if self.config.rabbit_hunt => {
+                self.rabbit_hunt(&msg.sender()).await;
+            }
+            _ => {}
+        }
+    }
+
+    /// Pauses the table at the end of the current hand or resumes it, returns
+    /// false if the table was already paused or running.
+    pub async fn set_paused(&mut self, paused: bool) -> bool {
+        if self.paused == paused {
+            return false;
+        }
+
+        self.paused = paused;
+        if paused {
+            info!("Table {} paused", self.table_id);
+        } else {
+            info!("Table {} resumed", self.table_id);
+
+            // If the table was waiting to be resumed start a new hand.
+            if matches!(self.hand_state, HandState::Paused) {
+                self.enter_start_hand().await;
+            }
+        }
+
+        true
+    }
+
+    /// A player watches the game, returns false if there is no game in progress
+    /// or the player is seated at this table.
+    pub async fn spectate(
+        &mut self,
+        player_id: &PeerId,
+        table_tx: mpsc::Sender<TableMessage>,
+    ) -> bool {
+        let is_seated = self.players.iter().any(|p| &p.player_id == player_id);
+        let is_spectating = self.spectators.iter().any(|(id, _)| id == player_id);
+        if self.game_players.is_empty() || is_seated || is_spectating {
+            return false;
+        }
+
+        let smsg = SignedMessage::new(&self.sk, self.game_update());
+        let _ = table_tx.send(TableMessage::Send(smsg)).await;
+        self.spectators.push((player_id.clone(), table_tx));
+
+        info!("Player {player_id} is spectating table {}", self.table_id);
+        true
+    }
+
+    /// Relays a spectator chat message to the other spectators.
+    ///
+    /// Seated players never receive spectators chat and cannot send it.
+    async fn spectator_chat(&self, sender: &PeerId, text: &str) {
+        if !self.spectators.iter().any(|(id, _)| id == sender) {
+            return;
+        }
+
+        let text = text.chars().take(Self::MAX_CHAT_LEN).collect::<String>();
+        let msg = Message::SpectatorChat {
+            player_id: sender.clone(),
+            text,
+        };
+
+        let smsg = SignedMessage::new(&self.sk, msg);
+        for (_, table_tx) in self.spectators.iter().filter(|(id, _)| id != sender) {
+            let _ = table_tx.send(TableMessage::Send(smsg.clone())).await;
+        }
+    }
+
+    /// Deals the rest of the board after a hand that ended before the river.
+    ///
+    /// The cards come from the hand deck and are only shown to the table, the
+    /// hand has already been paid when they are dealt. The board can be hunted
+    /// once per hand by any player at the table.
+    async fn rabbit_hunt(&mut self, sender: &PeerId) {
+        let is_seated = self.players.iter().any(|p| &p.player_id == sender);
+        if !matches!(self.hand_state, HandState::EndHand)
+            || !is_seated
+            || self.rabbit_hunted
+            || self.board.len() >= 5
+        {
+            return;
+        }
+
+        self.rabbit_hunted = true;
+
+        let mut board = self.board.clone();
+        board.extend(self.deck.deal_n(5 - board.len()));
+        self.broadcast_message(Message::RabbitHuntBoard { board })
+            .await;
+    }
+
+    /// Handle an action response from a player.
+    async fn action_response(&mut self, sender: &PeerId, action: PlayerAction, amount: Chips) {
+        let options = self.action_options();
+        let timeout = self.action_timeout();
+        if let Some(player) = self.players.active_player()
+            // Only process responses coming from active player.
+            && &player.player_id == sender
+        {
+            // Ignore illegal actions, the player can still respond before the
+            // action timer expires, and clamp amounts to the legal range.
+            let Some(option) = options.iter().find(|o| o.action == action) else {
+                warn!("Illegal action {action:?} from player {sender}");
+                return;
+            };
+            let amount = amount.clamp(option.min, option.max);
+
+            player.action = action;
+            player.stop_action_timer(timeout);
+            player.timeouts = 0;
+
+            match action {
+                PlayerAction::Fold => {
+                    player.fold();
+                }
+                PlayerAction::Call => {
+                    player.bet(action, self.last_bet);
+                }
+                PlayerAction::Check => {}
+                PlayerAction::Bet | PlayerAction::Raise => {
+                    // Opening bets are not counted as raises.
+                    if self.last_bet > Chips::ZERO && amount > self.last_bet {
+                        self.raises += 1;
                     }
 
-                    self.action_update().await;
+                    self.min_raise = (amount - self.last_bet).max(self.min_raise);
+                    self.last_bet = amount.max(self.last_bet);
+                    player.bet(action, amount);
                 }
+                _ => {}
             }
+
+            self.hand_log.action(&player.player_id, action, player.bet);
+            self.betting_round
+                .push(&player.player_id, action, player.bet);
+            self.action_update().await;
         }
     }
 
     pub async fn tick(&mut self) {
         // Check if there is any player with an active timer.
         if self.players.iter().any(|p| p.action_timer.is_some()) {
+            let timeout = self.action_timeout();
             let player = self
                 .players
                 .iter_mut()
                 .find(|p| p.action_timer.is_some())
                 .unwrap();
 
-            // If timer has expired fold otherwise broadcast timer update.
-            if player.action_timer.unwrap().elapsed() > Self::ACTION_TIMEOUT {
+            // If timer and time bank have expired fold otherwise broadcast timer update.
+            if player.action_timer.unwrap().elapsed() > timeout + player.time_bank {
+                player.stop_action_timer(timeout);
                 player.fold();
-                self.action_update().await;
+                player.timeouts += 1;
+                self.hand_log
+                    .action(&player.player_id, PlayerAction::Fold, player.bet);
+                self.betting_round
+                    .push(&player.player_id, PlayerAction::Fold, player.bet);
+
+                // Remove players that keep letting their action time out.
+                if self
+                    .config
+                    .max_timeouts
+                    .is_some_and(|max| player.timeouts >= max)
+                {
+                    let player_id = player.player_id.clone();
+                    info!(
+                        "Player {player_id} timed out {} times on table {}",
+                        player.timeouts, self.table_id
+                    );
+                    self.leave(&player_id).await;
+                } else {
+                    self.action_update().await;
+                }
             } else {
                 self.broadcast_game_update().await;
             }
         }
 
+        // Remove disconnected players that have not reconnected in time.
+        let grace = self.config.reconnect_grace;
+        let expired = self
+            .players
+            .iter()
+            .filter(|p| p.disconnected.is_some_and(|t| t.elapsed() >= grace))
+            .map(|p| p.player_id.clone())
+            .collect::<Vec<_>>();
+        for player_id in expired {
+            info!(
+                "Player {player_id} didn't reconnect to table {}",
+                self.table_id
+            );
+            self.leave(&player_id).await;
+        }
+
+        // Update the reconnect countdown for the players still disconnected.
+        let disconnected = self
+            .players
+            .iter()
+            .filter_map(|p| {
+                p.disconnected.map(|t| Message::PlayerDisconnected {
+                    player_id: p.player_id.clone(),
+                    seconds: grace.saturating_sub(t.elapsed()).as_secs_f32() as u16,
+                })
+            })
+            .collect::<Vec<_>>();
+        for msg in disconnected {
+            self.broadcast_message(msg).await;
+        }
+
         // Check if it is time to start a new hand.
-        if let Some(timer) = &self.new_hand_timer {
-            if timer.elapsed() > self.new_hand_timeout {
-                self.new_hand_timer = None;
+        if let Some(timer) = &self.new_hand_timer
+            && timer.elapsed() > self.new_hand_timeout
+        {
+            self.new_hand_timer = None;
+
+            if self.paused {
+                self.enter_paused().await;
+            } else {
                 self.enter_start_hand().await;
             }
         }
@@ -304,6 +793,11 @@ impl State {
         self.players.activate_next_player();
         self.broadcast_game_update().await;
 
+        // Give players time to see the last action.
+        if !self.config.min_action_pace.is_zero() {
+            self.broadcast_throttle(self.config.min_action_pace).await;
+        }
+
         if self.is_round_complete() {
             self.next_round().await;
         } else {
@@ -314,11 +808,21 @@ impl State {
     async fn enter_start_game(&mut self) {
         self.hand_state = HandState::StartGame;
 
-        // Shuffle seats before starting the game.
-        self.players.shuffle_seats(&mut self.rng);
+        // Shuffle seats before starting the game unless seats are fixed.
+        if !self.config.fixed_seats {
+            self.players.shuffle_seats(&mut self.rng);
+        }
+
+        self.reservation = None;
+        self.eliminated.clear();
 
         // Tell players to update their seats order.
-        let seats = self.players.iter().map(|p| p.player_id.clone()).collect();
+        let seats = self
+            .players
+            .iter()
+            .map(|p| p.player_id.clone())
+            .collect::<Vec<_>>();
+        self.game_players = seats.clone();
         self.broadcast_message(Message::StartGame(seats)).await;
 
         self.enter_start_hand().await;
@@ -337,35 +841,87 @@ impl State {
         }
 
         self.update_blinds();
+        self.hand_log.start(self.hand_count);
+        self.betting_round.reset();
+
+        // Reset pots, the antes go to the pots before the blinds are posted.
+        self.pots = vec![Pot::default()];
+        self.post_antes();
 
         // Pay small and big blind.
         if let Some(player) = self.players.active_player() {
             player.bet(PlayerAction::SmallBlind, self.small_blind);
+            self.hand_log
+                .action(&player.player_id, PlayerAction::SmallBlind, player.bet);
+            self.betting_round
+                .push(&player.player_id, PlayerAction::SmallBlind, player.bet);
         };
 
         self.players.activate_next_player();
 
         if let Some(player) = self.players.active_player() {
             player.bet(PlayerAction::BigBlind, self.big_blind);
+            self.hand_log
+                .action(&player.player_id, PlayerAction::BigBlind, player.bet);
+            self.betting_round
+                .push(&player.player_id, PlayerAction::BigBlind, player.bet);
         };
 
         self.last_bet = self.big_blind;
         self.min_raise = self.big_blind;
+        self.raises = 0;
+
+        // The straddling player becomes the active player so that the action starts
+        // from the next player and the straddle is the last to act preflop.
+        if let Some(position) = self.config.straddle
+            && self.players.count_active() > 2
+        {
+            match position {
+                StraddlePosition::Utg => self.players.activate_next_player(),
+                StraddlePosition::Button => self.players.activate_button(),
+            }
+
+            let straddle = self.big_blind * 2;
+            if let Some(player) = self.players.active_player() {
+                player.bet(PlayerAction::Straddle, straddle);
+                self.hand_log
+                    .action(&player.player_id, PlayerAction::Straddle, player.bet);
+                self.betting_round
+                    .push(&player.player_id, PlayerAction::Straddle, player.bet);
+            };
 
-        // Create a new deck.
-        self.deck = Deck::shuffled(&mut self.rng);
+            self.last_bet = straddle;
+            self.min_raise = straddle;
+        }
 
-        // Clear board.
-        self.board.clear();
+        // Create a new deck from a per hand seed, the seed and button position are
+        // logged so that a hand can be reproduced when investigating a report.
+        self.hand_seed = self.rng.next_u64();
+        self.deck = Deck::shuffled(&mut StdRng::seed_from_u64(self.hand_seed));
+        self.hand_log.seed(self.hand_seed);
 
-        // Reset pots.
-        self.pots = vec![Pot::default()];
+        let button = self.players.iter().position(|p| p.has_button);
+        debug!(
+            "Table {} hand {} seed {:016x} button {button:?}",
+            self.table_id, self.hand_count, self.hand_seed
+        );
+
+        // Clear boards.
+        self.board.clear();
+        self.rabbit_hunted = false;
+        self.extra_boards = vec![Vec::default(); self.config.boards.saturating_sub(1)];
 
         // Tell clients to prepare for a new hand.
-        self.broadcast_message(Message::StartHand).await;
+        self.broadcast_message(Message::StartHand {
+            hand_number: self.hand_count as u64,
+        })
+        .await;
+        self.observe(|o| o.on_hand_start(self.table_id, self.hand_count));
 
         // Deal cards to each player.
         for player in self.players.iter_mut() {
+            player.equity = None;
+
             if player.is_active {
                 player.public_cards = PlayerCards::Covered;
 
@@ -376,6 +932,12 @@ impl State {
                 } else {
                     PlayerCards::Cards(c2, c1)
                 };
+
+                self.hand_log.deal(
+                    &player.player_id,
+                    player.chips + player.bet + player.ante,
+                    player.hole_cards,
+                );
             } else {
                 player.public_cards = PlayerCards::None;
                 player.hole_cards = PlayerCards::None;
@@ -403,43 +965,70 @@ impl State {
     }
 
     async fn enter_deal_flop(&mut self) {
-        for _ in 1..=3 {
-            self.board.push(self.deck.deal());
-        }
+        self.deal_boards(3);
 
         self.hand_state = HandState::FlopBetting;
+        self.observe(|o| o.on_street(self.table_id, &self.board));
         self.start_round().await;
     }
 
     async fn enter_deal_turn(&mut self) {
-        self.board.push(self.deck.deal());
+        self.deal_boards(1);
 
         self.hand_state = HandState::TurnBetting;
+        self.observe(|o| o.on_street(self.table_id, &self.board));
         self.start_round().await;
     }
 
     async fn enter_deal_river(&mut self) {
-        self.board.push(self.deck.deal());
+        self.deal_boards(1);
 
         self.hand_state = HandState::RiverBetting;
+        self.observe(|o| o.on_street(self.table_id, &self.board));
         self.start_round().await;
     }
 
+    /// Deals the given number of cards to each board.
+    fn deal_boards(&mut self, n: usize) {
+        self.board.extend(self.deck.deal_n(n));
+
+        for board in &mut self.extra_boards {
+            board.extend(self.deck.deal_n(n));
+        }
+    }
+
     async fn enter_showdown(&mut self) {
         self.hand_state = HandState::Showdown;
 
+        // Players with auto muck don't show hands that lose all the pots unless
+        // all hands must be revealed.
+        self.update_pots();
+        let mucked = if self.config.mandatory_reveal {
+            Vec::default()
+        } else {
+            self.players
+                .iter()
+                .filter(|p| p.is_active && p.auto_muck)
+                .filter(|p| !matches!(p.public_cards, PlayerCards::Cards(..)))
+                .filter(|p| self.is_losing_hand(&p.player_id))
+                .map(|p| p.player_id.clone())
+                .collect::<Vec<_>>()
+        };
+
         for player in self.players.iter_mut() {
             player.action = PlayerAction::None;
-            if player.is_active {
+            if player.is_active && !mucked.contains(&player.player_id) {
                 player.public_cards = player.hole_cards;
             }
         }
 
+        self.observe(|o| o.on_showdown(self.table_id, &self.board));
         self.enter_end_hand().await;
     }
 
     async fn enter_end_hand(&mut self) {
-        self.new_hand_timeout = if matches!(self.hand_state, HandState::Showdown) {
+        let is_showdown = matches!(self.hand_state, HandState::Showdown);
+        self.new_hand_timeout = if is_showdown {
             // If coming from a showdown give players more time to see the winning
             // hand and chips.
             Duration::from_millis(7_000)
@@ -452,21 +1041,33 @@ impl State {
         self.update_pots();
         self.broadcast_game_update().await;
 
-        // Give time to the UI to look at the updated pot and board.
-        self.broadcast_throttle(Duration::from_millis(1_000)).await;
+        // Give time to the UI to look at the updated pot and board, there is
+        // not much to look at if everyone folded to one player.
+        self.broadcast_throttle(if is_showdown {
+            Self::SHOWDOWN_PAYOUT_PAUSE
+        } else {
+            Self::FOLD_PAYOUT_PAUSE
+        })
+        .await;
 
+        let rake = self.take_rake().await;
         let winners = self.pay_bets();
+        self.observe(|o| o.on_payout(self.table_id, &winners));
+        let hash = self.save_hand_hash(&winners, rake).await;
 
         // Update players and broadcast update to all players.
         self.players.end_hand();
         self.broadcast_message(Message::EndHand {
             payoffs: winners,
             board: self.board.clone(),
+            extra_boards: self.extra_boards.clone(),
             cards: self
                 .players
                 .iter()
                 .map(|p| (p.player_id.clone(), p.public_cards))
                 .collect(),
+            rake,
+            hash,
         })
         .await;
 
@@ -474,11 +1075,29 @@ impl State {
         if self.players.count_with_chips() < 2 {
             self.enter_end_game().await;
         } else {
+            // Players that run out of chips in the same hand finish in order of
+            // their chips at the start of the hand.
+            let mut busted = self
+                .players
+                .iter()
+                .filter(|p| p.chips == Chips::ZERO)
+                .map(|p| {
+                    (
+                        self.hand_log.starting_chips(&p.player_id),
+                        Self::standing(p),
+                    )
+                })
+                .collect::<Vec<_>>();
+            busted.sort_by_key(|(chips, _)| *chips);
+            self.eliminated.extend(busted.into_iter().map(|(_, s)| s));
+
             // All players that run out of chips must leave the table before the
             // start of a new hand.
             for player in self.players.iter() {
                 if player.chips == Chips::ZERO {
                     // Notify the client that this player has left the table.
+                    let msg = TableMessage::GameEnded(self.game_players.clone());
+                    let _ = player.table_tx.send(msg).await;
                     let _ = player.table_tx.send(TableMessage::PlayerLeft).await;
 
                     let msg = Message::PlayerLeft(player.player_id.clone());
@@ -491,37 +1110,125 @@ impl State {
         }
     }
 
+    async fn enter_paused(&mut self) {
+        self.hand_state = HandState::Paused;
+
+        // Tell players the next hand starts when the table is resumed.
+        self.broadcast_message(Message::TablePaused).await;
+    }
+
     async fn enter_end_game(&mut self) {
         // Give time to the UI to look at winning results before ending the game.
         self.broadcast_throttle(Duration::from_millis(4500)).await;
 
         self.hand_state = HandState::EndGame;
 
+        // Tell players the final standings if the game has been played.
+        if self.hand_count > 0 {
+            let standings = self.standings();
+            self.broadcast_message(Message::GameOver { standings })
+                .await;
+        }
+
         for player in self.players.iter() {
             // Pay the winning player.
             let res = self
                 .db
-                .pay_to_player(player.player_id.clone(), player.chips)
+                .pay_to_player(
+                    self.tx_id(TxKind::CashOut, &player.player_id),
+                    player.player_id.clone(),
+                    player.chips,
+                )
                 .await;
             if let Err(e) = res {
                 error!("Db players update failed {e}");
             }
 
             // Notify the client that this player has left the table.
+            let msg = TableMessage::GameEnded(self.game_players.clone());
+            let _ = player.table_tx.send(msg).await;
             let _ = player.table_tx.send(TableMessage::PlayerLeft).await;
         }
 
         self.players.clear();
+        self.game_players.clear();
+
+        // Spectators leave when the game ends.
+        for (_, table_tx) in self.spectators.drain(..) {
+            let _ = table_tx.send(TableMessage::PlayerLeft).await;
+        }
 
         // Reset hand count for next game.
         self.hand_count = 0;
+        self.game_number += 1;
 
         // Wait for players to join.
         self.hand_state = HandState::WaitForPlayers;
     }
 
+    /// Returns the final standings with the players at the table ranked by chips,
+    /// followed by the eliminated players from last to first eliminated.
+    fn standings(&mut self) -> Vec<Standing> {
+        let mut remaining = self
+            .players
+            .iter()
+            .map(|p| (p.chips, self.hand_log.starting_chips(&p.player_id), p))
+            .collect::<Vec<_>>();
+        remaining.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+
+        let eliminated = std::mem::take(&mut self.eliminated);
+        remaining
+            .into_iter()
+            .map(|(_, _, p)| Self::standing(p))
+            .chain(eliminated.into_iter().rev())
+            .enumerate()
+            .map(|(idx, standing)| Standing {
+                place: idx + 1,
+                ..standing
+            })
+            .collect()
+    }
+
+    /// Returns the standing of a player without a place.
+    fn standing(player: &Player) -> Standing {
+        Standing {
+            player_id: player.player_id.clone(),
+            nickname: player.nickname.clone(),
+            chips: player.chips,
+            place: 0,
+        }
+    }
+
+    /// Computes the signed hand hash and stores it to the database.
+    async fn save_hand_hash(&self, payoffs: &[HandPayoff], rake: Chips) -> HandHash {
+        // Extra boards cards follow the first board cards.
+        let board = self
+            .board
+            .iter()
+            .chain(self.extra_boards.iter().flatten())
+            .copied()
+            .collect::<Vec<_>>();
+        let hash = self.hand_log.hash(self.table_id, &board, payoffs, rake);
+
+        // A hand may end before the game started if players leave while waiting
+        // for other players, only store hands that have been played.
+        if self.hand_count > 0 {
+            let signature = self.sk.sign(&hash);
+            let res = self
+                .db
+                .save_hand(self.table_id, self.hand_log.hand(), hash, signature)
+                .await;
+            if let Err(e) = res {
+                error!("Db hand update failed {e}");
+            }
+        }
+
+        hash
+    }
+
     fn update_blinds(&mut self) {
-        let multiplier = (1 << (self.hand_count / 4).min(4)) as u32;
+        let level = (self.hand_count / Self::HANDS_PER_LEVEL).min(Self::LAST_LEVEL);
+        let multiplier = (1 << level) as u32;
         if multiplier < 16 {
             self.small_blind = Self::START_GAME_SB * multiplier;
             self.big_blind = Self::START_GAME_BB * multiplier;
@@ -534,13 +1241,85 @@ impl State {
         self.hand_count += 1;
     }
 
-    fn pay_bets(&mut self) -> Vec<HandPayoff> {
-        let mut payoffs = Vec::<HandPayoff>::new();
+    /// Takes the rake from the pots and pays it to the rake account.
+    async fn take_rake(&mut self) -> Chips {
+        let Some(config) = &self.config.rake else {
+            return Chips::ZERO;
+        };
 
-        match self.players.count_active() {
-            1 => {
-                // If one player left gets all the chips.
-                if let Some(player) = self.players.active_player() {
+        // Only take rake from pots contested by more than one player.
+        let mut rake = Chips::ZERO;
+        for pot in self.pots.iter_mut().filter(|p| p.players.len() > 1) {
+            let pot_rake = config.rake(pot.chips, rake);
+            pot.chips -= pot_rake;
+            rake += pot_rake;
+        }
+
+        if rake > Chips::ZERO {
+            let account = config
+                .account
+                .clone()
+                .unwrap_or_else(|| self.sk.verifying_key().peer_id());
+            let tx_id = self.tx_id(TxKind::Rake, &account);
+            if let Err(e) = self.db.pay_rake(tx_id, account, rake).await {
+                error!("Db rake update failed {e}");
+            }
+        }
+
+        rake
+    }
+
+    /// Returns the id of a chips transaction for a player in the current hand,
+    /// players are paid out once per game as they all leave when it ends.
+    fn tx_id(&self, kind: TxKind, player_id: &PeerId) -> TxId {
+        let scope = format!("{}:{}:{}", self.table_id, self.game_number, self.hand_count);
+        self.db.tx_id(kind, &scope, player_id)
+    }
+
+    /// Checks if a player hand doesn't win or tie any of the pots it is in on
+    /// any board.
+    fn is_losing_hand(&self, player_id: &PeerId) -> bool {
+        let boards = std::iter::once(&self.board)
+            .chain(&self.extra_boards)
+            .collect::<Vec<_>>();
+
+        let hand_value = |player: &Player, board: &[Card]| match player.hole_cards {
+            PlayerCards::None | PlayerCards::Covered => None,
+            PlayerCards::Cards(c1, c2) => {
+                let mut cards = vec![c1, c2];
+                cards.extend_from_slice(board);
+                Some(HandValue::eval(&cards))
+            }
+        };
+
+        let Some(player) = self.players.iter().find(|p| &p.player_id == player_id) else {
+            return false;
+        };
+
+        for pot in self.pots.iter().filter(|p| p.players.contains(player_id)) {
+            for board in &boards {
+                let best = self
+                    .players
+                    .iter()
+                    .filter(|p| p.is_active && pot.players.contains(&p.player_id))
+                    .filter_map(|p| hand_value(p, board))
+                    .max();
+                if hand_value(player, board) >= best {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    fn pay_bets(&mut self) -> Vec<HandPayoff> {
+        let mut payoffs = Vec::<HandPayoff>::new();
+
+        match self.players.count_active() {
+            1 => {
+                // If one player left gets all the chips.
+                if let Some(player) = self.players.active_player() {
                     for pot in self.pots.drain(..) {
                         player.chips += pot.chips;
 
@@ -555,71 +1334,89 @@ impl State {
                                 chips: pot.chips,
                                 cards: Vec::default(),
                                 rank: String::default(),
+                                kickers: 0,
                             });
                         }
                     }
                 }
             }
             n if n > 1 => {
-                // With more than 1 active player we need to compare hands for each pot
-                for pot in self.pots.drain(..) {
-                    // Evaluate all active players hands.
-                    let mut hands = self
-                        .players
-                        .iter_mut()
-                        .filter(|p| p.is_active && pot.players.contains(&p.player_id))
-                        .filter_map(|p| match p.hole_cards {
-                            PlayerCards::None | PlayerCards::Covered => None,
-                            PlayerCards::Cards(c1, c2) => Some((p, c1, c2)),
-                        })
-                        .map(|(p, c1, c2)| {
-                            let mut cards = vec![c1, c2];
-                            cards.extend_from_slice(&self.board);
-                            let (v, bh) = HandValue::eval_with_best_hand(&cards);
-                            (p, v, bh)
-                        })
-                        .collect::<Vec<_>>();
-
-                    // This may happen when the last pot is empty.
-                    if hands.is_empty() {
-                        continue;
-                    }
+                let boards = std::iter::once(&self.board)
+                    .chain(&self.extra_boards)
+                    .collect::<Vec<_>>();
 
-                    // Sort descending order, winners first.
-                    hands.sort_by(|p1, p2| p2.1.cmp(&p1.1));
-
-                    // Count hands with the same value.
-                    let winners_count = hands.iter().filter(|(_, v, _)| v == &hands[0].1).count();
-                    let win_payoff = pot.chips / winners_count as u32;
-                    let win_remainder = pot.chips % winners_count as u32;
+                // With more than 1 active player we need to compare hands for each
+                // pot, with multiple boards each board wins an equal share of the pot.
+                for pot in self.pots.drain(..) {
+                    let share = pot.chips / boards.len() as u32;
+                    let share_remainder = pot.chips % boards.len() as u32;
 
-                    for (idx, (player, v, bh)) in hands.iter_mut().take(winners_count).enumerate() {
-                        // Give remaineder to first player.
-                        let player_payoff = if idx == 0 {
-                            win_payoff + win_remainder
+                    for (board_idx, board) in boards.iter().enumerate() {
+                        // Give remainder to the first board.
+                        let board_chips = if board_idx == 0 {
+                            share + share_remainder
                         } else {
-                            win_payoff
+                            share
                         };
 
-                        player.chips += player_payoff;
+                        // Evaluate all active players hands.
+                        let mut hands = self
+                            .players
+                            .iter_mut()
+                            .filter(|p| p.is_active && pot.players.contains(&p.player_id))
+                            .filter_map(|p| match p.hole_cards {
+                                PlayerCards::None | PlayerCards::Covered => None,
+                                PlayerCards::Cards(c1, c2) => Some((p, c1, c2)),
+                            })
+                            .map(|(p, c1, c2)| {
+                                let mut cards = vec![c1, c2];
+                                cards.extend_from_slice(board);
+                                let (v, bh) = HandValue::eval_best_hand_detailed(&cards);
+                                (p, v, bh)
+                            })
+                            .collect::<Vec<_>>();
+
+                        // This may happen when the last pot is empty.
+                        if hands.is_empty() {
+                            continue;
+                        }
+
+                        // Sort descending order, winners first.
+                        hands.sort_by_key(|h| std::cmp::Reverse(h.1));
 
-                        // Sort by rank for the UI.
-                        let mut cards = bh.to_vec();
-                        cards.sort_by_key(|c| c.rank());
+                        // Count hands with the same value.
+                        let winners_count =
+                            hands.iter().filter(|(_, v, _)| v == &hands[0].1).count();
+                        let win_payoff = board_chips / winners_count as u32;
+                        let win_remainder = board_chips % winners_count as u32;
 
-                        // If a player has already a payoff add chips to that one.
-                        if let Some(payoff) = payoffs
-                            .iter_mut()
-                            .find(|po| po.player_id == player.player_id)
+                        for (idx, (player, v, bh)) in
+                            hands.iter_mut().take(winners_count).enumerate()
                         {
-                            payoff.chips += player_payoff;
-                        } else {
-                            payoffs.push(HandPayoff {
-                                player_id: player.player_id.clone(),
-                                chips: player_payoff,
-                                cards,
-                                rank: v.rank().to_string(),
-                            });
+                            // Give remaineder to first player.
+                            let player_payoff = if idx == 0 {
+                                win_payoff + win_remainder
+                            } else {
+                                win_payoff
+                            };
+
+                            player.chips += player_payoff;
+
+                            // If a player has already a payoff add chips to that one.
+                            if let Some(payoff) = payoffs
+                                .iter_mut()
+                                .find(|po| po.player_id == player.player_id)
+                            {
+                                payoff.chips += player_payoff;
+                            } else {
+                                payoffs.push(HandPayoff {
+                                    player_id: player.player_id.clone(),
+                                    chips: player_payoff,
+                                    cards: [bh.made.as_slice(), &bh.kickers].concat(),
+                                    rank: v.to_string(),
+                                    kickers: bh.kickers.len() as u8,
+                                });
+                            }
                         }
                     }
                 }
@@ -653,7 +1450,11 @@ impl State {
             if player.is_active {
                 // If a player didn't act the round is not complete.
                 match player.action {
-                    PlayerAction::None | PlayerAction::SmallBlind | PlayerAction::BigBlind
+                    PlayerAction::None
+                    | PlayerAction::SmallBlind
+                    | PlayerAction::BigBlind
+                    | PlayerAction::Straddle
+                    | PlayerAction::Ante
                         if player.chips > Chips::ZERO =>
                     {
                         return false;
@@ -672,6 +1473,11 @@ impl State {
             return;
         }
 
+        // Reveal the all in players cards before running out the board.
+        if self.update_all_in_equities() {
+            self.broadcast_game_update().await;
+        }
+
         while self.is_round_complete() {
             match self.hand_state {
                 HandState::PreflopBetting => self.enter_deal_flop().await,
@@ -689,6 +1495,14 @@ impl State {
     async fn start_round(&mut self) {
         self.update_pots();
 
+        debug!(
+            "Table {} hand {} round actions {:?}",
+            self.table_id,
+            self.hand_count,
+            self.betting_round.actions()
+        );
+        self.betting_round.reset();
+
         // Give some time to watch last action and pots.
         self.broadcast_throttle(Duration::from_millis(1000)).await;
 
@@ -699,13 +1513,97 @@ impl State {
 
         self.last_bet = Chips::ZERO;
         self.min_raise = self.big_blind;
+        self.raises = 0;
 
         self.players.start_round();
+        self.update_all_in_equities();
 
         self.broadcast_game_update().await;
+        self.send_spectator_hands().await;
         self.request_action().await;
     }
 
+    /// Reveals the cards of the players in the hand and computes their equity if
+    /// there is no betting left because players are all in.
+    ///
+    /// Returns true if the equities have been updated.
+    fn update_all_in_equities(&mut self) -> bool {
+        if !self.config.all_in_equity || !self.is_all_in_runout() {
+            return false;
+        }
+
+        let hands = self
+            .players
+            .iter()
+            .filter(|p| p.is_active)
+            .filter_map(|p| match p.hole_cards {
+                PlayerCards::Cards(c1, c2) => Some((c1, c2)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // Cards are revealed even when there is no budget left for the equities.
+        let mut equities = if self.has_equity_budget() {
+            Some(all_in_equities(&hands, &self.board).into_iter())
+        } else {
+            debug!("Table {} skipped all in equities", self.table_id);
+            None
+        };
+
+        let players = self.players.iter_mut().filter(|p| p.is_active);
+        for player in players {
+            player.public_cards = player.hole_cards;
+            player.equity = equities.as_mut().and_then(Iterator::next);
+        }
+
+        true
+    }
+
+    /// Takes an equity computation from the budget, returns false if the
+    /// computation should be skipped.
+    fn has_equity_budget(&self) -> bool {
+        self.config
+            .equity_budget
+            .as_ref()
+            .is_none_or(|budget| budget.try_acquire())
+    }
+
+    /// Sends spectators the made hand of each player in the hand when the board
+    /// runs out with players all in.
+    async fn send_spectator_hands(&self) {
+        if !self.config.spectator_hands
+            || self.spectators.is_empty()
+            || self.board.len() < 3
+            || !self.is_all_in_runout()
+            || !self.has_equity_budget()
+        {
+            return;
+        }
+
+        let hands = self
+            .players
+            .iter()
+            .filter(|p| p.is_active)
+            .filter_map(|p| match p.hole_cards {
+                PlayerCards::Cards(c1, c2) => {
+                    let mut cards = self.board.clone();
+                    cards.extend([c1, c2]);
+                    let rank = HandValue::eval(&cards).rank();
+                    Some((p.player_id.clone(), rank.to_string()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let smsg = SignedMessage::new(&self.sk, Message::SpectatorHands { hands });
+        self.send_spectators(smsg).await;
+    }
+
+    /// Checks if there is no betting left because players in the hand are all in.
+    fn is_all_in_runout(&self) -> bool {
+        self.players.count_active() > 1 && self.players.count_active_with_chips() < 2
+    }
+
     fn update_pots(&mut self) {
         // Updates pots if there is a bet.
         if self.last_bet > Chips::ZERO {
@@ -746,16 +1644,110 @@ impl State {
         }
     }
 
+    /// Players in the hand post the ante.
+    ///
+    /// Antes are dead money that goes straight to the pots and doesn't count as
+    /// part of the players bets, a player with fewer chips than the ante goes all
+    /// in and can only win the pots it contributed to.
+    fn post_antes(&mut self) {
+        let ante = self.big_blind * self.config.ante_percent / 100;
+        if ante == Chips::ZERO {
+            return;
+        }
+
+        for player in self.players.iter_mut().filter(|p| p.is_active) {
+            player.bet(PlayerAction::Ante, ante);
+            player.ante = player.bet;
+            self.hand_log
+                .action(&player.player_id, PlayerAction::Ante, player.bet);
+            self.betting_round
+                .push(&player.player_id, PlayerAction::Ante, player.bet);
+        }
+
+        self.last_bet = ante;
+        self.update_pots();
+    }
+
+    /// Returns the blinds level for the current hand.
+    fn blind_level(&self) -> BlindLevel {
+        // The hand count is incremented when the blinds are updated at the start of
+        // a hand so the current hand index is one less.
+        let hand = self.hand_count.saturating_sub(1);
+        let level = (hand / Self::HANDS_PER_LEVEL).min(Self::LAST_LEVEL);
+        let hands_to_next = (level < Self::LAST_LEVEL)
+            .then(|| (Self::HANDS_PER_LEVEL - hand % Self::HANDS_PER_LEVEL) as u16);
+
+        BlindLevel {
+            level: level as u8 + 1,
+            small_blind: self.small_blind,
+            big_blind: self.big_blind,
+            hands_to_next,
+        }
+    }
+
     /// Broadcast a game state update to all connected players.
-    async fn broadcast_game_update(&self) {
+    async fn broadcast_game_update(&mut self) {
+        self.update_seq += 1;
+
+        let smsg = SignedMessage::new(&self.sk, self.game_update());
+        for player in self.players.iter() {
+            player.send_message(smsg.clone()).await;
+        }
+
+        self.send_spectators(smsg).await;
+    }
+
+    /// Sends a full game update to a player that missed some updates.
+    async fn resync(&mut self, player_id: &PeerId) {
+        let Some(player) = self.players.iter().find(|p| &p.player_id == player_id) else {
+            return;
+        };
+
+        let mut msgs = vec![self.game_update()];
+        if let PlayerCards::Cards(c1, c2) = player.hole_cards
+            && player.is_active
+        {
+            msgs.push(Message::DealCards(c1, c2));
+        }
+
+        for msg in msgs {
+            player.send_message(SignedMessage::new(&self.sk, msg)).await;
+        }
+
+        // Send the action request again to the player in case it was missed, the
+        // action timer keeps running so that a resync doesn't give more time.
+        let is_waiting_action = self
+            .players
+            .active_player()
+            .is_some_and(|p| &p.player_id == player_id && p.action_timer.is_some());
+        if is_waiting_action {
+            let msg = Message::ActionRequest {
+                player_id: player_id.clone(),
+                big_blind: self.big_blind,
+                actions: self.action_options(),
+            };
+
+            if let Some(player) = self.players.active_player() {
+                player.send_message(SignedMessage::new(&self.sk, msg)).await;
+            }
+        }
+    }
+
+    /// Returns a game update with the current table state.
+    fn game_update(&self) -> Message {
+        let timeout = self.action_timeout();
         let players = self
             .players
             .iter()
             .map(|p| {
-                let action_timer = p.action_timer.map(|t| {
-                    Self::ACTION_TIMEOUT
-                        .saturating_sub(t.elapsed())
-                        .as_secs_f32() as u16
+                let elapsed = p.action_timer.map(|t| t.elapsed());
+                let action_timer =
+                    elapsed.map(|elapsed| timeout.saturating_sub(elapsed).as_secs_f32() as u16);
+
+                // The time bank is reported once the action timer has expired.
+                let time_bank = elapsed.filter(|elapsed| *elapsed > timeout).map(|elapsed| {
+                    let used = elapsed - timeout;
+                    p.time_bank.saturating_sub(used).as_secs_f32() as u16
                 });
 
                 PlayerUpdate {
@@ -764,65 +1756,149 @@ impl State {
                     bet: p.bet,
                     action: p.action,
                     action_timer,
+                    time_bank,
                     cards: p.public_cards,
                     has_button: p.has_button,
                     is_active: p.is_active,
+                    equity: p.equity,
                 }
             })
             .collect();
 
-        let pot = self
-            .pots
-            .iter()
-            .map(|p| p.chips)
-            .fold(Chips::ZERO, |acc, c| acc + c);
+        let pots = self.pots.iter().map(|p| p.chips).collect::<Vec<_>>();
+        let pot = pots.iter().fold(Chips::ZERO, |acc, c| acc + *c);
 
-        let msg = Message::GameUpdate {
+        Message::GameUpdate {
+            seq: self.update_seq,
             players,
             board: self.board.clone(),
+            extra_boards: self.extra_boards.clone(),
             pot,
-        };
-        let smsg = SignedMessage::new(&self.sk, msg);
-        for player in self.players.iter() {
-            player.send_message(smsg.clone()).await;
+            pots,
+            blinds: self.blind_level(),
         }
     }
 
     /// Request action to the active player.
     async fn request_action(&mut self) {
+        let actions = self.action_options();
+        if let Some(player) = self.players.active_player() {
+            player.action_timer = Some(Instant::now());
+
+            let msg = Message::ActionRequest {
+                player_id: player.player_id.clone(),
+                big_blind: self.big_blind,
+                actions,
+            };
+
+            self.broadcast_message(msg).await;
+        }
+    }
+
+    /// Returns the legal actions for the active player with their amounts.
+    fn action_options(&mut self) -> Vec<ActionOption> {
+        // If all other players are all in there is nobody left to bet against.
+        let can_bet = self.players.count_active_with_chips() > 1;
+        let can_raise = !self.is_raise_capped();
+
+        // In push-fold mode players can only call after somebody went all in.
+        let push_fold = self.is_push_fold();
+        let can_call = !push_fold
+            || self
+                .players
+                .iter()
+                .any(|p| matches!(p.action, PlayerAction::Bet | PlayerAction::Raise));
+
         if let Some(player) = self.players.active_player() {
-            let mut actions = vec![PlayerAction::Fold];
+            // The most a player can bet is all its chips.
+            let max_bet = player.chips + player.bet;
+
+            let mut actions = vec![ActionOption::fixed(PlayerAction::Fold, Chips::ZERO)];
 
             if player.bet == self.last_bet {
-                actions.push(PlayerAction::Check);
+                actions.push(ActionOption::fixed(PlayerAction::Check, Chips::ZERO));
             }
 
-            if player.bet < self.last_bet {
-                actions.push(PlayerAction::Call);
+            // A player facing a bet calls, a call is never for zero chips.
+            if can_call && player.bet < self.last_bet {
+                let amount = self.last_bet.min(max_bet);
+                debug_assert!(amount > player.bet, "Call for zero chips");
+                actions.push(ActionOption::fixed(PlayerAction::Call, amount));
             }
 
-            if self.last_bet == Chips::ZERO && player.chips > Chips::ZERO {
-                actions.push(PlayerAction::Bet);
+            if can_bet && self.last_bet == Chips::ZERO && player.chips > Chips::ZERO {
+                actions.push(ActionOption {
+                    action: PlayerAction::Bet,
+                    min: if push_fold {
+                        max_bet
+                    } else {
+                        self.big_blind.min(max_bet)
+                    },
+                    max: max_bet,
+                });
             }
 
-            if player.chips + player.bet > self.last_bet
+            if can_bet
+                && can_raise
+                && max_bet > self.last_bet
                 && self.last_bet > Chips::ZERO
                 && player.chips > Chips::ZERO
             {
-                actions.push(PlayerAction::Raise);
+                // If the player cannot make a full raise it can still go all in.
+                actions.push(ActionOption {
+                    action: PlayerAction::Raise,
+                    min: if push_fold {
+                        max_bet
+                    } else {
+                        (self.min_raise + self.last_bet).min(max_bet)
+                    },
+                    max: max_bet,
+                });
             }
 
-            player.action_timer = Some(Instant::now());
+            actions
+        } else {
+            Vec::default()
+        }
+    }
 
-            let msg = Message::ActionRequest {
-                player_id: player.player_id.clone(),
-                min_raise: self.min_raise + self.last_bet,
-                big_blind: self.big_blind,
-                actions,
-            };
+    /// Calls the table observer if there is one.
+    fn observe(&self, f: impl FnOnce(&dyn StateObserver)) {
+        if let Some(observer) = &self.config.observer {
+            f(observer.as_ref());
+        }
+    }
 
-            self.broadcast_message(msg).await;
+    /// Checks if the raises in this street have reached the table cap.
+    fn is_raise_capped(&self) -> bool {
+        self.config.raise_cap.is_some_and(|cap| self.raises >= cap)
+    }
+
+    /// Returns the time the active player has to act on the current street.
+    fn action_timeout(&self) -> Duration {
+        let timeouts = &self.config.action_timeouts;
+        match self.hand_state {
+            HandState::FlopBetting => timeouts.flop,
+            HandState::TurnBetting => timeouts.turn,
+            HandState::RiverBetting => timeouts.river,
+            _ => timeouts.preflop,
+        }
+    }
+
+    /// Checks if betting is restricted to fold or all in.
+    fn is_push_fold(&self) -> bool {
+        self.config.push_fold && matches!(self.hand_state, HandState::PreflopBetting)
+    }
+
+    /// Sets the players hole cards in seat order and the board cards to be dealt
+    /// in the current hand, used to script hands in tests.
+    #[cfg(test)]
+    fn set_hand_cards(&mut self, hole_cards: &[(Card, Card)], board: &[Card]) {
+        for (player, (c1, c2)) in self.players.iter_mut().zip(hole_cards) {
+            player.hole_cards = PlayerCards::Cards(*c1, *c2);
         }
+
+        self.deck = Deck::from_cards(board.iter().copied());
     }
 
     /// Broadcast a message to all players at the table.
@@ -831,19 +1907,67 @@ impl State {
         for player in self.players.iter() {
             player.send_message(smsg.clone()).await;
         }
+
+        self.send_spectators(smsg).await;
+    }
+
+    /// Sends a public message to the table spectators.
+    async fn send_spectators(&self, smsg: SignedMessage) {
+        for (_, table_tx) in &self.spectators {
+            let _ = table_tx.send(TableMessage::Send(smsg.clone())).await;
+        }
     }
 
     /// Broadcast a throttle message to all players at the table.
     async fn broadcast_throttle(&self, dt: Duration) {
+        let dt = dt.max(self.config.min_action_pace);
         for player in self.players.iter() {
             player.send_throttle(dt).await;
         }
     }
 }
 
+/// Computes the equity of each player hole cards on the given board.
+///
+/// The equity is exact from the flop onwards, preflop it is estimated from a
+/// sample of the runouts and scaled so that the players equities add up to one.
+fn all_in_equities(hands: &[(Card, Card)], board: &[Card]) -> Vec<f32> {
+    /// Number of sampled runouts preflop.
+    const SAMPLES: usize = 20_000;
+
+    if hands.len() < 2 {
+        return vec![1.0; hands.len()];
+    }
+
+    let equities = (0..hands.len())
+        .map(|idx| {
+            let (c1, c2) = hands[idx];
+            let villains = hands
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != idx)
+                .map(|(_, hand)| *hand);
+
+            if board.len() >= 3 {
+                equity::enumerate(&[c1, c2], &villains.collect::<Vec<_>>(), board).equity
+            } else {
+                let ranges = villains.map(|hand| vec![hand]).collect::<Vec<_>>();
+                equity::simulate_ranges(&[c1, c2], &ranges, board, SAMPLES).equity
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let total = equities.iter().sum::<f64>().max(f64::EPSILON);
+    equities.into_iter().map(|e| (e / total) as f32).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::table::{
+        ActionTimeouts, EquityBudget, RakeConfig,
+        test_support::{drain_messages, expect_messages},
+    };
     use freezeout_core::poker::{Rank, Suit};
 
     struct TestPlayer {
@@ -909,10 +2033,15 @@ mod tests {
     impl TestTable {
         /// Creates a `State` with seeded randomness and memory database.
         fn new(player_chips: Vec<u32>) -> Self {
+            Self::with_config(player_chips, TableConfig::default())
+        }
+
+        /// Creates a `State` with the given table configuration.
+        fn with_config(player_chips: Vec<u32>, config: TableConfig) -> Self {
             let rng = StdRng::seed_from_u64(101333);
             let db = Db::open_in_memory().unwrap();
             let sk = Arc::new(SigningKey::default());
-            let state = State::with_rng(TableId::new_id(), player_chips.len(), sk, db, rng);
+            let state = State::with_rng(TableId::new_id(), player_chips.len(), config, sk, db, rng);
             let players = player_chips
                 .into_iter()
                 .map(|c| TestPlayer::new(Chips::new(c)))
@@ -982,7 +2111,7 @@ mod tests {
         async fn test_start_hand(&mut self) {
             // Before a new hand starts all players get a StartHand message.
             for p in self.players.iter_mut() {
-                assert_message!(p, Message::StartHand);
+                assert_message!(p, Message::StartHand { .. });
             }
 
             // The small blind and big blind players pay the blinds.
@@ -1029,12 +2158,16 @@ mod tests {
             }
         }
 
+        /// Bets or raises if there is already a bet.
         async fn bet(&mut self, amount: Chips) {
-            self.send_action(Message::ActionResponse {
-                action: PlayerAction::Bet,
-                amount,
-            })
-            .await;
+            let action = if self.state.last_bet > Chips::ZERO {
+                PlayerAction::Raise
+            } else {
+                PlayerAction::Bet
+            };
+
+            self.send_action(Message::ActionResponse { action, amount })
+                .await;
         }
 
         async fn call(&mut self) {
@@ -1091,7 +2224,7 @@ mod tests {
         // request for the next player to act.
         for p in table.players.iter_mut() {
             assert_message!(p, Message::GameUpdate { players, .. }, || {
-                assert!(matches!(players[2].action, PlayerAction::Bet));
+                assert!(matches!(players[2].action, PlayerAction::Raise));
             });
             assert_message!(p, Message::ActionRequest { .. });
         }
@@ -1162,8 +2295,10 @@ mod tests {
 
         // Request action from first player.
         for p in table.players.iter_mut() {
-            assert_message!(p, Message::GameUpdate { .. });
-            assert_message!(p, Message::ActionRequest { .. });
+            expect_messages!(
+                &mut p.rx,
+                [Message::GameUpdate { .. }, Message::ActionRequest { .. }]
+            );
         }
 
         // First player to act goes all in, this is the player with fewer chips.
@@ -1172,47 +2307,36 @@ mod tests {
         // All players get a game update with the player action followed by an action
         // request for the next player to act.
         for p in table.players.iter_mut() {
-            assert_message!(p, Message::GameUpdate { players, .. }, || {
-                assert!(matches!(players[0].action, PlayerAction::Bet));
-            });
-            assert_message!(p, Message::ActionRequest { .. });
+            expect_messages!(
+                &mut p.rx,
+                [
+                    Message::GameUpdate { players, .. }
+                        if matches!(players[0].action, PlayerAction::Raise),
+                    Message::ActionRequest { .. },
+                ]
+            );
         }
 
         table.call().await;
 
+        // The board is dealt to the showdown and the all in player loses.
         for p in table.players.iter_mut() {
-            assert_message!(p, Message::GameUpdate { players, .. }, || {
-                assert!(matches!(players[1].action, PlayerAction::Call));
-            });
-
-            // New round deal flop update.
-            assert_message!(p, Message::GameUpdate { board, .. }, || {
-                assert_eq!(board.len(), 3);
-            });
-
-            // Deal turn.
-            assert_message!(p, Message::GameUpdate { board, .. }, || {
-                assert_eq!(board.len(), 4);
-            });
-
-            // Deal river.
-            assert_message!(p, Message::GameUpdate { board, .. }, || {
-                assert_eq!(board.len(), 5);
-            });
-
-            // Showdown message with all players cards.
-            assert_message!(p, Message::GameUpdate { players, .. }, || {
-                for p in players {
-                    assert!(matches!(p.cards, PlayerCards::Cards(_, _)));
-                }
-            });
-
-            // All players get a EndHand message with winner.
-            assert_message!(p, Message::EndHand { payoffs, .. }, || {
-                // Only one payoff
-                assert_eq!(payoffs.len(), 1);
-                assert_eq!(payoffs[0].chips, Chips::new(100_000));
-            });
+            expect_messages!(
+                &mut p.rx,
+                [
+                    Message::GameUpdate { players, .. }
+                        if matches!(players[1].action, PlayerAction::Call),
+                    Message::GameUpdate { board, .. } if board.len() == 3,
+                    Message::GameUpdate { board, .. } if board.len() == 4,
+                    Message::GameUpdate { board, .. } if board.len() == 5,
+                    Message::GameUpdate { players, .. }
+                        if players.iter().all(|p| matches!(p.cards, PlayerCards::Cards(_, _))),
+                    Message::EndHand { payoffs, .. }
+                        if payoffs.len() == 1 && payoffs[0].chips == Chips::new(100_000),
+                    Message::GameOver { standings }
+                        if standings.len() == 2 && standings[1].chips == Chips::ZERO,
+                ]
+            );
         }
     }
 
@@ -1238,7 +2362,7 @@ mod tests {
         // request for the next player to act.
         for p in table.players.iter_mut() {
             assert_message!(p, Message::GameUpdate { players, .. }, || {
-                assert!(matches!(players[2].action, PlayerAction::Bet));
+                assert!(matches!(players[2].action, PlayerAction::Raise));
             });
             assert_message!(p, Message::ActionRequest { .. });
         }
@@ -1298,45 +2422,216 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn small_blind_all_in() {
-        // Test games where the small blind chips are lower than the small blind.
-        let mut table = TestTable::new(vec![20_000, 100_000]);
-        // Incremebt small blind to 40000 so that is greater than player chips.
-
-        loop {
-            table.state.update_blinds();
-            if table.state.small_blind == Chips::new(40_000) {
-                break;
-            }
-        }
+    async fn last_player_with_chips() {
+        const JOIN_CHIPS: u32 = 100_000;
+        const JOIN_CHIPS_SMALL: u32 = JOIN_CHIPS / 2;
 
+        let mut table = TestTable::new(vec![JOIN_CHIPS, JOIN_CHIPS, JOIN_CHIPS_SMALL]);
         table.test_start_game().await;
         table.test_start_hand().await;
 
-        // The small blind player is all in we should go all the way to showdown.
         for p in table.players.iter_mut() {
-            // Preflop game update.
             assert_message!(p, Message::GameUpdate { .. });
+            assert_message!(p, Message::ActionRequest { .. });
+        }
 
-            // New round deal flop update.
-            assert_message!(p, Message::GameUpdate { board, .. }, || {
-                assert_eq!(board.len(), 3);
-            });
+        // UG goes all in.
+        table.bet(Chips::new(JOIN_CHIPS_SMALL)).await;
 
-            // New round deal turn update.
-            assert_message!(p, Message::GameUpdate { board, .. }, || {
-                assert_eq!(board.len(), 4);
+        for p in table.players.iter_mut() {
+            assert_message!(p, Message::GameUpdate { .. });
+            assert_message!(p, Message::ActionRequest { actions, .. }, || {
+                assert!(actions.iter().any(|o| o.action == PlayerAction::Raise));
             });
+        }
 
-            // New round deal river update.
-            assert_message!(p, Message::GameUpdate { board, .. }, || {
-                assert_eq!(board.len(), 5);
-            });
+        // SB goes all in, BB is the only player left with chips.
+        table.bet(Chips::new(JOIN_CHIPS)).await;
 
-            // Pot update.
-            assert_message!(p, Message::GameUpdate { pot, .. }, || {
-                // Pot if the big blind plus the small blind chips that were half the
-                // small blind.
+        for p in table.players.iter_mut() {
+            assert_message!(p, Message::GameUpdate { .. });
+
+            // BB can only call or fold as there is nobody left to raise against.
+            assert_message!(
+                p,
+                Message::ActionRequest {
+                    player_id,
+                    actions,
+                    ..
+                },
+                || {
+                    assert_eq!(player_id, &table.state.players.player(1).player_id);
+                    let actions = actions.iter().map(|o| o.action).collect::<Vec<_>>();
+                    assert_eq!(actions, vec![PlayerAction::Fold, PlayerAction::Call]);
+                }
+            );
+        }
+
+        table.call().await;
+
+        // The board is dealt to the showdown without any other action request.
+        for p in table.players.iter_mut() {
+            assert_message!(p, Message::GameUpdate { players, .. }, || {
+                assert!(matches!(players[1].action, PlayerAction::Call));
+            });
+
+            for n in 3..=5 {
+                assert_message!(p, Message::GameUpdate { board, .. }, || {
+                    assert_eq!(board.len(), n);
+                });
+            }
+
+            assert_message!(p, Message::GameUpdate { .. });
+            assert_message!(p, Message::EndHand { .. });
+        }
+    }
+
+    #[tokio::test]
+    async fn refund_players_mid_hand() {
+        const DB_CHIPS: u32 = 1_000_000;
+        const JOIN_CHIPS: u32 = 100_000;
+
+        let mut table = TestTable::new(vec![JOIN_CHIPS, JOIN_CHIPS, JOIN_CHIPS]);
+
+        // Players pay the join chips from their accounts as the server handler does.
+        for p in &table.players {
+            let db = &table.state.db;
+            db.join_server(p.id().clone(), "nn", Chips::new(DB_CHIPS), true)
+                .await
+                .unwrap();
+            assert!(
+                db.pay_from_player(TxId::new_id(), p.id().clone(), Chips::new(JOIN_CHIPS))
+                    .await
+                    .unwrap()
+            );
+        }
+
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        // UTG bets and SB calls so that there are chips in the pot.
+        table.bet(Chips::new(50_000)).await;
+        table.call().await;
+        assert!(table.state.players.iter().any(|p| p.bet > Chips::ZERO));
+
+        // Shutting down mid-hand returns the starting stacks to all players.
+        table.state.refund_players().await;
+        assert_eq!(table.state.players.count(), 0);
+
+        for p in &table.players {
+            let player = table.state.db.get_player(p.id().clone()).await.unwrap();
+            assert_eq!(player.chips, Chips::new(DB_CHIPS));
+        }
+    }
+
+    #[tokio::test]
+    async fn fixed_seats() {
+        let config = TableConfig {
+            fixed_seats: true,
+            ..TableConfig::default()
+        };
+        let mut table = TestTable::with_config(vec![100_000, 100_000, 100_000], config);
+
+        for p in table.players.iter_mut() {
+            table
+                .state
+                .try_join(
+                    &p.p.player_id,
+                    &p.p.nickname,
+                    p.join_chips,
+                    p.p.table_tx.clone(),
+                )
+                .await
+                .unwrap();
+        }
+
+        // With fixed seats the players are seated in join order.
+        let player_ids = table
+            .players
+            .iter()
+            .map(|p| p.id().clone())
+            .collect::<Vec<_>>();
+
+        for p in table.players.iter_mut() {
+            let seats = loop {
+                match p.rx() {
+                    Some(TableMessage::Send(msg)) => {
+                        if let Message::StartGame(seats) = msg.message() {
+                            break seats.clone();
+                        }
+                    }
+                    Some(_) => {}
+                    None => panic!("No StartGame message"),
+                }
+            };
+            assert_eq!(seats, player_ids);
+        }
+    }
+
+    #[tokio::test]
+    async fn hand_seed_reproduces_deal() {
+        let mut table = TestTable::new(vec![100_000, 100_000, 100_000]);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        // Shuffle a deck with the hand seed and deal two cards to each player.
+        let mut deck = Deck::shuffled(&mut StdRng::seed_from_u64(table.state.hand_seed));
+        for player in table.state.players.iter() {
+            let (c1, c2) = (deck.deal(), deck.deal());
+            let expected = if c1.rank() < c2.rank() {
+                PlayerCards::Cards(c1, c2)
+            } else {
+                PlayerCards::Cards(c2, c1)
+            };
+            assert_eq!(player.hole_cards, expected);
+        }
+
+        // The remaining cards match the ones left in the table deck.
+        for _ in 0..5 {
+            assert_eq!(deck.deal(), table.state.deck.deal());
+        }
+    }
+
+    #[tokio::test]
+    async fn small_blind_all_in() {
+        // Test games where the small blind chips are lower than the small blind.
+        let mut table = TestTable::new(vec![20_000, 100_000]);
+        // Incremebt small blind to 40000 so that is greater than player chips.
+
+        loop {
+            table.state.update_blinds();
+            if table.state.small_blind == Chips::new(40_000) {
+                break;
+            }
+        }
+
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        // The small blind player is all in we should go all the way to showdown.
+        for p in table.players.iter_mut() {
+            // Preflop game update.
+            assert_message!(p, Message::GameUpdate { .. });
+
+            // New round deal flop update.
+            assert_message!(p, Message::GameUpdate { board, .. }, || {
+                assert_eq!(board.len(), 3);
+            });
+
+            // New round deal turn update.
+            assert_message!(p, Message::GameUpdate { board, .. }, || {
+                assert_eq!(board.len(), 4);
+            });
+
+            // New round deal river update.
+            assert_message!(p, Message::GameUpdate { board, .. }, || {
+                assert_eq!(board.len(), 5);
+            });
+
+            // Pot update.
+            assert_message!(p, Message::GameUpdate { pot, .. }, || {
+                // Pot if the big blind plus the small blind chips that were half the
+                // small blind.
                 assert_eq!(*pot, table.state.big_blind + Chips::new(20_000));
             });
 
@@ -1404,12 +2699,150 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn rake() {
+        let house = SigningKey::default().verifying_key().peer_id();
+        let config = TableConfig {
+            rake: Some(RakeConfig {
+                percent: 5,
+                cap: Chips::new(1_000),
+                account: Some(house.clone()),
+            }),
+            ..Default::default()
+        };
+
+        let mut table = TestTable::with_config(vec![100_000, 100_000, 100_000], config);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        for p in table.players.iter_mut() {
+            assert_message!(p, Message::GameUpdate { .. });
+            assert_message!(p, Message::ActionRequest { .. });
+        }
+
+        let bb_player_id = table.state.players.player(1).player_id.clone();
+
+        // UTG and SB fold so that the BB wins the blinds.
+        table.fold().await;
+        table.fold().await;
+
+        // The pot is 30k, 5% is 1.5k that is capped to 1k.
+        let pot = table.state.big_blind + table.state.small_blind;
+        let rake = Chips::new(1_000);
+
+        for p in table.players.iter_mut() {
+            // UTG fold and action request for SB.
+            assert_message!(p, Message::GameUpdate { .. });
+            assert_message!(p, Message::ActionRequest { .. });
+
+            // SB fold and pot update.
+            assert_message!(p, Message::GameUpdate { .. });
+            assert_message!(p, Message::GameUpdate { .. });
+
+            assert_message!(
+                p,
+                Message::EndHand {
+                    payoffs,
+                    rake: hand_rake,
+                    ..
+                },
+                || {
+                    assert_eq!(*hand_rake, rake);
+
+                    let payoff = &payoffs[0];
+                    assert_eq!(payoff.player_id, bb_player_id);
+                    assert_eq!(payoff.chips, pot - rake);
+                }
+            );
+        }
+
+        // The house account has been credited with the rake.
+        let account = table.state.db.get_player(house).await.unwrap();
+        assert_eq!(account.chips, rake);
+    }
+
+    #[tokio::test]
+    async fn pause_table() {
+        let admin = TestPlayer::new(Chips::new(100_000));
+        let config = TableConfig {
+            admins: vec![admin.id().clone()],
+            ..Default::default()
+        };
+
+        let mut table = TestTable::with_config(vec![100_000], config);
+        table.players.push(admin);
+        table.state.seats = 2;
+
+        table.test_start_game().await;
+        table.test_start_hand().await;
+        table.drain_players_message();
+
+        // Find the admin position after the seats have been shuffled.
+        let admin_idx = table
+            .players
+            .iter()
+            .position(|p| table.state.config.admins.contains(p.id()))
+            .unwrap();
+
+        // Players cannot pause the table with a table message.
+        let table_id = table.state.table_id;
+        let msg = table.players[admin_idx].msg(Message::PauseTable { table_id });
+        table.state.message(msg).await;
+        assert!(!table.state.paused);
+
+        // The admin pauses the table during the hand.
+        assert!(table.state.set_paused(true).await);
+        assert!(table.state.paused);
+
+        // Pausing a paused table doesn't change it.
+        assert!(!table.state.set_paused(true).await);
+
+        // The hand continues until the end.
+        table.fold().await;
+        assert!(matches!(table.state.hand_state, HandState::EndHand));
+        table.drain_players_message();
+
+        // When the new hand timer expires the table is paused.
+        table.state.new_hand_timer = Some(Instant::now() - Duration::from_secs(60));
+        table.state.tick().await;
+        assert!(matches!(table.state.hand_state, HandState::Paused));
+
+        for p in table.players.iter_mut() {
+            assert_message!(p, Message::TablePaused);
+        }
+
+        // Ticks don't start a new hand while paused.
+        table.state.tick().await;
+        for p in table.players.iter_mut() {
+            assert!(p.rx().is_none());
+        }
+
+        // Resume the table starts a new hand.
+        assert!(table.state.set_paused(false).await);
+        assert!(!table.state.paused);
+
+        for p in table.players.iter_mut() {
+            assert_message!(p, Message::StartHand { .. });
+        }
+    }
+
     #[tokio::test]
     async fn multi_pots() {
         let mut table = TestTable::new(vec![500_000, 300_000, 100_000]);
         table.test_start_game().await;
         table.test_start_hand().await;
 
+        // Set players cards so that the player with fewer chips has the best hand
+        // and the one with more chips the worst hand (board is KC AH 8C TD TC).
+        let cards = [
+            (Rank::Deuce, Suit::Diamonds, Rank::Trey, Suit::Diamonds),
+            (Rank::Jack, Suit::Hearts, Rank::Jack, Suit::Spades),
+            (Rank::Ace, Suit::Spades, Rank::Ace, Suit::Diamonds),
+        ];
+        for (p, (r1, s1, r2, s2)) in table.state.players.iter_mut().zip(cards) {
+            p.hole_cards = PlayerCards::Cards(Card::new(r1, s1), Card::new(r2, s2));
+        }
+
         for p in table.players.iter_mut() {
             assert_message!(p, Message::GameUpdate { .. });
             assert_message!(p, Message::ActionRequest { .. });
@@ -1438,9 +2871,7 @@ mod tests {
         }
 
         // Last player (BB) calls and goes all in.
-        let player = table.state.players.active_player().unwrap();
-        let amount = player.chips + player.bet;
-        table.bet(amount).await;
+        table.call().await;
 
         // All players went all in we should get the following messages.
         for p in table.players.iter_mut() {
@@ -1484,11 +2915,30 @@ mod tests {
         table.test_start_game().await;
         table.test_start_hand().await;
 
-        // Set player cards so that we get a split win (another player has 7D 9H).
-        let p = table.state.players.iter_mut().next().unwrap();
-        p.hole_cards = PlayerCards::Cards(
-            Card::new(Rank::Seven, Suit::Spades),
-            Card::new(Rank::Nine, Suit::Diamonds),
+        // Set players cards so that we get a split win between the first two
+        // players that both make a straight.
+        table.state.set_hand_cards(
+            &[
+                (
+                    Card::new(Rank::Seven, Suit::Spades),
+                    Card::new(Rank::Nine, Suit::Diamonds),
+                ),
+                (
+                    Card::new(Rank::Seven, Suit::Diamonds),
+                    Card::new(Rank::Nine, Suit::Hearts),
+                ),
+                (
+                    Card::new(Rank::Deuce, Suit::Spades),
+                    Card::new(Rank::Trey, Suit::Spades),
+                ),
+            ],
+            &[
+                Card::new(Rank::King, Suit::Clubs),
+                Card::new(Rank::Six, Suit::Hearts),
+                Card::new(Rank::Eight, Suit::Clubs),
+                Card::new(Rank::Ten, Suit::Diamonds),
+                Card::new(Rank::Ten, Suit::Clubs),
+            ],
         );
 
         // Preflop.
@@ -1523,10 +2973,13 @@ mod tests {
             // Game update with showdown.
             assert_message!(p, Message::GameUpdate { .. });
 
-            assert_message!(p, Message::EndHand { payoffs, .. }, || {
+            assert_message!(p, Message::EndHand { payoffs, board, .. }, || {
+                assert_eq!(board[1], Card::new(Rank::Six, Suit::Hearts));
+
                 // We should have 2 payoffs, with equal amount as two players have
                 // the same cards value (7S, 9D) and (7D, 9H)
                 assert_eq!(payoffs.len(), 2);
+                assert_eq!(payoffs[0].rank, "STRAIGHT");
                 assert_eq!(payoffs[0].chips, Chips::new(75_000));
                 assert_eq!(payoffs[1].chips, Chips::new(75_000));
             });
@@ -1567,4 +3020,1775 @@ mod tests {
         assert_eq!(table.state.small_blind, State::START_GAME_SB * 12);
         assert_eq!(table.state.big_blind, State::START_GAME_BB * 12);
     }
+
+    #[tokio::test]
+    async fn blinds_level() {
+        let mut table = TestTable::new(vec![100_000, 100_000]);
+
+        // Play 5 hands so that the next hand is the second of level 2.
+        (0..5).for_each(|_| table.state.update_blinds());
+
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        for p in table.players.iter_mut() {
+            assert_message!(p, Message::GameUpdate { blinds, .. }, || {
+                assert_eq!(blinds.level, 2);
+                assert_eq!(blinds.small_blind, State::START_GAME_SB * 2);
+                assert_eq!(blinds.big_blind, State::START_GAME_BB * 2);
+                assert_eq!(blinds.hands_to_next, Some(3));
+            });
+        }
+
+        // At the last level there are no more increases.
+        (0..16).for_each(|_| table.state.update_blinds());
+        let blinds = table.state.blind_level();
+        assert_eq!(blinds.level, 5);
+        assert_eq!(blinds.big_blind, State::START_GAME_BB * 12);
+        assert_eq!(blinds.hands_to_next, None);
+    }
+
+    #[tokio::test]
+    async fn reconnect_grace() {
+        const GRACE: Duration = Duration::from_secs(20);
+
+        let config = TableConfig {
+            reconnect_grace: GRACE,
+            ..Default::default()
+        };
+
+        let mut table = TestTable::with_config(vec![100_000, 100_000, 100_000], config);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+        table.drain_players_message();
+
+        // The UTG player connection is lost, the other players are told how long the
+        // player has to reconnect.
+        let player_id = table.players[2].id().clone();
+        assert!(table.state.disconnect(&player_id).await);
+        for p in table.players.iter_mut().take(2) {
+            assert_message!(
+                p,
+                Message::PlayerDisconnected {
+                    player_id: id,
+                    seconds
+                },
+                || {
+                    assert_eq!(id, &player_id);
+                    assert_eq!(*seconds, GRACE.as_secs() as u16);
+                }
+            );
+        }
+
+        // The seat is held during the grace period.
+        table.state.tick().await;
+        assert_eq!(table.state.players.count(), 3);
+        table.drain_players_message();
+
+        // The player reconnects with a new connection and gets the table state.
+        let (tx, mut rx) = mpsc::channel(64);
+        assert!(table.state.reconnect(&player_id, tx).await);
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(TableMessage::Send(msg)) if matches!(msg.message(), Message::TableJoined { .. })
+        ));
+        assert!(table.state.players.iter().all(|p| p.disconnected.is_none()));
+        table.players[2].rx = rx;
+        table.drain_players_message();
+
+        // Only disconnected players can reconnect.
+        let (tx, _rx) = mpsc::channel(64);
+        assert!(!table.state.reconnect(&player_id, tx).await);
+
+        // The player disconnects again and doesn't come back in time.
+        table.state.disconnect(&player_id).await;
+        table.drain_players_message();
+
+        let player = table
+            .state
+            .players
+            .iter_mut()
+            .find(|p| p.player_id == player_id)
+            .unwrap();
+        player.disconnected = Some(Instant::now() - GRACE - Duration::from_secs(1));
+        table.state.tick().await;
+
+        // The player has been removed from the table.
+        assert_eq!(table.state.players.count(), 2);
+        for p in table.players.iter_mut().take(2) {
+            let mut left = false;
+            while let Some(msg) = p.rx() {
+                if let TableMessage::Send(msg) = msg
+                    && let Message::PlayerLeft(id) = msg.message()
+                    && id == &player_id
+                {
+                    left = true;
+                }
+            }
+            assert!(left);
+        }
+    }
+
+    #[tokio::test]
+    async fn action_options() {
+        const JOIN_CHIPS: u32 = 100_000;
+        const JOIN_CHIPS_SMALL: u32 = 50_000;
+
+        let mut table = TestTable::new(vec![JOIN_CHIPS, JOIN_CHIPS, JOIN_CHIPS_SMALL]);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        // UTG can call the big blind or raise from a min raise to all its chips.
+        let bb = table.state.big_blind;
+        for p in table.players.iter_mut() {
+            assert_message!(p, Message::GameUpdate { .. });
+            assert_message!(p, Message::ActionRequest { actions, .. }, || {
+                assert_eq!(
+                    actions,
+                    &vec![
+                        ActionOption::fixed(PlayerAction::Fold, Chips::ZERO),
+                        ActionOption::fixed(PlayerAction::Call, bb),
+                        ActionOption {
+                            action: PlayerAction::Raise,
+                            min: bb * 2,
+                            max: Chips::new(JOIN_CHIPS_SMALL),
+                        },
+                    ]
+                );
+            });
+        }
+
+        // UTG raises to 40k, SB can raise from 60k to all its chips.
+        table.bet(bb * 2).await;
+        for p in table.players.iter_mut() {
+            assert_message!(p, Message::GameUpdate { .. });
+            assert_message!(p, Message::ActionRequest { actions, .. }, || {
+                let raise = actions
+                    .iter()
+                    .find(|o| o.action == PlayerAction::Raise)
+                    .unwrap();
+                assert_eq!(raise.min, bb * 3);
+                assert_eq!(raise.max, Chips::new(JOIN_CHIPS));
+            });
+        }
+
+        // SB raises to 90k, BB can go all in but cannot make a full raise.
+        table.bet(Chips::new(90_000)).await;
+        for p in table.players.iter_mut() {
+            assert_message!(p, Message::GameUpdate { .. });
+            assert_message!(p, Message::ActionRequest { actions, .. }, || {
+                let raise = actions
+                    .iter()
+                    .find(|o| o.action == PlayerAction::Raise)
+                    .unwrap();
+                assert_eq!(raise.min, Chips::new(JOIN_CHIPS));
+                assert_eq!(raise.max, Chips::new(JOIN_CHIPS));
+            });
+        }
+    }
+
+    #[tokio::test]
+    async fn button_straddle() {
+        const JOIN_CHIPS: u32 = 100_000;
+
+        let config = TableConfig {
+            straddle: Some(StraddlePosition::Button),
+            ..Default::default()
+        };
+
+        let mut table = TestTable::with_config(vec![JOIN_CHIPS; 4], config);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        // The button pays the straddle and the small blind is the first to act.
+        let bb = table.state.big_blind;
+        let sb_id = table.players[0].id().clone();
+        for p in table.players.iter_mut() {
+            expect_messages!(
+                &mut p.rx,
+                [
+                    Message::GameUpdate { players, .. }
+                        if players[3].action == PlayerAction::Straddle && players[3].bet == bb * 2,
+                    Message::ActionRequest { player_id, actions, .. }
+                        if player_id == &sb_id && actions.contains(&ActionOption {
+                            action: PlayerAction::Raise,
+                            min: bb * 4,
+                            max: Chips::new(JOIN_CHIPS),
+                        }),
+                ]
+            );
+        }
+
+        // SB, BB and UTG call the straddle.
+        for _ in 0..3 {
+            table.call().await;
+        }
+
+        // The straddle is the last to act preflop and can check.
+        let straddle_id = table.players[3].id().clone();
+        for p in table.players.iter_mut() {
+            let mut requests = drain_messages(&mut p.rx)
+                .into_iter()
+                .filter_map(|msg| match msg.message() {
+                    Message::ActionRequest {
+                        player_id, actions, ..
+                    } => Some((player_id.clone(), actions.clone())),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            let (player_id, actions) = requests.pop().unwrap();
+            assert_eq!(player_id, straddle_id);
+            assert!(actions.iter().any(|o| o.action == PlayerAction::Check));
+        }
+
+        // After the straddle checks the flop is dealt and the SB acts first.
+        table.check().await;
+        assert!(matches!(table.state.hand_state, HandState::FlopBetting));
+        assert!(table.state.players.is_active(&sb_id));
+    }
+
+    #[tokio::test]
+    async fn utg_straddle() {
+        let config = TableConfig {
+            straddle: Some(StraddlePosition::Utg),
+            ..Default::default()
+        };
+
+        let mut table = TestTable::with_config(vec![100_000; 4], config);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        // UTG pays the straddle and the button is the first to act.
+        let bb = table.state.big_blind;
+        let btn_id = table.players[3].id().clone();
+        for p in table.players.iter_mut() {
+            expect_messages!(
+                &mut p.rx,
+                [
+                    Message::GameUpdate { players, .. }
+                        if players[2].action == PlayerAction::Straddle && players[2].bet == bb * 2,
+                    Message::ActionRequest { player_id, .. } if player_id == &btn_id,
+                ]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn push_fold() {
+        const JOIN_CHIPS: u32 = 100_000;
+        const JOIN_CHIPS_SMALL: u32 = 50_000;
+
+        let config = TableConfig {
+            push_fold: true,
+            ..Default::default()
+        };
+
+        let mut table =
+            TestTable::with_config(vec![JOIN_CHIPS, JOIN_CHIPS, JOIN_CHIPS_SMALL], config);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        // UTG can only fold or go all in.
+        for p in table.players.iter_mut() {
+            assert_message!(p, Message::GameUpdate { .. });
+            assert_message!(p, Message::ActionRequest { actions, .. }, || {
+                assert_eq!(
+                    actions,
+                    &vec![
+                        ActionOption::fixed(PlayerAction::Fold, Chips::ZERO),
+                        ActionOption::fixed(PlayerAction::Raise, Chips::new(JOIN_CHIPS_SMALL)),
+                    ]
+                );
+            });
+        }
+
+        // A sized raise is an all in, SB can call the all in or go all in.
+        table.bet(table.state.big_blind * 2).await;
+        for p in table.players.iter_mut() {
+            assert_message!(p, Message::GameUpdate { players, .. }, || {
+                assert_eq!(players[2].bet, Chips::new(JOIN_CHIPS_SMALL));
+            });
+            assert_message!(p, Message::ActionRequest { actions, .. }, || {
+                assert_eq!(
+                    actions,
+                    &vec![
+                        ActionOption::fixed(PlayerAction::Fold, Chips::ZERO),
+                        ActionOption::fixed(PlayerAction::Call, Chips::new(JOIN_CHIPS_SMALL)),
+                        ActionOption::fixed(PlayerAction::Raise, Chips::new(JOIN_CHIPS)),
+                    ]
+                );
+            });
+        }
+    }
+
+    #[tokio::test]
+    async fn state_observer() {
+        #[derive(Debug, Default)]
+        struct Recorder(std::sync::Mutex<Vec<String>>);
+
+        impl StateObserver for Recorder {
+            fn on_hand_start(&self, _table_id: TableId, hand: usize) {
+                self.0.lock().unwrap().push(format!("start {hand}"));
+            }
+
+            fn on_street(&self, _table_id: TableId, board: &[Card]) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("street {}", board.len()));
+            }
+
+            fn on_showdown(&self, _table_id: TableId, _board: &[Card]) {
+                self.0.lock().unwrap().push("showdown".to_string());
+            }
+
+            fn on_payout(&self, _table_id: TableId, payoffs: &[HandPayoff]) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("payout {}", payoffs.len()));
+            }
+        }
+
+        let recorder = Arc::new(Recorder::default());
+        let config = TableConfig {
+            observer: Some(recorder.clone()),
+            ..Default::default()
+        };
+
+        let mut table = TestTable::with_config(vec![100_000, 100_000], config);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        // Check down the hand to showdown.
+        table.call().await;
+        table.check().await;
+        for _ in 0..3 {
+            table.check().await;
+            table.check().await;
+        }
+
+        let events = recorder.0.lock().unwrap().clone();
+        assert_eq!(
+            events,
+            vec![
+                "start 1", "street 3", "street 4", "street 5", "showdown", "payout 1"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn raise_cap() {
+        let config = TableConfig {
+            raise_cap: Some(2),
+            ..Default::default()
+        };
+
+        let mut table = TestTable::with_config(vec![1_000_000; 4], config);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        // UTG and the button raise, the small blind can only fold or call.
+        let bb = table.state.big_blind;
+        table.bet(bb * 2).await;
+        table.bet(bb * 4).await;
+        for p in table.players.iter_mut() {
+            let messages = drain_messages(&mut p.rx);
+            let Some(Message::ActionRequest { actions, .. }) = messages.last().map(|m| m.message())
+            else {
+                panic!("Expected an action request got {messages:?}");
+            };
+
+            let actions = actions.iter().map(|o| o.action).collect::<Vec<_>>();
+            assert_eq!(actions, vec![PlayerAction::Fold, PlayerAction::Call]);
+        }
+
+        // The cap is reset on the next street.
+        table.call().await;
+        table.call().await;
+        table.call().await;
+        table.bet(bb).await;
+        table.bet(bb * 2).await;
+        for p in table.players.iter_mut() {
+            let messages = drain_messages(&mut p.rx);
+            let Some(Message::ActionRequest { actions, .. }) = messages.last().map(|m| m.message())
+            else {
+                panic!("Expected an action request got {messages:?}");
+            };
+
+            assert!(actions.iter().any(|o| o.action == PlayerAction::Raise));
+        }
+    }
+
+    #[tokio::test]
+    async fn min_action_pace() {
+        let pace = Duration::from_millis(1_500);
+        let config = TableConfig {
+            min_action_pace: pace,
+            ..Default::default()
+        };
+
+        let mut table = TestTable::with_config(vec![100_000; 3], config);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+        for p in table.players.iter_mut() {
+            drain_messages(&mut p.rx);
+        }
+
+        // After each action players wait before getting the next request.
+        table.call().await;
+        for p in table.players.iter_mut() {
+            let msgs = std::iter::from_fn(|| p.rx.try_recv().ok()).collect::<Vec<_>>();
+            assert!(
+                matches!(
+                    msgs.as_slice(),
+                    [
+                        TableMessage::Send(m1),
+                        TableMessage::Throttle(dt),
+                        TableMessage::Send(m2),
+                    ] if matches!(m1.message(), Message::GameUpdate { .. })
+                        && *dt == pace
+                        && matches!(m2.message(), Message::ActionRequest { .. })
+                ),
+                "Unexpected messages {msgs:?}"
+            );
+        }
+
+        // Complete the preflop round, shorter throttles are raised to the pace.
+        table.call().await;
+        table.check().await;
+        for p in table.players.iter_mut() {
+            let msgs = std::iter::from_fn(|| p.rx.try_recv().ok()).collect::<Vec<_>>();
+            let throttles = msgs
+                .iter()
+                .filter_map(|m| match m {
+                    TableMessage::Throttle(dt) => Some(*dt),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            assert!(throttles.len() > 2);
+            assert!(throttles.iter().all(|dt| *dt >= pace));
+        }
+    }
+
+    #[tokio::test]
+    async fn double_board() {
+        let config = TableConfig {
+            boards: 2,
+            ..Default::default()
+        };
+
+        let mut table = TestTable::with_config(vec![100_000, 100_000], config);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        // The first player wins the first board and the second player the second
+        // board, cards are dealt to each board street by street.
+        let card = Card::new;
+        table.state.set_hand_cards(
+            &[
+                (card(Rank::Ace, Suit::Spades), card(Rank::Ace, Suit::Hearts)),
+                (
+                    card(Rank::King, Suit::Spades),
+                    card(Rank::King, Suit::Hearts),
+                ),
+            ],
+            &[
+                // Flops.
+                card(Rank::Deuce, Suit::Clubs),
+                card(Rank::Seven, Suit::Diamonds),
+                card(Rank::Nine, Suit::Hearts),
+                card(Rank::King, Suit::Diamonds),
+                card(Rank::Seven, Suit::Clubs),
+                card(Rank::Deuce, Suit::Hearts),
+                // Turns.
+                card(Rank::Jack, Suit::Clubs),
+                card(Rank::Trey, Suit::Spades),
+                // Rivers.
+                card(Rank::Four, Suit::Spades),
+                card(Rank::Eight, Suit::Diamonds),
+            ],
+        );
+
+        // Check down the hand to showdown.
+        table.call().await;
+        table.check().await;
+        for _ in 0..3 {
+            table.check().await;
+            table.check().await;
+        }
+
+        let p1_id = table.players[0].id().clone();
+        let p2_id = table.players[1].id().clone();
+        for p in table.players.iter_mut() {
+            let msgs = drain_messages(&mut p.rx);
+            let Some(Message::EndHand {
+                payoffs,
+                board,
+                extra_boards,
+                ..
+            }) = msgs.last().map(|m| m.message())
+            else {
+                panic!("Expected end hand got {msgs:?}");
+            };
+
+            assert_eq!(board.len(), 5);
+            assert_eq!(extra_boards.len(), 1);
+            assert_eq!(extra_boards[0][0], card(Rank::King, Suit::Diamonds));
+
+            // The 40k pot is split between the two boards winners.
+            assert_eq!(payoffs.len(), 2);
+            assert_eq!(payoffs[0].player_id, p1_id);
+            assert_eq!(payoffs[0].chips, Chips::new(20_000));
+            assert_eq!(payoffs[0].rank, "ONE PAIR");
+            assert_eq!(payoffs[1].player_id, p2_id);
+            assert_eq!(payoffs[1].chips, Chips::new(20_000));
+            assert_eq!(payoffs[1].rank, "THREE OF A KIND");
+        }
+    }
+
+    #[tokio::test]
+    async fn max_boards() {
+        assert_eq!(TableConfig::max_boards(2), 9);
+        assert_eq!(TableConfig::max_boards(6), 8);
+
+        // A full table with the maximum number of boards deals all boards from
+        // one deck.
+        let config = TableConfig {
+            boards: TableConfig::max_boards(6),
+            ..Default::default()
+        };
+
+        let mut table = TestTable::with_config(vec![100_000; 6], config);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        for _ in 0..5 {
+            table.call().await;
+        }
+        table.check().await;
+        for _ in 0..3 {
+            for _ in 0..6 {
+                table.check().await;
+            }
+        }
+
+        let msgs = drain_messages(&mut table.players[0].rx);
+        let Some(Message::EndHand {
+            board,
+            extra_boards,
+            ..
+        }) = msgs.last().map(|m| m.message())
+        else {
+            panic!("Expected end hand got {msgs:?}");
+        };
+
+        assert_eq!(board.len(), 5);
+        assert_eq!(extra_boards.len(), 7);
+        assert!(extra_boards.iter().all(|b| b.len() == 5));
+    }
+
+    #[tokio::test]
+    async fn resync_request() {
+        let mut table = TestTable::new(vec![100_000, 100_000, 100_000]);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+        table.drain_players_message();
+
+        // Each update has the next sequence number.
+        let seq = table.state.update_seq;
+        table.call().await;
+        assert_eq!(table.state.update_seq, seq + 1);
+        for p in table.players.iter_mut() {
+            assert_message!(p, Message::GameUpdate { seq: s, .. }, || {
+                assert_eq!(*s, seq + 1);
+            });
+        }
+        table.drain_players_message();
+
+        // The SB asks for a resync and gets the last update, its cards and the
+        // action request.
+        let msg = table.players[0].msg(Message::ResyncRequest);
+        table.state.message(msg).await;
+        assert_eq!(table.state.update_seq, seq + 1);
+
+        let p = &mut table.players[0];
+        assert_message!(p, Message::GameUpdate { seq: s, .. }, || {
+            assert_eq!(*s, seq + 1);
+        });
+        assert_message!(p, Message::DealCards(_, _));
+        assert_message!(p, Message::ActionRequest { player_id, .. }, || {
+            assert_eq!(player_id, p.id());
+        });
+
+        // The action request is sent only to the player and its timer keeps
+        // running.
+        for p in table.players.iter_mut().skip(1) {
+            assert!(p.rx().is_none());
+        }
+
+        let started = Instant::now() - Duration::from_secs(5);
+        table.state.players.active_player().unwrap().action_timer = Some(started);
+        let msg = table.players[0].msg(Message::ResyncRequest);
+        table.state.message(msg).await;
+        assert_eq!(
+            table.state.players.active_player().unwrap().action_timer,
+            Some(started)
+        );
+    }
+
+    #[tokio::test]
+    async fn nickname_policy() {
+        async fn join(table: &mut TestTable, idx: usize) -> Result<(), TableJoinError> {
+            let p = &table.players[idx];
+            table
+                .state
+                .try_join(&p.p.player_id, "Bob", p.join_chips, p.p.table_tx.clone())
+                .await
+        }
+
+        let nicknames = |table: &TestTable| {
+            table
+                .state
+                .players
+                .iter()
+                .map(|p| p.nickname.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // By default players can have the same nickname.
+        let mut table = TestTable::new(vec![100_000, 100_000, 100_000]);
+        join(&mut table, 0).await.unwrap();
+        join(&mut table, 1).await.unwrap();
+        assert_eq!(nicknames(&table), vec!["Bob", "Bob"]);
+
+        // The second player is rejected.
+        let config = TableConfig {
+            nickname_policy: NicknamePolicy::Reject,
+            ..TableConfig::default()
+        };
+        let mut table = TestTable::with_config(vec![100_000, 100_000, 100_000], config);
+        join(&mut table, 0).await.unwrap();
+        assert!(matches!(
+            join(&mut table, 1).await,
+            Err(TableJoinError::NicknameTaken)
+        ));
+        assert_eq!(nicknames(&table), vec!["Bob"]);
+
+        // Nicknames are disambiguated with a suffix.
+        let config = TableConfig {
+            nickname_policy: NicknamePolicy::Suffix,
+            ..TableConfig::default()
+        };
+        let mut table = TestTable::with_config(vec![100_000, 100_000, 100_000, 100_000], config);
+        join(&mut table, 0).await.unwrap();
+        join(&mut table, 1).await.unwrap();
+        join(&mut table, 2).await.unwrap();
+        assert_eq!(nicknames(&table), vec!["Bob", "Bob (2)", "Bob (3)"]);
+
+        // Other players are told the disambiguated nickname.
+        let p = &mut table.players[0];
+        assert_message!(p, Message::TableJoined { .. });
+        assert_message!(p, Message::PlayerJoined { nickname, .. }, || {
+            assert_eq!(nickname, "Bob (2)");
+        });
+    }
+
+    #[tokio::test]
+    async fn validate_action_amounts() {
+        let mut table = TestTable::new(vec![100_000, 100_000, 100_000]);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+        table.drain_players_message();
+
+        // UTG cannot check the big blind, the action is ignored.
+        let bb = table.state.big_blind;
+        table.check().await;
+        assert!(table.players[2].rx().is_none());
+        assert_eq!(table.state.players.player(2).action, PlayerAction::None);
+
+        // A raise below the minimum is clamped to the minimum raise.
+        table.bet(bb + Chips::new(1_000)).await;
+        assert_eq!(table.state.players.player(2).action, PlayerAction::Raise);
+        assert_eq!(table.state.players.player(2).bet, bb * 2);
+        assert_eq!(table.state.last_bet, bb * 2);
+        table.drain_players_message();
+
+        // A raise above the player chips is clamped to an all in.
+        table.bet(Chips::new(1_000_000)).await;
+        assert_eq!(table.state.players.player(0).bet, Chips::new(100_000));
+        assert_eq!(table.state.players.player(0).chips, Chips::ZERO);
+    }
+
+    #[tokio::test]
+    async fn all_in_equity() {
+        let config = TableConfig {
+            all_in_equity: true,
+            ..TableConfig::default()
+        };
+
+        let mut table = TestTable::with_config(vec![100_000, 100_000], config);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        let hands = [
+            (
+                Card::new(Rank::Ace, Suit::Spades),
+                Card::new(Rank::Queen, Suit::Spades),
+            ),
+            (
+                Card::new(Rank::Jack, Suit::Hearts),
+                Card::new(Rank::Ten, Suit::Hearts),
+            ),
+        ];
+        let board = [
+            Card::new(Rank::King, Suit::Hearts),
+            Card::new(Rank::Nine, Suit::Spades),
+            Card::new(Rank::Deuce, Suit::Hearts),
+            Card::new(Rank::Five, Suit::Clubs),
+            Card::new(Rank::Trey, Suit::Diamonds),
+        ];
+        table.state.set_hand_cards(&hands, &board);
+
+        // Players check to the turn where both go all in.
+        table.call().await;
+        table.check().await;
+        table.check().await;
+        table.check().await;
+        table.drain_players_message();
+
+        table.bet(Chips::new(80_000)).await;
+        table.call().await;
+
+        // Both players cards are revealed with their equity on the turn.
+        let msgs = drain_messages(&mut table.players[0].rx);
+        let equities = msgs
+            .iter()
+            .find_map(|m| match m.message() {
+                Message::GameUpdate { board, players, .. } if board.len() == 4 => {
+                    let equities = players.iter().map(|p| p.equity).collect::<Option<Vec<_>>>();
+                    if equities.is_some() {
+                        assert!(
+                            players
+                                .iter()
+                                .all(|p| matches!(p.cards, PlayerCards::Cards(..)))
+                        );
+                    }
+                    equities
+                }
+                _ => None,
+            })
+            .expect("No equities update");
+
+        // Compare with an exact enumeration of the river cards.
+        let mut wins = [0.0f32; 2];
+        let mut rivers = 0;
+        for river in Deck::default() {
+            let known = hands.iter().flat_map(|(c1, c2)| [*c1, *c2]);
+            if known.chain(board[..4].iter().copied()).any(|c| c == river) {
+                continue;
+            }
+
+            let values = hands
+                .iter()
+                .map(|(c1, c2)| {
+                    let mut cards = vec![*c1, *c2, river];
+                    cards.extend_from_slice(&board[..4]);
+                    HandValue::eval(&cards)
+                })
+                .collect::<Vec<_>>();
+            match values[0].cmp(&values[1]) {
+                std::cmp::Ordering::Greater => wins[0] += 1.0,
+                std::cmp::Ordering::Less => wins[1] += 1.0,
+                std::cmp::Ordering::Equal => {
+                    wins[0] += 0.5;
+                    wins[1] += 0.5;
+                }
+            }
+            rivers += 1;
+        }
+        assert_eq!(rivers, 44);
+
+        assert!((equities.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+        for (equity, wins) in equities.iter().zip(wins) {
+            assert!((equity - wins / rivers as f32).abs() < 1e-5);
+        }
+    }
+
+    #[tokio::test]
+    async fn time_bank() {
+        const BANK: Duration = Duration::from_secs(30);
+
+        let config = TableConfig {
+            time_bank: BANK,
+            ..TableConfig::default()
+        };
+
+        let mut table = TestTable::with_config(vec![100_000, 100_000], config);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+        table.drain_players_message();
+
+        let time_bank_update = |table: &mut TestTable| {
+            drain_messages(&mut table.players[0].rx)
+                .iter()
+                .find_map(|m| match m.message() {
+                    Message::GameUpdate { players, .. } => players
+                        .iter()
+                        .find(|p| p.action_timer.is_some())
+                        .map(|p| (p.action_timer.unwrap(), p.time_bank)),
+                    _ => None,
+                })
+                .expect("No timer update")
+        };
+
+        // While the action timer is running the time bank is not used.
+        table.state.tick().await;
+        let (timer, bank) = time_bank_update(&mut table);
+        assert!(timer > 0);
+        assert!(bank.is_none());
+
+        // Once the action timer expires the player is into the time bank.
+        let timeout = ActionTimeouts::default().preflop;
+        let player = table.state.players.active_player().unwrap();
+        player.action_timer = Some(Instant::now() - timeout - Duration::from_secs(10));
+        table.state.tick().await;
+        let (timer, bank) = time_bank_update(&mut table);
+        assert_eq!(timer, 0);
+        assert!(bank.is_some_and(|b| (19..=20).contains(&b)));
+
+        // The time used is charged to the player time bank.
+        let player_id = table
+            .state
+            .players
+            .active_player()
+            .unwrap()
+            .player_id
+            .clone();
+        table.call().await;
+        let player = table
+            .state
+            .players
+            .iter()
+            .find(|p| p.player_id == player_id)
+            .unwrap();
+        assert!(player.time_bank <= BANK - Duration::from_secs(10));
+        assert!(player.time_bank > BANK - Duration::from_secs(11));
+        table.drain_players_message();
+
+        // The player folds when both timer and time bank expire.
+        let player = table.state.players.active_player().unwrap();
+        player.action_timer = Some(Instant::now() - timeout - BANK);
+        table.state.tick().await;
+        assert!(!table.state.players.iter().all(|p| p.is_active));
+    }
+
+    #[tokio::test]
+    async fn game_over_standings() {
+        let mut table = TestTable::new(vec![50_000, 100_000, 200_000]);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        let ids = table
+            .players
+            .iter()
+            .map(|p| p.id().clone())
+            .collect::<Vec<_>>();
+
+        // The big stack has aces and the other players go all in against it.
+        let hands = table
+            .state
+            .players
+            .iter()
+            .map(|p| {
+                if p.player_id == ids[2] {
+                    (
+                        Card::new(Rank::Ace, Suit::Spades),
+                        Card::new(Rank::Ace, Suit::Hearts),
+                    )
+                } else if p.player_id == ids[1] {
+                    (
+                        Card::new(Rank::Eight, Suit::Clubs),
+                        Card::new(Rank::Trey, Suit::Diamonds),
+                    )
+                } else {
+                    (
+                        Card::new(Rank::Seven, Suit::Clubs),
+                        Card::new(Rank::Deuce, Suit::Diamonds),
+                    )
+                }
+            })
+            .collect::<Vec<_>>();
+        let board = [
+            Card::new(Rank::King, Suit::Hearts),
+            Card::new(Rank::Queen, Suit::Diamonds),
+            Card::new(Rank::Nine, Suit::Clubs),
+            Card::new(Rank::Four, Suit::Spades),
+            Card::new(Rank::Jack, Suit::Hearts),
+        ];
+        table.state.set_hand_cards(&hands, &board);
+
+        table.bet(Chips::new(200_000)).await;
+        table.call().await;
+        table.call().await;
+
+        // Both short stacks bust in the same hand, the bigger one finishes second.
+        let msgs = drain_messages(&mut table.players[0].rx);
+        let standings = msgs
+            .iter()
+            .find_map(|m| match m.message() {
+                Message::GameOver { standings } => Some(standings.clone()),
+                _ => None,
+            })
+            .expect("No game over message");
+
+        let places = standings
+            .iter()
+            .map(|s| (s.player_id.clone(), s.chips, s.place))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            places,
+            vec![
+                (ids[2].clone(), Chips::new(350_000), 1),
+                (ids[1].clone(), Chips::ZERO, 2),
+                (ids[0].clone(), Chips::ZERO, 3),
+            ]
+        );
+        assert!(standings.iter().all(|s| !s.nickname.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn spectator_chat() {
+        let mut table = TestTable::new(vec![100_000, 100_000]);
+
+        // Spectators can only watch a game in progress.
+        let sk1 = SigningKey::default();
+        let (tx1, mut rx1) = mpsc::channel(64);
+        let id1 = sk1.verifying_key().peer_id();
+        assert!(!table.state.spectate(&id1, tx1.clone()).await);
+
+        table.test_start_game().await;
+        table.test_start_hand().await;
+        table.drain_players_message();
+
+        let (tx2, mut rx2) = mpsc::channel(64);
+        let id2 = SigningKey::default().verifying_key().peer_id();
+        assert!(table.state.spectate(&id1, tx1).await);
+        assert!(table.state.spectate(&id2, tx2).await);
+
+        // Seated players cannot spectate.
+        let (tx, _rx) = mpsc::channel(64);
+        let player_id = table.players[0].id().clone();
+        assert!(!table.state.spectate(&player_id, tx).await);
+
+        // Spectators get the game state.
+        for rx in [&mut rx1, &mut rx2] {
+            let msgs = drain_messages(rx);
+            assert!(matches!(msgs[0].message(), Message::GameUpdate { .. }));
+        }
+
+        // A spectator chat reaches the other spectators only.
+        let msg = SignedMessage::new(
+            &sk1,
+            Message::SpectatorChat {
+                player_id: player_id.clone(),
+                text: "nice hand".to_string(),
+            },
+        );
+        table.state.message(msg).await;
+
+        let msgs = drain_messages(&mut rx2);
+        assert_eq!(msgs.len(), 1);
+        assert!(matches!(
+            msgs[0].message(),
+            Message::SpectatorChat { player_id, text } if player_id == &id1 && text == "nice hand"
+        ));
+        assert!(drain_messages(&mut rx1).is_empty());
+        for p in table.players.iter_mut() {
+            assert!(p.rx().is_none());
+        }
+
+        // Seated players cannot send spectators chat.
+        let msg = table.players[1].msg(Message::SpectatorChat {
+            player_id: player_id.clone(),
+            text: "hello".to_string(),
+        });
+        table.state.message(msg).await;
+        assert!(drain_messages(&mut rx1).is_empty());
+        assert!(drain_messages(&mut rx2).is_empty());
+
+        // Spectators get the table public updates.
+        table.call().await;
+        assert!(
+            drain_messages(&mut rx2)
+                .iter()
+                .any(|m| matches!(m.message(), Message::GameUpdate { .. }))
+        );
+
+        // A spectator leaving doesn't affect the players.
+        table.state.leave(&id2).await;
+        assert!(matches!(rx2.try_recv(), Ok(TableMessage::PlayerLeft)));
+        assert_eq!(table.state.players.count(), 2);
+    }
+
+    #[tokio::test]
+    async fn mandatory_reveal() {
+        async fn showdown_cards(mandatory_reveal: bool) -> Vec<PlayerCards> {
+            let config = TableConfig {
+                mandatory_reveal,
+                ..TableConfig::default()
+            };
+
+            let mut table = TestTable::with_config(vec![100_000, 100_000], config);
+            table.test_start_game().await;
+            table.test_start_hand().await;
+
+            // Both players want to muck their losing hands.
+            for idx in 0..2 {
+                let msg = table.players[idx].msg(Message::SetAutoMuck(true));
+                table.state.message(msg).await;
+            }
+
+            let winner = table.players[0].id().clone();
+            let hands = table
+                .state
+                .players
+                .iter()
+                .map(|p| {
+                    if p.player_id == winner {
+                        (
+                            Card::new(Rank::Ace, Suit::Spades),
+                            Card::new(Rank::Ace, Suit::Hearts),
+                        )
+                    } else {
+                        (
+                            Card::new(Rank::Seven, Suit::Clubs),
+                            Card::new(Rank::Deuce, Suit::Diamonds),
+                        )
+                    }
+                })
+                .collect::<Vec<_>>();
+            let board = [
+                Card::new(Rank::King, Suit::Hearts),
+                Card::new(Rank::Queen, Suit::Diamonds),
+                Card::new(Rank::Nine, Suit::Clubs),
+                Card::new(Rank::Four, Suit::Spades),
+                Card::new(Rank::Jack, Suit::Hearts),
+            ];
+            table.state.set_hand_cards(&hands, &board);
+
+            // Players check down to the showdown.
+            table.call().await;
+            for _ in 0..7 {
+                table.check().await;
+            }
+
+            let msgs = drain_messages(&mut table.players[0].rx);
+            let cards = msgs
+                .iter()
+                .find_map(|m| match m.message() {
+                    Message::EndHand { cards, .. } => Some(cards.clone()),
+                    _ => None,
+                })
+                .expect("No end hand message");
+
+            // The winner always shows its hand.
+            let (_, winner_cards) = cards.iter().find(|(id, _)| id == &winner).unwrap();
+            assert!(matches!(winner_cards, PlayerCards::Cards(..)));
+
+            cards
+                .into_iter()
+                .filter(|(id, _)| id != &winner)
+                .map(|(_, c)| c)
+                .collect()
+        }
+
+        // The losing hand is mucked.
+        let cards = showdown_cards(false).await;
+        assert!(matches!(cards[..], [PlayerCards::Covered]));
+
+        // With mandatory reveal all hands are shown.
+        let cards = showdown_cards(true).await;
+        assert!(matches!(cards[..], [PlayerCards::Cards(..)]));
+    }
+
+    #[tokio::test]
+    async fn street_action_timeouts() {
+        let new_table = async || {
+            let config = TableConfig {
+                action_timeouts: ActionTimeouts {
+                    preflop: Duration::from_secs(5),
+                    flop: Duration::from_secs(30),
+                    ..ActionTimeouts::default()
+                },
+                ..TableConfig::default()
+            };
+
+            let mut table = TestTable::with_config(vec![100_000, 100_000], config);
+            table.test_start_game().await;
+            table.test_start_hand().await;
+            table
+        };
+
+        let mut table = new_table().await;
+        table.drain_players_message();
+
+        let action_timer = |table: &mut TestTable| {
+            drain_messages(&mut table.players[0].rx)
+                .iter()
+                .rev()
+                .find_map(|m| match m.message() {
+                    Message::GameUpdate { players, .. } => {
+                        players.iter().find_map(|p| p.action_timer)
+                    }
+                    _ => None,
+                })
+                .expect("No timer update")
+        };
+
+        // The preflop timer uses the preflop timeout.
+        table.state.tick().await;
+        assert!((4..=5).contains(&action_timer(&mut table)));
+
+        // Preflop players fold after the preflop timeout.
+        let player = table.state.players.active_player().unwrap();
+        player.action_timer = Some(Instant::now() - Duration::from_secs(6));
+        table.state.tick().await;
+        assert!(!table.state.players.iter().all(|p| p.is_active));
+
+        // On the flop players get the flop timeout.
+        let mut table = new_table().await;
+        table.call().await;
+        table.check().await;
+        assert!(matches!(table.state.hand_state, HandState::FlopBetting));
+        table.drain_players_message();
+
+        table.state.tick().await;
+        assert!((29..=30).contains(&action_timer(&mut table)));
+
+        let player = table.state.players.active_player().unwrap();
+        player.action_timer = Some(Instant::now() - Duration::from_secs(6));
+        table.state.tick().await;
+        assert!(table.state.players.iter().all(|p| p.is_active));
+
+        let player = table.state.players.active_player().unwrap();
+        player.action_timer = Some(Instant::now() - Duration::from_secs(31));
+        table.state.tick().await;
+        assert!(!table.state.players.iter().all(|p| p.is_active));
+    }
+
+    #[tokio::test]
+    async fn payout_pause() {
+        // Returns the pause before the players get the end hand message.
+        let payout_pause = |table: &mut TestTable| {
+            let p = &mut table.players[0];
+            let msgs = std::iter::from_fn(|| p.rx.try_recv().ok()).collect::<Vec<_>>();
+            let end_hand = msgs
+                .iter()
+                .position(|m| {
+                    matches!(m, TableMessage::Send(m) if matches!(m.message(), Message::EndHand { .. }))
+                })
+                .expect("No end hand");
+            msgs[..end_hand]
+                .iter()
+                .rev()
+                .find_map(|m| match m {
+                    TableMessage::Throttle(dt) => Some(*dt),
+                    _ => None,
+                })
+                .expect("No throttle")
+        };
+
+        // A fold win uses the short pause.
+        let mut table = TestTable::new(vec![100_000, 100_000]);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+        table.fold().await;
+        assert_eq!(payout_pause(&mut table), State::FOLD_PAYOUT_PAUSE);
+
+        // A showdown uses the full pause.
+        let mut table = TestTable::new(vec![100_000, 100_000]);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+        table.call().await;
+        table.check().await;
+        for _ in 0..3 {
+            table.check().await;
+            table.check().await;
+        }
+        assert_eq!(payout_pause(&mut table), State::SHOWDOWN_PAYOUT_PAUSE);
+    }
+
+    #[tokio::test]
+    async fn betting_round_actions() {
+        let mut table = TestTable::new(vec![100_000; 3]);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        let active_id = |table: &mut TestTable| {
+            table
+                .state
+                .players
+                .active_player()
+                .unwrap()
+                .player_id
+                .clone()
+        };
+
+        let raiser = active_id(&mut table);
+        table.bet(Chips::new(60_000)).await;
+        let caller = active_id(&mut table);
+        table.call().await;
+
+        // The blinds, raise, and call are recorded in order with the player bets.
+        let actions = table.state.betting_round.actions();
+        assert_eq!(actions.len(), 4);
+        assert_eq!(actions[0].0, *table.players[0].id());
+        assert_eq!(actions[0].1, PlayerAction::SmallBlind);
+        assert_eq!(actions[0].2, Chips::new(10_000));
+        assert_eq!(actions[1].0, *table.players[1].id());
+        assert_eq!(actions[1].1, PlayerAction::BigBlind);
+        assert_eq!(actions[1].2, Chips::new(20_000));
+        assert_eq!(
+            actions[2],
+            (raiser, PlayerAction::Raise, Chips::new(60_000))
+        );
+        assert_eq!(actions[3], (caller, PlayerAction::Call, Chips::new(60_000)));
+
+        // A new round starts with no actions.
+        table.call().await;
+        assert!(matches!(table.state.hand_state, HandState::FlopBetting));
+        assert!(table.state.betting_round.actions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn suspended_action_timer() {
+        let mut table = TestTable::new(vec![100_000, 100_000]);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        // The player has used 10s of the action time when a prompt starts.
+        let player = table.state.players.active_player().unwrap();
+        let player_id = player.player_id.clone();
+        player.action_timer = Some(Instant::now() - Duration::from_secs(10));
+        table.state.suspend_timer(&player_id).await;
+
+        // While answering the prompt the player is not folded however long it takes.
+        let player = table.state.players.active_player().unwrap();
+        assert!(player.action_timer.is_none());
+        player.suspended_timer = Some(Duration::from_secs(60));
+        table.state.tick().await;
+        assert!(table.state.players.iter().all(|p| p.is_active));
+
+        // The timer resumes from the time used before the prompt.
+        let player = table.state.players.active_player().unwrap();
+        player.suspended_timer = Some(Duration::from_secs(10));
+        table.state.resume_timer(&player_id).await;
+        let player = table.state.players.active_player().unwrap();
+        let elapsed = player.action_timer.unwrap().elapsed();
+        assert!(elapsed >= Duration::from_secs(10) && elapsed < Duration::from_secs(11));
+        table.state.tick().await;
+        assert!(table.state.players.iter().all(|p| p.is_active));
+
+        // Once resumed the player times out as usual.
+        let player = table.state.players.active_player().unwrap();
+        player.action_timer = Some(Instant::now() - Duration::from_secs(16));
+        table.state.tick().await;
+        assert!(!table.state.players.iter().all(|p| p.is_active));
+    }
+
+    #[tokio::test]
+    async fn hand_number() {
+        let mut table = TestTable::new(vec![100_000, 100_000]);
+        table.test_start_game().await;
+
+        let start_hand_number = |table: &mut TestTable| {
+            drain_messages(&mut table.players[1].rx)
+                .iter()
+                .find_map(|m| match m.message() {
+                    Message::StartHand { hand_number } => Some(*hand_number),
+                    _ => None,
+                })
+                .expect("No start hand")
+        };
+
+        // The game starts with the first hand.
+        assert_eq!(start_hand_number(&mut table), 1);
+
+        // Each new hand tells players its number.
+        for hand in 2..=4 {
+            table.drain_players_message();
+            table.fold().await;
+            assert!(matches!(table.state.hand_state, HandState::EndHand));
+
+            table.state.new_hand_timer = Some(Instant::now() - Duration::from_secs(60));
+            table.state.tick().await;
+            assert_eq!(start_hand_number(&mut table), hand);
+        }
+    }
+
+    #[tokio::test]
+    async fn max_timeouts() {
+        let mut table = TestTable::with_config(
+            vec![100_000, 100_000, 100_000],
+            TableConfig {
+                max_timeouts: Some(2),
+                ..TableConfig::default()
+            },
+        );
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        let timeout = ActionTimeouts::default().preflop;
+        let expire_timer = |table: &mut TestTable, timeouts: usize| {
+            let player = table.state.players.active_player().unwrap();
+            player.timeouts = timeouts;
+            player.action_timer = Some(Instant::now() - timeout - Duration::from_secs(1));
+            player.player_id.clone()
+        };
+        let timeouts = |table: &TestTable, player_id: &PeerId| {
+            table
+                .state
+                .players
+                .iter()
+                .find(|p| &p.player_id == player_id)
+                .map(|p| p.timeouts)
+        };
+
+        // The first timeout folds the player who keeps the seat.
+        let first = expire_timer(&mut table, 0);
+        table.state.tick().await;
+        assert_eq!(timeouts(&table, &first), Some(1));
+        assert_eq!(table.state.players.count(), 3);
+
+        // Acting resets the consecutive timeouts.
+        let second = table
+            .state
+            .players
+            .active_player()
+            .unwrap()
+            .player_id
+            .clone();
+        table.state.players.active_player().unwrap().timeouts = 1;
+        table.call().await;
+        assert_eq!(timeouts(&table, &second), Some(0));
+
+        // After the configured number of timeouts the player leaves the table.
+        let third = expire_timer(&mut table, 1);
+        table.state.tick().await;
+        assert_eq!(timeouts(&table, &third), None);
+        assert_eq!(table.state.players.count(), 2);
+
+        // The betting round ends when the last player to act is removed.
+        let mut table = TestTable::with_config(
+            vec![100_000, 100_000, 100_000],
+            TableConfig {
+                max_timeouts: Some(1),
+                ..TableConfig::default()
+            },
+        );
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        table.call().await;
+        table.call().await;
+        table.check().await;
+        assert!(matches!(table.state.hand_state, HandState::FlopBetting));
+
+        table.check().await;
+        table.check().await;
+        let last = table.state.players.active_player().unwrap();
+        last.action_timer = Some(Instant::now() - Duration::from_secs(3_600));
+        table.state.tick().await;
+        assert_eq!(table.state.players.count(), 2);
+        assert!(matches!(table.state.hand_state, HandState::TurnBetting));
+    }
+
+    #[tokio::test]
+    async fn check_or_call() {
+        let mut table = TestTable::new(vec![100_000; 3]);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+        table.drain_players_message();
+
+        let offered = |table: &mut TestTable| {
+            let actions = table.state.action_options();
+            let has = |action| actions.iter().any(|o| o.action == action);
+            (has(PlayerAction::Check), has(PlayerAction::Call))
+        };
+        let last_action = |table: &TestTable| {
+            let (_, action, _) = table.state.betting_round.actions().last().unwrap();
+            *action
+        };
+
+        // Players facing the big blind can call but not check.
+        assert_eq!(offered(&mut table), (false, true));
+
+        // A check facing a bet is ignored.
+        let count = table.state.betting_round.actions().len();
+        table.check().await;
+        assert_eq!(table.state.betting_round.actions().len(), count);
+
+        table.call().await;
+        assert_eq!(last_action(&table), PlayerAction::Call);
+        table.call().await;
+        assert_eq!(last_action(&table), PlayerAction::Call);
+
+        // The big blind faces no bet and can check but not call.
+        assert_eq!(offered(&mut table), (true, false));
+
+        // A call facing no bet is ignored.
+        let count = table.state.betting_round.actions().len();
+        table.call().await;
+        assert_eq!(table.state.betting_round.actions().len(), count);
+
+        table.check().await;
+        assert!(matches!(table.state.hand_state, HandState::FlopBetting));
+
+        // On the flop the first player checks and the next one bets.
+        assert_eq!(offered(&mut table), (true, false));
+        table.check().await;
+        assert_eq!(last_action(&table), PlayerAction::Check);
+        table.bet(Chips::new(20_000)).await;
+
+        // The next player faces a bet and calls.
+        assert_eq!(offered(&mut table), (false, true));
+        table.call().await;
+        let (_, action, bet) = table.state.betting_round.actions().last().unwrap();
+        assert_eq!((*action, *bet), (PlayerAction::Call, Chips::new(20_000)));
+    }
+
+    #[tokio::test]
+    async fn heads_up_min_players() {
+        let mut table = TestTable::with_config(
+            vec![100_000; 2],
+            TableConfig {
+                min_players: Some(2),
+                ..TableConfig::default()
+            },
+        );
+
+        // Two players at a six seats table start the game.
+        table.state.seats = 6;
+        table.test_start_game().await;
+        table.test_start_hand().await;
+        assert!(matches!(table.state.hand_state, HandState::PreflopBetting));
+
+        // The button posts the small blind and acts first preflop.
+        let players = table.state.players.iter().collect::<Vec<_>>();
+        assert!(players[0].has_button);
+        assert!(matches!(players[0].action, PlayerAction::SmallBlind));
+        assert!(!players[1].has_button);
+        assert!(matches!(players[1].action, PlayerAction::BigBlind));
+        let button = players[0].player_id.clone();
+        let big_blind = players[1].player_id.clone();
+        let active_id = |table: &mut TestTable| {
+            table
+                .state
+                .players
+                .active_player()
+                .map(|p| p.player_id.clone())
+        };
+        assert_eq!(active_id(&mut table), Some(button.clone()));
+
+        // The big blind closes the preflop action and acts first after the flop.
+        table.call().await;
+        assert_eq!(active_id(&mut table), Some(big_blind.clone()));
+        table.check().await;
+        assert!(matches!(table.state.hand_state, HandState::FlopBetting));
+        assert_eq!(active_id(&mut table), Some(big_blind));
+        table.check().await;
+        assert_eq!(active_id(&mut table), Some(button));
+    }
+
+    #[tokio::test]
+    async fn spectator_hands() {
+        let config = TableConfig {
+            spectator_hands: true,
+            ..TableConfig::default()
+        };
+
+        let mut table = TestTable::with_config(vec![100_000, 100_000], config);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        let (tx, mut rx) = mpsc::channel(64);
+        let spectator = SigningKey::default().verifying_key().peer_id();
+        assert!(table.state.spectate(&spectator, tx).await);
+
+        let hands = [
+            (
+                Card::new(Rank::Ace, Suit::Spades),
+                Card::new(Rank::Queen, Suit::Spades),
+            ),
+            (
+                Card::new(Rank::Jack, Suit::Hearts),
+                Card::new(Rank::Ten, Suit::Hearts),
+            ),
+        ];
+        let board = [
+            Card::new(Rank::Ace, Suit::Hearts),
+            Card::new(Rank::Nine, Suit::Spades),
+            Card::new(Rank::Deuce, Suit::Hearts),
+            Card::new(Rank::Jack, Suit::Clubs),
+            Card::new(Rank::Trey, Suit::Diamonds),
+        ];
+        table.state.set_hand_cards(&hands, &board);
+        let player_ids = table
+            .state
+            .players
+            .iter()
+            .map(|p| p.player_id.clone())
+            .collect::<Vec<_>>();
+
+        // No made hands are sent while players are betting.
+        table.call().await;
+        table.check().await;
+        assert!(
+            !drain_messages(&mut rx)
+                .iter()
+                .any(|m| matches!(m.message(), Message::SpectatorHands { .. }))
+        );
+        table.drain_players_message();
+
+        // Players go all in on the flop and the board runs out.
+        table.bet(Chips::new(100_000)).await;
+        table.call().await;
+
+        // The spectator gets the made hands on the turn and river.
+        let made_hands = drain_messages(&mut rx)
+            .iter()
+            .filter_map(|m| match m.message() {
+                Message::SpectatorHands { hands } => Some(hands.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(made_hands.len(), 2);
+        for hands in &made_hands {
+            assert_eq!(hands.len(), 2);
+        }
+        assert!(made_hands[0].contains(&(player_ids[0].clone(), "ONE PAIR".to_string())));
+        assert!(made_hands[0].contains(&(player_ids[1].clone(), "ONE PAIR".to_string())));
+
+        // Seated players never get the made hands.
+        for p in table.players.iter_mut() {
+            assert!(
+                !drain_messages(&mut p.rx)
+                    .iter()
+                    .any(|m| matches!(m.message(), Message::SpectatorHands { .. }))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn antes() {
+        let mut table = TestTable::with_config(
+            vec![100_000; 3],
+            TableConfig {
+                ante_percent: 10,
+                ..TableConfig::default()
+            },
+        );
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        // All players post the ante before the blinds.
+        let ante = table.state.big_blind / 10;
+        let ids = table
+            .state
+            .players
+            .iter()
+            .map(|p| p.player_id.clone())
+            .collect::<Vec<_>>();
+        let expected = vec![
+            (ids[0].clone(), PlayerAction::Ante, ante),
+            (ids[1].clone(), PlayerAction::Ante, ante),
+            (ids[2].clone(), PlayerAction::Ante, ante),
+            (
+                ids[0].clone(),
+                PlayerAction::SmallBlind,
+                table.state.small_blind,
+            ),
+            (
+                ids[1].clone(),
+                PlayerAction::BigBlind,
+                table.state.big_blind,
+            ),
+        ];
+        assert_eq!(table.state.betting_round.actions(), expected);
+
+        // The antes are in the pot and don't count towards the bets.
+        assert_eq!(table.state.pots.len(), 1);
+        assert_eq!(table.state.pots[0].chips, ante * 3);
+        let player = table.state.players.iter().nth(2).unwrap();
+        assert_eq!(player.bet, Chips::ZERO);
+        assert_eq!(player.chips, Chips::new(100_000) - ante);
+        assert_eq!(
+            table.state.hand_log.starting_chips(&ids[2]),
+            Some(Chips::new(100_000))
+        );
+
+        // The ante doesn't close the action for the player after the big blind.
+        assert_eq!(
+            table.state.players.active_player().unwrap().player_id,
+            ids[2]
+        );
+        table.call().await;
+        table.call().await;
+        table.check().await;
+        assert!(matches!(table.state.hand_state, HandState::FlopBetting));
+        assert_eq!(
+            table.state.pots[0].chips,
+            ante * 3 + table.state.big_blind * 3
+        );
+    }
+
+    #[tokio::test]
+    async fn rabbit_hunt() {
+        let config = TableConfig {
+            rabbit_hunt: true,
+            ..TableConfig::default()
+        };
+
+        let mut table = TestTable::with_config(vec![100_000, 100_000], config);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+
+        let hole_cards = table
+            .state
+            .players
+            .iter()
+            .flat_map(|p| match p.hole_cards {
+                PlayerCards::Cards(c1, c2) => vec![c1, c2],
+                _ => vec![],
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(hole_cards.len(), 4);
+
+        // The small blind folds preflop.
+        table.fold().await;
+        assert!(matches!(table.state.hand_state, HandState::EndHand));
+        assert!(table.state.board.is_empty());
+        table.drain_players_message();
+
+        let chips = table
+            .state
+            .players
+            .iter()
+            .map(|p| p.chips)
+            .collect::<Vec<_>>();
+
+        let msg = table.players[0].msg(Message::RabbitHunt);
+        table.state.message(msg).await;
+
+        // All players see a full board dealt from the hand deck.
+        let mut boards = Vec::new();
+        for p in table.players.iter_mut() {
+            assert_message!(p, Message::RabbitHuntBoard { board }, || {
+                boards.push(board.clone());
+            });
+        }
+        assert_eq!(boards[0].len(), 5);
+        assert_eq!(boards[0], boards[1]);
+        assert!(boards[0].iter().all(|c| !hole_cards.contains(c)));
+
+        // The hunted board doesn't change the payoffs.
+        let after = table
+            .state
+            .players
+            .iter()
+            .map(|p| p.chips)
+            .collect::<Vec<_>>();
+        assert_eq!(after, chips);
+        assert!(table.state.board.is_empty());
+
+        // The board can be hunted only once per hand.
+        let msg = table.players[1].msg(Message::RabbitHunt);
+        table.state.message(msg).await;
+        for p in table.players.iter_mut() {
+            assert!(p.rx().is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn equity_budget() {
+        // Only one equity computation for the whole test.
+        let budget = Arc::new(EquityBudget::new(1, Duration::from_secs(3_600)));
+        let config = TableConfig {
+            all_in_equity: true,
+            equity_budget: Some(budget.clone()),
+            ..TableConfig::default()
+        };
+
+        let mut table = TestTable::with_config(vec![100_000, 100_000], config);
+        table.test_start_game().await;
+        table.test_start_hand().await;
+        table.drain_players_message();
+
+        // Players go all in preflop and the board runs out.
+        table.bet(Chips::new(100_000)).await;
+        table.call().await;
+
+        let msgs = drain_messages(&mut table.players[0].rx);
+        let updates = msgs
+            .iter()
+            .filter_map(|m| match m.message() {
+                Message::GameUpdate { players, board, .. }
+                    if players
+                        .iter()
+                        .all(|p| matches!(p.cards, PlayerCards::Cards(..))) =>
+                {
+                    let has_equities = players.iter().all(|p| p.equity.is_some());
+                    Some((board.len(), has_equities))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // The equities are computed once preflop, the later streets skip them
+        // but still show the players cards and the hand completes.
+        assert_eq!(updates.first(), Some(&(0, true)));
+        assert!(updates.contains(&(5, false)));
+        assert!(updates[1..].iter().all(|(_, has_equities)| !has_equities));
+        assert!(!budget.try_acquire());
+        assert!(
+            msgs.iter()
+                .any(|m| matches!(m.message(), Message::EndHand { .. }))
+        );
+    }
+
+    #[test]
+    fn river_equities() {
+        let hands = [
+            (
+                Card::new(Rank::Ace, Suit::Spades),
+                Card::new(Rank::Ace, Suit::Hearts),
+            ),
+            (
+                Card::new(Rank::King, Suit::Spades),
+                Card::new(Rank::King, Suit::Hearts),
+            ),
+        ];
+
+        let board = [
+            Card::new(Rank::Deuce, Suit::Clubs),
+            Card::new(Rank::Seven, Suit::Diamonds),
+            Card::new(Rank::Nine, Suit::Clubs),
+            Card::new(Rank::Jack, Suit::Hearts),
+            Card::new(Rank::Trey, Suit::Spades),
+        ];
+
+        // With the full board the best hand wins.
+        assert_eq!(all_in_equities(&hands, &board), vec![1.0, 0.0]);
+
+        // On the turn the kings need one of the two kings left in 44 cards.
+        let equities = all_in_equities(&hands, &board[..4]);
+        assert!((equities[1] - 2.0 / 44.0).abs() < 1e-6);
+        assert!((equities.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+
+        // Runouts are sampled preflop.
+        let equities = all_in_equities(&hands, &[]);
+        assert!((equities[0] - 0.82).abs() < 0.02);
+        assert!((equities.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+    }
 }
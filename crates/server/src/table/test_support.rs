@@ -0,0 +1,64 @@
+// Copyright (C) 2025 Vince Vasta
+// SPDX-License-Identifier: Apache-2.0
+
+//! Test support utilities for checking the messages sent to players.
+use tokio::sync::mpsc;
+
+use freezeout_core::message::SignedMessage;
+
+use super::TableMessage;
+
+/// Drains the messages sent to a player connection.
+///
+/// Only messages sent to the client are returned, connection control messages
+/// like throttle are skipped.
+pub fn drain_messages(rx: &mut mpsc::Receiver<TableMessage>) -> Vec<SignedMessage> {
+    let mut msgs = Vec::new();
+    while let Ok(msg) = rx.try_recv() {
+        if let TableMessage::Send(msg) = msg {
+            msgs.push(msg);
+        }
+    }
+
+    msgs
+}
+
+/// Checks that a player received exactly a sequence of messages.
+///
+/// Takes a player connection receiver and a list of message patterns with
+/// optional guards, panics showing the received messages if the messages don't
+/// match the patterns in order or if the number of messages is different:
+///
+/// ```ignore
+/// expect_messages!(&mut rx, [
+///     Message::StartHand { .. },
+///     Message::GameUpdate { board, .. } if board.len() == 3,
+/// ]);
+/// ```
+macro_rules! expect_messages {
+    ($rx:expr, [$($pattern:pat $(if $guard:expr)?),* $(,)?]) => {{
+        let msgs = $crate::table::test_support::drain_messages($rx);
+        let received = msgs.iter().map(|m| m.message()).collect::<Vec<_>>();
+
+        #[allow(unused_mut)]
+        let mut idx = 0;
+        $(
+            match received.get(idx).copied() {
+                Some($pattern) $(if $guard)? => {}
+                msg => panic!(
+                    "Message {idx} {msg:?} doesn't match `{}`, received messages {received:#?}",
+                    stringify!($pattern $(if $guard)?),
+                ),
+            }
+            idx += 1;
+        )*
+
+        assert_eq!(
+            received.len(),
+            idx,
+            "Expected {idx} messages, received messages {received:#?}"
+        );
+    }};
+}
+
+pub(crate) use expect_messages;
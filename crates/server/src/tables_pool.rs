@@ -2,20 +2,23 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! Tables pool.
+use ahash::AHashMap;
 use anyhow::Result;
-use log::error;
-use std::{collections::VecDeque, sync::Arc};
+use std::{collections::VecDeque, sync::Arc, time::Duration};
 use thiserror::Error;
-use tokio::sync::{Mutex, broadcast, mpsc};
+use tokio::{
+    sync::{Mutex, broadcast, mpsc},
+    time::Instant,
+};
 
 use freezeout_core::{
     crypto::{PeerId, SigningKey},
-    poker::Chips,
+    poker::{Chips, TableId},
 };
 
 use crate::{
     db::Db,
-    table::{Table, TableJoinError, TableMessage},
+    table::{Table, TableConfig, TableJoinError, TableMessage},
 };
 
 /// An error from table join operations.
@@ -27,6 +30,15 @@ pub enum TablesPoolsError {
     /// The player has already joined the table.
     #[error("player already joined")]
     AlreadyJoined,
+    /// Another player at the table has the same nickname.
+    #[error("nickname taken")]
+    NicknameTaken,
+    /// The player left a table too recently, with the time left to wait.
+    #[error("join cooldown")]
+    JoinCooldown(Duration),
+    /// The player has joined the maximum number of tables.
+    #[error("too many tables")]
+    TooManyTables(usize),
 }
 
 /// A pool of tables players can join.
@@ -37,13 +49,71 @@ pub struct TablesPool(Arc<Mutex<Shared>>);
 struct Shared {
     avail: VecDeque<Arc<Table>>,
     full: VecDeque<Arc<Table>>,
+    /// When players last left a table, used to enforce the join cooldown.
+    left: AHashMap<PeerId, Instant>,
+    join_cooldown: Duration,
+    /// Number of tables each player has joined, used to enforce the tables limit.
+    joined: AHashMap<PeerId, usize>,
+    max_tables_per_player: Option<usize>,
+    /// Tables holding the seat of disconnected players with the disconnection time.
+    held: AHashMap<PeerId, (Arc<Table>, Instant)>,
+    reconnect_grace: Duration,
+    /// Rematches waiting for all players to opt in.
+    rematches: Vec<Rematch>,
+    /// Players allowed to pause and resume tables.
+    admins: Vec<PeerId>,
+}
+
+impl Shared {
+    /// Decrements the number of tables a player is seated at.
+    fn seat_released(&mut self, player_id: &PeerId) {
+        if let Some(count) = self.joined.get_mut(player_id) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                self.joined.remove(player_id);
+            }
+        }
+    }
+
+    /// Releases the seats held for disconnected players that have not reconnected
+    /// within the reconnect grace period.
+    fn release_expired_seats(&mut self) {
+        let grace = self.reconnect_grace;
+        let mut expired = Vec::new();
+        self.held.retain(|player_id, (_, t)| {
+            let held = t.elapsed() < grace;
+            if !held {
+                expired.push(player_id.clone());
+            }
+            held
+        });
+
+        for player_id in expired {
+            self.seat_released(&player_id);
+        }
+    }
+}
+
+/// A rematch requested by some of the players of an ended game.
+#[derive(Debug)]
+struct Rematch {
+    /// The players who started the game.
+    players: Vec<PeerId>,
+    /// The players who opted in with their connection channel.
+    accepted: AHashMap<PeerId, mpsc::Sender<TableMessage>>,
+    /// When the first player opted in.
+    created: Instant,
 }
 
 impl TablesPool {
+    /// Time players have to opt into a rematch.
+    const REMATCH_WINDOW: Duration = Duration::from_secs(60);
+
     /// Creates a new table pool.
     pub fn new(
         tables: usize,
         seats: usize,
+        config: TableConfig,
         sk: Arc<SigningKey>,
         db: Db,
         shutdown_broadcast_tx: &broadcast::Sender<()>,
@@ -53,6 +123,7 @@ impl TablesPool {
             .map(|_| {
                 Arc::new(Table::new(
                     seats,
+                    config.clone(),
                     sk.clone(),
                     db.clone(),
                     shutdown_broadcast_tx.subscribe(),
@@ -64,6 +135,14 @@ impl TablesPool {
         let state = Shared {
             avail,
             full: VecDeque::with_capacity(tables),
+            left: AHashMap::default(),
+            join_cooldown: config.join_cooldown,
+            joined: AHashMap::default(),
+            max_tables_per_player: config.max_tables_per_player,
+            held: AHashMap::default(),
+            reconnect_grace: config.reconnect_grace,
+            rematches: Vec::default(),
+            admins: config.admins.clone(),
         };
 
         Self(Arc::new(Mutex::new(state)))
@@ -77,7 +156,26 @@ impl TablesPool {
         join_chips: Chips,
         table_tx: mpsc::Sender<TableMessage>,
     ) -> Result<Arc<Table>, TablesPoolsError> {
-        let mut pool = self.0.lock().await;
+        let mut guard = self.0.lock().await;
+        let pool = &mut *guard;
+
+        // Check the player is not joining too soon after leaving a table.
+        if let Some(left) = pool.left.get(player_id) {
+            let elapsed = left.elapsed();
+            if elapsed < pool.join_cooldown {
+                return Err(TablesPoolsError::JoinCooldown(pool.join_cooldown - elapsed));
+            }
+
+            pool.left.remove(player_id);
+        }
+
+        // Check the player has not joined too many tables.
+        pool.release_expired_seats();
+        if let Some(max_tables) = pool.max_tables_per_player
+            && pool.joined.get(player_id).copied().unwrap_or(0) >= max_tables
+        {
+            return Err(TablesPoolsError::TooManyTables(max_tables));
+        }
 
         // If there are no available tables try to find them.
         if pool.avail.is_empty() {
@@ -100,12 +198,19 @@ impl TablesPool {
                 Err(TableJoinError::AlreadyJoined) => {
                     return Err(TablesPoolsError::AlreadyJoined);
                 }
+                Err(TableJoinError::NicknameTaken) => {
+                    return Err(TablesPoolsError::NicknameTaken);
+                }
                 Err(_) => {
                     return Err(TablesPoolsError::NoTablesLeft);
                 }
                 _ => {}
             };
 
+            if pool.max_tables_per_player.is_some() {
+                *pool.joined.entry(player_id.clone()).or_default() += 1;
+            }
+
             // If no other player can join the table move it to the full queue.
             if !table.player_can_join().await {
                 let table = pool.avail.pop_front().unwrap();
@@ -118,12 +223,190 @@ impl TablesPool {
             Err(TablesPoolsError::NoTablesLeft)
         }
     }
+
+    /// A player opts into a rematch with the players of the game it played.
+    ///
+    /// When all players have opted in within the rematch window an empty table is
+    /// reserved for them and each player connection is sent a
+    /// [TableMessage::Rematch] with the table to join. If there are no empty
+    /// tables the rematch is cancelled and an error is returned.
+    pub async fn request_rematch(
+        &self,
+        player_id: &PeerId,
+        players: &[PeerId],
+        table_tx: mpsc::Sender<TableMessage>,
+    ) -> Result<(), TablesPoolsError> {
+        let mut guard = self.0.lock().await;
+        let pool = &mut *guard;
+
+        // Remove expired rematches.
+        pool.rematches
+            .retain(|r| r.created.elapsed() < Self::REMATCH_WINDOW);
+
+        let idx = match pool.rematches.iter().position(|r| r.players == players) {
+            Some(idx) => idx,
+            None => {
+                pool.rematches.push(Rematch {
+                    players: players.to_vec(),
+                    accepted: AHashMap::default(),
+                    created: Instant::now(),
+                });
+                pool.rematches.len() - 1
+            }
+        };
+
+        let rematch = &mut pool.rematches[idx];
+        rematch.accepted.insert(player_id.clone(), table_tx);
+        if players.iter().any(|p| !rematch.accepted.contains_key(p)) {
+            return Ok(());
+        }
+
+        let rematch = pool.rematches.swap_remove(idx);
+
+        // Find an empty table for the players.
+        let mut reserved = None;
+        for (idx, table) in pool.avail.iter().enumerate() {
+            if table.reserve(players).await {
+                reserved = pool.avail.remove(idx);
+                break;
+            }
+        }
+
+        if reserved.is_none() {
+            for (idx, table) in pool.full.iter().enumerate() {
+                if table.reserve(players).await {
+                    reserved = pool.full.remove(idx);
+                    break;
+                }
+            }
+        }
+
+        let Some(table) = reserved else {
+            return Err(TablesPoolsError::NoTablesLeft);
+        };
+
+        // Other players cannot join the reserved table.
+        pool.full.push_back(table.clone());
+
+        for (player_id, table_tx) in rematch.accepted {
+            if pool.max_tables_per_player.is_some() {
+                *pool.joined.entry(player_id).or_default() += 1;
+            }
+
+            let _ = table_tx.send(TableMessage::Rematch(table.clone())).await;
+        }
+
+        Ok(())
+    }
+
+    /// Withdraws a player from the rematches it opted into, used when the player
+    /// joins another table or disconnects before all players opted in.
+    pub async fn cancel_rematch(&self, player_id: &PeerId) {
+        let mut pool = self.0.lock().await;
+        for rematch in &mut pool.rematches {
+            rematch.accepted.remove(player_id);
+        }
+
+        pool.rematches.retain(|r| !r.accepted.is_empty());
+    }
+
+    /// Finds a table with a game in progress for a player to watch.
+    pub async fn spectate(
+        &self,
+        player_id: &PeerId,
+        table_tx: mpsc::Sender<TableMessage>,
+    ) -> Option<Arc<Table>> {
+        let pool = self.0.lock().await;
+        for table in pool.full.iter().chain(pool.avail.iter()) {
+            if table.spectate(player_id, table_tx.clone()).await {
+                return Some(table.clone());
+            }
+        }
+
+        None
+    }
+
+    /// An admin pauses a table at the end of the current hand or resumes it.
+    ///
+    /// Returns false if the player is not an admin, the table is not in this
+    /// pool, or the table was already paused or running.
+    pub async fn set_paused(&self, player_id: &PeerId, table_id: TableId, paused: bool) -> bool {
+        let table = {
+            let pool = self.0.lock().await;
+            if !pool.admins.contains(player_id) {
+                return false;
+            }
+
+            let table = pool
+                .full
+                .iter()
+                .chain(pool.avail.iter())
+                .find(|t| t.table_id() == table_id);
+            match table {
+                Some(table) => table.clone(),
+                None => return false,
+            }
+        };
+
+        table.set_paused(paused).await
+    }
+
+    /// Records that a player is no longer seated at a table it joined.
+    pub async fn table_left(&self, player_id: &PeerId) {
+        self.0.lock().await.seat_released(player_id);
+    }
+
+    /// Records that a player connection was lost while its seat is held at a
+    /// table so that the player can reconnect to it within the reconnect grace
+    /// period, the seat counts towards the player tables until it is released.
+    pub async fn player_disconnected(&self, player_id: &PeerId, table: Arc<Table>) {
+        let mut pool = self.0.lock().await;
+
+        // Remove expired entries so that the map doesn't grow unbounded.
+        pool.release_expired_seats();
+        pool.held.insert(player_id.clone(), (table, Instant::now()));
+    }
+
+    /// Tries to reconnect a player to the table that is holding its seat.
+    pub async fn reconnect(
+        &self,
+        player_id: &PeerId,
+        table_tx: mpsc::Sender<TableMessage>,
+    ) -> Option<Arc<Table>> {
+        // Don't hold the pool lock while waiting for the table.
+        let table = {
+            let mut pool = self.0.lock().await;
+            pool.release_expired_seats();
+            let (table, _) = pool.held.remove(player_id)?;
+            table
+        };
+
+        if table.reconnect(player_id, table_tx).await {
+            Some(table)
+        } else {
+            // The table has already released the seat.
+            self.table_left(player_id).await;
+            None
+        }
+    }
+
+    /// Records that a player has left a table to start the join cooldown.
+    pub async fn player_left(&self, player_id: &PeerId) {
+        let mut pool = self.0.lock().await;
+        if pool.join_cooldown.is_zero() {
+            return;
+        }
+
+        // Remove expired entries so that the map doesn't grow unbounded.
+        let cooldown = pool.join_cooldown;
+        pool.left.retain(|_, left| left.elapsed() < cooldown);
+        pool.left.insert(player_id.clone(), Instant::now());
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use freezeout_core::poker::TableId;
 
     struct TestPool {
         pool: TablesPool,
@@ -133,6 +416,10 @@ mod tests {
 
     impl TestPool {
         fn new(n: usize) -> Self {
+            Self::with_config(n, TableConfig::default())
+        }
+
+        fn with_config(n: usize, config: TableConfig) -> Self {
             let sk = SigningKey::default();
             let db = Db::open_in_memory().unwrap();
             let (shutdown_complete_tx, shutdown_complete_rx) = mpsc::channel(1);
@@ -140,6 +427,7 @@ mod tests {
             let pool = TablesPool::new(
                 n,
                 2,
+                config,
                 Arc::new(sk),
                 db,
                 &shutdown_broadcast_tx,
@@ -154,10 +442,13 @@ mod tests {
         }
 
         async fn join(&self, p: &TestPlayer) -> Option<Arc<Table>> {
+            self.try_join(p).await.ok()
+        }
+
+        async fn try_join(&self, p: &TestPlayer) -> Result<Arc<Table>, TablesPoolsError> {
             self.pool
                 .join(&p.peer_id, "nn", Chips::new(1_000_000), p.tx.clone())
                 .await
-                .ok()
         }
 
         async fn avail_ids(&self) -> Vec<TableId> {
@@ -183,7 +474,7 @@ mod tests {
 
     struct TestPlayer {
         tx: mpsc::Sender<TableMessage>,
-        _rx: mpsc::Receiver<TableMessage>,
+        rx: mpsc::Receiver<TableMessage>,
         peer_id: PeerId,
     }
 
@@ -192,11 +483,7 @@ mod tests {
             let sk = SigningKey::default();
             let peer_id = sk.verifying_key().peer_id();
             let (tx, rx) = mpsc::channel(64);
-            Self {
-                tx,
-                _rx: rx,
-                peer_id,
-            }
+            Self { tx, rx, peer_id }
         }
     }
 
@@ -282,4 +569,219 @@ mod tests {
         assert_eq!(tp.count_avail().await, N - 1);
         assert_eq!(tp.count_full().await, 1);
     }
+
+    #[tokio::test]
+    async fn join_cooldown() {
+        let config = TableConfig {
+            join_cooldown: Duration::from_millis(200),
+            ..TableConfig::default()
+        };
+        let tp = TestPool::with_config(2, config);
+
+        let p1 = TestPlayer::new();
+        let t1 = tp.join(&p1).await.unwrap();
+        t1.leave(&p1.peer_id).await;
+        tp.pool.player_left(&p1.peer_id).await;
+
+        // A rejoin within the cooldown is rejected.
+        let res = tp.try_join(&p1).await;
+        assert!(matches!(res, Err(TablesPoolsError::JoinCooldown(d)) if !d.is_zero()));
+
+        // Other players are not affected.
+        let p2 = TestPlayer::new();
+        assert!(tp.join(&p2).await.is_some());
+
+        // After the cooldown the player can join again.
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(tp.join(&p1).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn max_tables_per_player() {
+        let config = TableConfig {
+            max_tables_per_player: Some(1),
+            ..TableConfig::default()
+        };
+        let tp = TestPool::with_config(2, config);
+
+        // Player 1 joins a table and is at the limit.
+        let p1 = TestPlayer::new();
+        let t1 = tp.join(&p1).await.unwrap();
+
+        let res = tp.try_join(&p1).await;
+        assert!(matches!(res, Err(TablesPoolsError::TooManyTables(1))));
+
+        // Other players can still join.
+        let p2 = TestPlayer::new();
+        assert_eq!(tp.join(&p2).await.unwrap().table_id(), t1.table_id());
+
+        // Once player 1 leaves a table it can join another one.
+        tp.pool.table_left(&p1.peer_id).await;
+        let t2 = tp.join(&p1).await.unwrap();
+        assert_ne!(t2.table_id(), t1.table_id());
+    }
+
+    #[tokio::test]
+    async fn rematch() {
+        let tp = TestPool::new(2);
+
+        let mut p1 = TestPlayer::new();
+        let mut p2 = TestPlayer::new();
+        let t1 = tp.join(&p1).await.unwrap();
+        tp.join(&p2).await.unwrap();
+
+        let rematch_table = |p: &mut TestPlayer| {
+            let mut table = None;
+            while let Ok(msg) = p.rx.try_recv() {
+                if let TableMessage::Rematch(t) = msg {
+                    table = Some(t);
+                }
+            }
+            table
+        };
+
+        // The rematch waits for all players to opt in.
+        let players = vec![p1.peer_id.clone(), p2.peer_id.clone()];
+        tp.pool
+            .request_rematch(&p1.peer_id, &players, p1.tx.clone())
+            .await
+            .unwrap();
+        assert!(rematch_table(&mut p1).is_none());
+
+        tp.pool
+            .request_rematch(&p2.peer_id, &players, p2.tx.clone())
+            .await
+            .unwrap();
+
+        // Both players are sent the same new table.
+        let t2 = rematch_table(&mut p1).unwrap();
+        assert_eq!(rematch_table(&mut p2).unwrap().table_id(), t2.table_id());
+        assert_ne!(t2.table_id(), t1.table_id());
+
+        // Other players cannot join the reserved table.
+        let p3 = TestPlayer::new();
+        assert!(tp.join(&p3).await.is_none());
+        assert!(matches!(
+            t2.try_join(&p3.peer_id, "nn", Chips::new(1_000_000), p3.tx.clone())
+                .await,
+            Err(TableJoinError::TableFull)
+        ));
+
+        // The players are seated together.
+        for p in [&p1, &p2] {
+            t2.try_join(&p.peer_id, "nn", Chips::new(1_000_000), p.tx.clone())
+                .await
+                .unwrap();
+        }
+        assert!(!t2.player_can_join().await);
+    }
+
+    #[tokio::test]
+    async fn cancel_rematch() {
+        let tp = TestPool::new(2);
+
+        let mut p1 = TestPlayer::new();
+        let mut p2 = TestPlayer::new();
+        let players = vec![p1.peer_id.clone(), p2.peer_id.clone()];
+
+        // A player withdraws after opting in, the rematch starts only after it
+        // opts in again.
+        tp.pool
+            .request_rematch(&p1.peer_id, &players, p1.tx.clone())
+            .await
+            .unwrap();
+        tp.pool.cancel_rematch(&p1.peer_id).await;
+        assert!(tp.pool.0.lock().await.rematches.is_empty());
+
+        tp.pool
+            .request_rematch(&p2.peer_id, &players, p2.tx.clone())
+            .await
+            .unwrap();
+        assert!(p1.rx.try_recv().is_err());
+        assert!(p2.rx.try_recv().is_err());
+
+        tp.pool
+            .request_rematch(&p1.peer_id, &players, p1.tx.clone())
+            .await
+            .unwrap();
+        assert!(matches!(p1.rx.try_recv(), Ok(TableMessage::Rematch(_))));
+        assert!(matches!(p2.rx.try_recv(), Ok(TableMessage::Rematch(_))));
+    }
+
+    #[tokio::test]
+    async fn admin_pauses_table() {
+        let admin = TestPlayer::new();
+        let config = TableConfig {
+            admins: vec![admin.peer_id.clone()],
+            ..TableConfig::default()
+        };
+        let tp = TestPool::with_config(2, config);
+
+        let p1 = TestPlayer::new();
+        let p2 = TestPlayer::new();
+        let table = tp.join(&p1).await.unwrap();
+        tp.join(&p2).await.unwrap();
+        let table_id = table.table_id();
+
+        // Players that are not admins cannot pause the table.
+        assert!(!tp.pool.set_paused(&p1.peer_id, table_id, true).await);
+
+        // An admin that is not seated at the table pauses it.
+        assert!(tp.pool.set_paused(&admin.peer_id, table_id, true).await);
+        assert!(!tp.pool.set_paused(&admin.peer_id, table_id, true).await);
+
+        // Unknown tables are ignored.
+        assert!(
+            !tp.pool
+                .set_paused(&admin.peer_id, TableId::new_id(), false)
+                .await
+        );
+
+        // The admin resumes the table.
+        assert!(tp.pool.set_paused(&admin.peer_id, table_id, false).await);
+    }
+
+    #[tokio::test]
+    async fn held_seat_counts_towards_tables() {
+        const GRACE: Duration = Duration::from_secs(20);
+
+        let config = TableConfig {
+            max_tables_per_player: Some(1),
+            reconnect_grace: GRACE,
+            ..TableConfig::default()
+        };
+        let tp = TestPool::with_config(2, config);
+
+        let p1 = TestPlayer::new();
+        let p2 = TestPlayer::new();
+        let t1 = tp.join(&p1).await.unwrap();
+        tp.join(&p2).await.unwrap();
+
+        // The player connection is lost while the game is running, the held seat
+        // still counts towards the player tables.
+        assert!(t1.disconnect(&p1.peer_id).await);
+        tp.pool.player_disconnected(&p1.peer_id, t1.clone()).await;
+        let res = tp.try_join(&p1).await;
+        assert!(matches!(res, Err(TablesPoolsError::TooManyTables(1))));
+
+        // The player takes back its seat.
+        let table = tp.pool.reconnect(&p1.peer_id, p1.tx.clone()).await.unwrap();
+        assert_eq!(table.table_id(), t1.table_id());
+        assert_eq!(tp.pool.0.lock().await.joined.get(&p1.peer_id), Some(&1));
+
+        // The seat is released when the player doesn't reconnect in time.
+        assert!(t1.disconnect(&p1.peer_id).await);
+        tp.pool.player_disconnected(&p1.peer_id, t1.clone()).await;
+        tp.pool.0.lock().await.held.get_mut(&p1.peer_id).unwrap().1 =
+            Instant::now() - GRACE - Duration::from_secs(1);
+
+        let t2 = tp.join(&p1).await.unwrap();
+        assert_ne!(t2.table_id(), t1.table_id());
+        assert!(
+            tp.pool
+                .reconnect(&p1.peer_id, p1.tx.clone())
+                .await
+                .is_none()
+        );
+    }
 }